@@ -109,7 +109,10 @@ pub struct ApiCfg {
     pub log_path: PathBuf,
     /// Location of Builder encryption keys
     pub key_path: KeyCache,
+    /// Package targets served by this builder, requests for any other target are rejected
     pub targets: Vec<PackageTarget>,
+    /// Target of requests that don't specify one, it has to be one of `targets`
+    pub default_target: PackageTarget,
     #[serde(with = "deserialize_into_vec")]
     pub features_enabled: Vec<String>,
     pub private_max_age: usize,
@@ -120,6 +123,17 @@ pub struct ApiCfg {
     pub unrestricted_channels: Vec<String>,
     pub partially_unrestricted_channels: Vec<String>,
     pub restricted_if_present: Vec<String>,
+    /// How often, in seconds, expired channels are reaped. Zero disables the reaper.
+    pub channel_reaper_interval: u64,
+    /// How often, in seconds, origin retention policies are applied. Zero disables them.
+    pub retention_interval: u64,
+    /// How often, in seconds, the package store is checked against the database. Zero
+    /// disables the check.
+    pub storage_check_interval: u64,
+    /// Remove orphaned objects and stale temp files instead of only reporting them
+    pub storage_check_remove: bool,
+    /// Download every package during the check to compare its checksum
+    pub storage_check_verify_checksums: bool,
 }
 
 mod deserialize_into_vec {
@@ -146,8 +160,10 @@ impl Default for ApiCfg {
                  log_path: env::temp_dir(),
                  key_path: KeyCache::new(key_path),
                  targets: vec![target::X86_64_LINUX,
+                               target::AARCH64_LINUX,
                                target::X86_64_LINUX_KERNEL2,
                                target::X86_64_WINDOWS,],
+                 default_target: target::X86_64_LINUX,
                  features_enabled: vec!["jobsrv".to_string()],
                  private_max_age: 300,
                  saas_bldr_url: "https://bldr.habitat.sh".to_string(),
@@ -156,7 +172,12 @@ impl Default for ApiCfg {
                  allowed_users_for_origin_create: vec![],
                  unrestricted_channels: vec![],
                  partially_unrestricted_channels: vec![],
-                 restricted_if_present: vec![] }
+                 restricted_if_present: vec![],
+                 channel_reaper_interval: 300,
+                 retention_interval: 86400,
+                 storage_check_interval: 0,
+                 storage_check_remove: false,
+                 storage_check_verify_checksums: false }
     }
 }
 
@@ -411,11 +432,16 @@ mod tests {
         data_path = "/hab/svc/bio-depot/data"
         log_path = "/hab/svc/bio-depot/var/log"
         key_path = "/hab/svc/bio-depot/files"
-        targets = ["x86_64-linux", "x86_64-linux-kernel2", "x86_64-windows"]
+        targets = ["x86_64-linux", "aarch64-linux", "x86_64-windows"]
+        default_target = "aarch64-linux"
         features_enabled = "foo, bar"
         private_max_age = 400
         suppress_autobuild_origins = ["origin1", "origin2"]
         allowed_users_for_origin_create = ["super1", "super2"]
+        channel_reaper_interval = 60
+        retention_interval = 3600
+        storage_check_interval = 604800
+        storage_check_remove = true
 
         [http]
         listen = "0:0:0:0:0:0:0:1"
@@ -485,8 +511,9 @@ mod tests {
 
         assert_eq!(config.api.targets.len(), 3);
         assert_eq!(config.api.targets[0], target::X86_64_LINUX);
-        assert_eq!(config.api.targets[1], target::X86_64_LINUX_KERNEL2);
+        assert_eq!(config.api.targets[1], target::AARCH64_LINUX);
         assert_eq!(config.api.targets[2], target::X86_64_WINDOWS);
+        assert_eq!(config.api.default_target, target::AARCH64_LINUX);
 
         assert_eq!(&config.api.allowed_users_for_origin_create,
                    &["super1".to_string(), "super2".to_string()]);
@@ -494,6 +521,11 @@ mod tests {
         assert_eq!(&config.api.features_enabled,
                    &["FOO".to_string(), "BAR".to_string()]);
         assert_eq!(config.api.private_max_age, 400);
+        assert_eq!(config.api.channel_reaper_interval, 60);
+        assert_eq!(config.api.retention_interval, 3600);
+        assert_eq!(config.api.storage_check_interval, 604800);
+        assert!(config.api.storage_check_remove);
+        assert!(!config.api.storage_check_verify_checksums);
 
         assert_eq!(&format!("{}", config.http.listen), "::1");
 
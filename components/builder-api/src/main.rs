@@ -26,7 +26,8 @@ use builder_core::config::ConfigFile;
 use biome_builder_api as bldr_api;
 
 use crate::bldr_api::{config::Config,
-                      server};
+                      server::{self,
+                               services::storage_check::StorageCheck}};
 
 const VERSION: &str = include_str!(concat!(env!("OUT_DIR"), "/VERSION"));
 
@@ -35,7 +36,21 @@ async fn main() {
     env_logger::init();
     let matches = app().get_matches();
     debug!("CLI matches: {:?}", matches);
-    match server::run(config_from_args(&matches)).await {
+    let result = match matches.subcommand() {
+        ("fsck", Some(args)) => {
+            let opts = StorageCheck { remove:           args.is_present("remove"),
+                                      verify_checksums: args.is_present("verify_checksums"), };
+            server::fsck(config_from_args(&matches), opts).await
+        }
+        ("import-advisories", Some(args)) => {
+            let files = args.values_of("FILE")
+                            .map(|files| files.map(PathBuf::from).collect::<Vec<_>>())
+                            .unwrap_or_default();
+            server::import_advisories(config_from_args(&matches), &files)
+        }
+        _ => server::run(config_from_args(&matches)).await,
+    };
+    match result {
         Ok(_) => std::process::exit(0),
         Err(e) => exit_with(e, 1),
     }
@@ -55,6 +70,24 @@ fn app<'a, 'b>() -> clap::App<'a, 'b> {
                 "Filepath to store packages, keys, and other artifacts.")
             (@arg port: --port +takes_value "Listen port. [default: 9636]")
         )
+        (@subcommand fsck =>
+            (about: "Check the package store against the database")
+            (@arg config: -c --config +takes_value
+                "Filepath to configuration file.")
+            (@arg path: -p --path +takes_value
+                "Filepath to store packages, keys, and other artifacts.")
+            (@arg remove: --remove
+                "Remove orphaned objects and stale temp files instead of only reporting them.")
+            (@arg verify_checksums: --("verify-checksums")
+                "Download every package to compare its checksum.")
+        )
+        (@subcommand ("import-advisories") =>
+            (about: "Import vulnerability advisories from OSV JSON files")
+            (@arg config: -c --config +takes_value
+                "Filepath to configuration file.")
+            (@arg FILE: +required +multiple
+                "OSV file holding an advisory or a list of advisories.")
+        )
     )
 }
 
@@ -42,6 +42,7 @@ pub enum Error {
     Conflict,
     CreateBucketError(RusotoError<rusoto_s3::CreateBucketError>),
     DbError(db::error::Error),
+    DeleteObject(RusotoError<rusoto_s3::DeleteObjectError>),
     DieselError(diesel::result::Error),
     Github(HubError),
     BiomeCore(bio_core::Error),
@@ -50,6 +51,7 @@ pub enum Error {
     InnerError(io::IntoInnerError<io::BufWriter<fs::File>>),
     IO(io::Error),
     ListBuckets(RusotoError<rusoto_s3::ListBucketsError>),
+    ListObjects(RusotoError<rusoto_s3::ListObjectsV2Error>),
     MultipartCompletion(RusotoError<rusoto_s3::CompleteMultipartUploadError>),
     MultipartUploadReq(RusotoError<rusoto_s3::CreateMultipartUploadError>),
     NotFound,
@@ -80,6 +82,7 @@ impl fmt::Display for Error {
             Error::Conflict => "Entity conflict".to_string(),
             Error::CreateBucketError(ref e) => format!("{}", e),
             Error::DbError(ref e) => format!("{}", e),
+            Error::DeleteObject(ref e) => format!("{}", e),
             Error::DieselError(ref e) => format!("{}", e),
             Error::Github(ref e) => format!("{}", e),
             Error::BiomeCore(ref e) => format!("{}", e),
@@ -88,6 +91,7 @@ impl fmt::Display for Error {
             Error::InnerError(ref e) => format!("{}", e.error()),
             Error::IO(ref e) => format!("{}", e),
             Error::ListBuckets(ref e) => format!("{}", e),
+            Error::ListObjects(ref e) => format!("{}", e),
             Error::MultipartCompletion(ref e) => format!("{}", e),
             Error::MultipartUploadReq(ref e) => format!("{}", e),
             Error::NotFound => "Entity not found".to_string(),
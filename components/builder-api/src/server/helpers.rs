@@ -1,22 +1,36 @@
 use crate::{db::models::{channel::PackageChannelTrigger as PCT,
                          origin::OriginMemberRole,
-                         package::PackageVisibility},
+                         package::{BuilderPackageIdent,
+                                   GetPackageGroup,
+                                   Package,
+                                   PackageVisibility}},
             bio_core::package::PackageTarget,
+            config::ApiCfg,
             server::{authorize::authorize_session,
                      AppState}};
-use actix_web::{http::header,
+use actix_web::{http::{header,
+                       StatusCode},
                 web::Query,
                 HttpRequest,
                 HttpResponse};
 use chrono::{NaiveDate,
              NaiveDateTime};
+use diesel::{pg::PgConnection,
+             result::QueryResult};
 use regex::Regex;
 use serde::Serialize;
 use serde_json::Value;
-use std::str::FromStr;
+use std::{collections::{BTreeMap,
+                        HashMap},
+          str::FromStr};
 // TODO - this module should not just be a grab bag of stuff
 
 pub const PAGINATION_RANGE_MAX: isize = 50;
+pub const STATS_DAYS_DEFAULT: i64 = 30;
+pub const STATS_DAYS_MAX: i64 = 365;
+pub const LABELS_MAX: usize = 32;
+const LABEL_KEY_MAX_LEN: usize = 63;
+const LABEL_VALUE_MAX_LEN: usize = 255;
 
 #[derive(Deserialize)]
 pub struct Target {
@@ -24,6 +38,30 @@ pub struct Target {
     pub target: Option<String>,
 }
 
+#[derive(Deserialize)]
+pub struct StatsQuery {
+    #[serde(default)]
+    pub target: Option<String>,
+    #[serde(default)]
+    pub days:   Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct SbomQuery {
+    #[serde(default)]
+    pub target: Option<String>,
+    // `cyclonedx` or `spdx`, CycloneDX when unset
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct LabelQuery {
+    // Comma separated `key=value` pairs
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct Pagination {
     #[serde(default)]
@@ -111,43 +149,122 @@ pub fn extract_pagination_in_pages(pagination: &Query<Pagination>) -> (isize, is
     (pagination.range / PAGINATION_RANGE_MAX + 1, PAGINATION_RANGE_MAX)
 }
 
-// TODO: Deprecate getting target from User Agent header
-pub fn target_from_headers(req: &HttpRequest) -> PackageTarget {
-    let user_agent_header = match req.headers().get(header::USER_AGENT) {
-        Some(s) => s,
-        None => return PackageTarget::from_str("x86_64-linux").unwrap(),
+// Returns the first day of a statistics window of `days` days ending today, or None when
+// the window is out of range
+pub fn stats_since(days: Option<i64>) -> Option<NaiveDate> {
+    let days = days.unwrap_or(STATS_DAYS_DEFAULT);
+    if !(1..=STATS_DAYS_MAX).contains(&days) {
+        return None;
+    }
+    Some(chrono::Utc::now().date_naive() - chrono::Duration::days(days - 1))
+}
+
+// Label keys are short lowercase identifiers like `team` or `tier`, values are free form
+pub fn validate_label(key: &str, value: &str) -> Result<(), String> {
+    let valid_key = key.len() <= LABEL_KEY_MAX_LEN
+                    && key.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+                    && key.chars().all(|c| {
+                                      c.is_ascii_lowercase()
+                                      || c.is_ascii_digit()
+                                      || c == '.'
+                                      || c == '_'
+                                      || c == '-'
+                                  });
+    if !valid_key {
+        return Err(format!("Invalid label key '{}'", key));
+    }
+    if value.is_empty()
+       || value.len() > LABEL_VALUE_MAX_LEN
+       || value.chars().any(char::is_control)
+    {
+        return Err(format!("Invalid value for label '{}'", key));
+    }
+    Ok(())
+}
+
+pub fn validate_labels(labels: &BTreeMap<String, String>) -> Result<(), String> {
+    if labels.len() > LABELS_MAX {
+        return Err(format!("At most {} labels can be set", LABELS_MAX));
+    }
+    labels.iter()
+          .try_for_each(|(key, value)| validate_label(key, value))
+}
+
+// Parses a `key=value,key=value` label filter
+pub fn label_filter(filter: Option<&str>) -> Result<Vec<(String, String)>, String> {
+    let filter = match filter {
+        Some(filter) => filter,
+        None => return Ok(Vec::new()),
     };
 
-    let user_agent = match user_agent_header.to_str() {
-        Ok(s) => (*s).to_string(),
-        Err(_) => return PackageTarget::from_str("x86_64-linux").unwrap(),
+    filter.split(',')
+          .map(|label| {
+              match label.split_once('=') {
+                  Some((key, value)) => {
+                      validate_label(key, value)?;
+                      Ok((key.to_string(), value.to_string()))
+                  }
+                  None => Err(format!("Invalid label filter '{}'", label)),
+              }
+          })
+          .collect()
+}
+
+// Parses the target given in the query, falling back to the User Agent header when there is
+// none. Targets this builder doesn't serve are answered with a 422.
+pub fn requested_target(req: &HttpRequest,
+                        target: Option<&str>)
+                        -> Result<PackageTarget, HttpResponse> {
+    match target {
+        Some(t) => {
+            trace!("Query requested target = {}", t);
+            enabled_target(&req_state(req).config.api, t)
+        }
+        None => target_from_headers(req),
+    }
+}
+
+// TODO: Deprecate getting target from User Agent header
+pub fn target_from_headers(req: &HttpRequest) -> Result<PackageTarget, HttpResponse> {
+    // Tools that don't put a platform in their User Agent, e.g. curl, get the default target.
+    // All of our tooling does, or specifies a target in the query.
+    let user_agent = match req.headers().get(header::USER_AGENT).map(|h| h.to_str()) {
+        Some(Ok(s)) => s.to_string(),
+        _ => return Ok(default_target(req)),
     };
 
     trace!("Parsing target from UserAgent header: {}", &user_agent);
 
+    match user_agent_target(&user_agent) {
+        Some(target) => enabled_target(&req_state(req).config.api, target),
+        None => Ok(default_target(req)),
+    }
+}
+
+// The target in a User Agent like `bio/1.6.0 (x86_64-linux; 5.4.0)`
+fn user_agent_target(user_agent: &str) -> Option<&str> {
     let user_agent_regex =
         Regex::new(r"(?P<client>[^\s]+)\s?(\((?P<target>\w+-\w+); (?P<kernel>.*)\))?").unwrap();
 
-    let target = match user_agent_regex.captures(&user_agent) {
-        Some(user_agent_capture) => {
-            if let Some(target_match) = user_agent_capture.name("target") {
-                target_match.as_str().to_string()
-            } else {
-                return PackageTarget::from_str("x86_64-linux").unwrap();
-            }
-        }
-        None => return PackageTarget::from_str("x86_64-linux").unwrap(),
-    };
+    user_agent_regex.captures(user_agent)
+                    .and_then(|captures| captures.name("target"))
+                    .map(|target| target.as_str())
+}
+
+// The target of requests that don't ask for one
+pub fn default_target(req: &HttpRequest) -> PackageTarget {
+    req_state(req).config.api.default_target
+}
 
-    // All of our tooling that depends on this function to return a target will have a user
-    // agent that includes the platform, or will specify a target in the query.
-    // Therefore, if we can't find a valid target, it's safe to assume that some other kind of HTTP
-    // tool is being used, e.g. curl, with looser constraints. For those kinds of cases,
-    // let's default it to Linux instead of returning a bad request if we can't properly parse
-    // the inbound target.
-    match PackageTarget::from_str(&target) {
-        Ok(t) => t,
-        Err(_) => PackageTarget::from_str("x86_64-linux").unwrap(),
+// Parses a target and checks it is one of the targets enabled in the config
+pub fn enabled_target(api: &ApiCfg, target: &str) -> Result<PackageTarget, HttpResponse> {
+    match PackageTarget::from_str(target) {
+        Ok(t) if api.targets.contains(&t) => Ok(t),
+        result => {
+            debug!("Unsupported target requested: {}, parsed = {:?}", target, result);
+            Err(HttpResponse::build(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(format!("Unsupported package target '{}'", target)))
+        }
     }
 }
 
@@ -254,6 +371,33 @@ pub fn trigger_from_request_model(req: &HttpRequest) -> PCT {
     PCT::Unknown
 }
 
+/// Gets the releases of `target` in a group of idents that the requester can see. The
+/// releases may live in other origins, so visibility is checked per origin.
+pub fn visible_package_group(req: &HttpRequest,
+                             opt_session_id: Option<u64>,
+                             idents: Vec<BuilderPackageIdent>,
+                             target: PackageTarget,
+                             conn: &mut PgConnection)
+                             -> QueryResult<Vec<Package>> {
+    let packages = Package::get_group(GetPackageGroup { pkgs:       idents,
+                                                        visibility: PackageVisibility::all(), },
+                                      conn)?;
+
+    let mut origin_visibility: HashMap<String, Vec<PackageVisibility>> = HashMap::new();
+    Ok(packages.into_iter()
+               .filter(|pkg| *pkg.target == target)
+               .filter(|pkg| {
+                   origin_visibility.entry(pkg.origin.clone())
+                                    .or_insert_with(|| {
+                                        visibility_for_optional_session(req,
+                                                                        opt_session_id,
+                                                                        &pkg.origin)
+                                    })
+                                    .contains(&pkg.visibility)
+               })
+               .collect())
+}
+
 pub fn req_state(req: &HttpRequest) -> &AppState {
     req.app_data::<actix_web::web::Data<AppState>>()
        .expect("request state")
@@ -276,3 +420,36 @@ mod ymd_date_format {
         Ok(naive_date.and_hms_opt(0, 0, 0).unwrap())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bio_core::package::target;
+
+    #[test]
+    fn enabled_targets_are_accepted() {
+        let api = ApiCfg { targets: vec![target::X86_64_LINUX, target::AARCH64_LINUX],
+                           ..Default::default() };
+
+        assert_eq!(enabled_target(&api, "aarch64-linux").unwrap(), target::AARCH64_LINUX);
+    }
+
+    #[test]
+    fn unsupported_targets_are_rejected() {
+        let api = ApiCfg { targets: vec![target::X86_64_LINUX],
+                           ..Default::default() };
+
+        for t in &["x86_64-windows", "sparc-solaris", ""] {
+            let resp = enabled_target(&api, t).unwrap_err();
+            assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+
+    #[test]
+    fn target_from_user_agent() {
+        assert_eq!(user_agent_target("bio/1.6.0/20200420200029 (x86_64-windows; 10.0.17763)"),
+                   Some("x86_64-windows"));
+        assert_eq!(user_agent_target("bio/1.6.0 (aarch64-linux; 5.4.0)"), Some("aarch64-linux"));
+        assert_eq!(user_agent_target("curl/7.68.0"), None);
+    }
+}
@@ -17,8 +17,12 @@ use self::{framework::middleware::authentication_middleware,
                        profile::Profile,
                        settings::Settings,
                        user::User},
-           services::{memcache::MemcacheClient,
-                      s3::S3Handler}};
+           services::{channel_reaper,
+                      memcache::MemcacheClient,
+                      retention,
+                      s3::S3Handler,
+                      storage_check::{self,
+                                      StorageCheck}}};
 use crate::{bldr_core::keys,
             config::{Config,
                      GatewayCfg},
@@ -43,6 +47,7 @@ use resources::jobs::Jobs;
 use std::{cell::RefCell,
           collections::HashMap,
           iter::FromIterator,
+          path::PathBuf,
           sync::Arc,
           time::Duration};
 
@@ -119,6 +124,39 @@ fn enable_features(config: &Config) {
 /// Returns a status 200 on success. Any non-200 responses are an outage or a partial outage.
 pub async fn status() -> HttpResponse { HttpResponse::new(StatusCode::OK) }
 
+/// Checks the package store against the database once and prints the report.
+pub async fn fsck(config: Config, opts: StorageCheck) -> error::Result<()> {
+    enable_features(&config);
+
+    if feat::is_enabled(feat::Artifactory) {
+        println!("Packages are stored in Artifactory, there is no package store to check");
+        return Ok(());
+    }
+
+    let db_pool = DbPool::new(&config.datastore.clone());
+    let packages = S3Handler::new(config.s3.clone());
+
+    let report = storage_check::check(&config, &db_pool, &packages, opts).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+/// Imports vulnerability advisories from OSV files and prints what was imported.
+pub fn import_advisories(config: Config, files: &[PathBuf]) -> error::Result<()> {
+    let mut advisories = Vec::new();
+    for file in files {
+        advisories.extend(resources::advisories::read_file(file)?);
+    }
+
+    let db_pool = DbPool::new(&config.datastore.clone());
+    let mut conn = db_pool.get_conn()?;
+    migration::setup(&mut conn)?;
+
+    let report = resources::advisories::import(advisories, &mut conn)?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
 pub async fn run(config: Config) -> error::Result<()> {
     enable_features(&config);
 
@@ -129,6 +167,11 @@ pub async fn run(config: Config) -> error::Result<()> {
     if let Err(e) = keys::get_latest_builder_key(&config.api.key_path) {
         panic!("Failed to get the builder encryption key, error = {}", e);
     }
+    if !config.api.targets.contains(&config.api.default_target) {
+        panic!("The default target {} is not one of the enabled targets",
+               config.api.default_target);
+    }
+
     let mut conn = db_pool.get_conn().unwrap();
     migration::setup(&mut conn).unwrap();
     migrations::migrate_to_encrypted(&mut conn, &config.api.key_path).unwrap();
@@ -158,6 +201,10 @@ pub async fn run(config: Config) -> error::Result<()> {
         }
     }
 
+    channel_reaper::start(&config, db_pool.clone());
+    retention::start(&config, db_pool.clone());
+    storage_check::start(&config, db_pool.clone());
+
     let mut srv = HttpServer::new(move || {
                       let app_state = match AppState::new(&config, db_pool.clone()) {
                           Ok(state) => state,
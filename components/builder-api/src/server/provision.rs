@@ -72,7 +72,8 @@ pub fn provision_bldr_environment(app_state: &AppState) -> Result<String, Error>
     {
         let new_channel = CreateChannel { name: channel,
                                           origin,
-                                          owner_id: account.id };
+                                          owner_id: account.id,
+                                          expires_at: None };
 
         match Channel::create(&new_channel, &mut conn) {
             Ok(_) => {}
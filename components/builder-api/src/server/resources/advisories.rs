@@ -0,0 +1,464 @@
+// Biome project based on Chef Habitat's code (c) 2016-2020 Chef Software, Inc
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Vulnerability advisories in the OSV format (https://ossf.github.io/osv-schema/).
+//!
+//! Advisories are imported from files, so no network access is needed. Only the affected
+//! packages of the `Biome` ecosystem, or with a `pkg:biome/` purl, are kept. Their names are
+//! `origin/name` and their versions are compared the way package versions are.
+
+use std::{collections::{BTreeSet,
+                        HashMap},
+          fs,
+          path::Path};
+
+use chrono::DateTime;
+use diesel::PgConnection;
+use serde_json::Value;
+
+use crate::{bio_core::package::PackageIdent,
+            db::models::{advisories::{Advisory,
+                                      AdvisoryRange,
+                                      NewAdvisory},
+                         package::BuilderPackageIdent},
+            server::error::{Error,
+                            Result}};
+
+const ECOSYSTEM: &str = "Biome";
+const PURL_PREFIX: &str = "pkg:biome/";
+
+#[derive(Debug, Deserialize)]
+struct OsvAdvisory {
+    id:                String,
+    #[serde(default)]
+    summary:           Option<String>,
+    #[serde(default)]
+    details:           Option<String>,
+    #[serde(default)]
+    aliases:           Vec<String>,
+    #[serde(default)]
+    published:         Option<String>,
+    #[serde(default)]
+    modified:          Option<String>,
+    #[serde(default)]
+    withdrawn:         Option<String>,
+    #[serde(default)]
+    severity:          Vec<OsvSeverity>,
+    #[serde(default)]
+    affected:          Vec<OsvAffected>,
+    #[serde(default)]
+    database_specific: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvSeverity {
+    #[serde(rename = "type")]
+    kind:  String,
+    score: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvAffected {
+    #[serde(default)]
+    package:  Option<OsvPackage>,
+    #[serde(default)]
+    ranges:   Vec<OsvRange>,
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvPackage {
+    #[serde(default)]
+    ecosystem: String,
+    #[serde(default)]
+    name:      String,
+    #[serde(default)]
+    purl:      Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvRange {
+    #[serde(rename = "type")]
+    kind:   String,
+    #[serde(default)]
+    events: Vec<OsvEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvEvent {
+    #[serde(default)]
+    introduced:    Option<String>,
+    #[serde(default)]
+    fixed:         Option<String>,
+    #[serde(default)]
+    last_affected: Option<String>,
+}
+
+// An advisory as read from an OSV file. Withdrawn ones have no ranges.
+#[derive(Debug, PartialEq)]
+pub(crate) struct OsvImport {
+    pub advisory:  NewAdvisory,
+    pub withdrawn: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct ImportReport {
+    pub imported:  Vec<String>,
+    pub withdrawn: Vec<String>,
+    // Advisories without any affected Biome package or any range that isn't a git one
+    pub skipped:   Vec<String>,
+}
+
+// An advisory that applies to a release, or to one of its transitive dependencies
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct AdvisoryMatch {
+    pub id:         String,
+    pub summary:    Option<String>,
+    pub severity:   Option<String>,
+    pub aliases:    Vec<String>,
+    // The affected release
+    pub affected:   String,
+    pub transitive: bool,
+    // Versions the affected package is fixed in
+    pub fixed:      Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PackageAdvisories {
+    pub ident:      String,
+    pub target:     String,
+    pub advisories: Vec<AdvisoryMatch>,
+}
+
+/// Reads an OSV file holding one advisory or a list of them.
+pub(crate) fn read_file(path: &Path) -> Result<Vec<OsvImport>> {
+    let contents = fs::read_to_string(path)?;
+    parse(&contents)
+}
+
+pub(crate) fn parse(contents: &str) -> Result<Vec<OsvImport>> {
+    let osv = match serde_json::from_str::<Value>(contents)? {
+        Value::Array(list) => list,
+        advisory => vec![advisory],
+    };
+    osv.into_iter()
+       .map(|advisory| Ok(from_osv(serde_json::from_value(advisory)?)))
+       .collect()
+}
+
+fn from_osv(osv: OsvAdvisory) -> OsvImport {
+    let mut ranges = Vec::new();
+
+    // Withdrawn advisories are kept without ranges so they match nothing
+    let withdrawn = osv.withdrawn.is_some();
+    let affected = if withdrawn { Vec::new() } else { osv.affected };
+    for affected in affected {
+        let package = match affected.package.as_ref().and_then(package_name) {
+            Some(package) => package,
+            None => continue,
+        };
+
+        for range in affected.ranges.iter().filter(|r| r.kind != "GIT") {
+            let mut current: Option<AdvisoryRange> = None;
+            for event in range.events.iter() {
+                if let Some(ref introduced) = event.introduced {
+                    ranges.extend(current.take());
+                    // `0` stands for every version before the first fix
+                    let introduced = Some(introduced.clone()).filter(|v| v.as_str() != "0");
+                    current = Some(AdvisoryRange { package: package.clone(),
+                                                   introduced,
+                                                   ..Default::default() });
+                } else if let Some(mut range) = current.take() {
+                    range.fixed = event.fixed.clone();
+                    range.last_affected = event.last_affected.clone();
+                    ranges.push(range);
+                }
+            }
+            ranges.extend(current.take());
+        }
+
+        if !affected.versions.is_empty() {
+            ranges.push(AdvisoryRange { package,
+                                        versions: affected.versions,
+                                        ..Default::default() });
+        }
+    }
+
+    let severity = osv.database_specific
+                      .as_ref()
+                      .and_then(|db| db.get("severity"))
+                      .and_then(Value::as_str)
+                      .map(str::to_string)
+                      .or_else(|| osv.severity.first().map(|s| format!("{}:{}", s.kind, s.score)));
+
+    let advisory = NewAdvisory { id: osv.id,
+                                 summary: osv.summary,
+                                 details: osv.details,
+                                 aliases: osv.aliases,
+                                 severity,
+                                 published: osv.published.as_deref().and_then(timestamp),
+                                 modified: osv.modified.as_deref().and_then(timestamp),
+                                 ranges };
+    OsvImport { advisory, withdrawn }
+}
+
+// The `origin/name` of an affected package, if it is one of ours
+fn package_name(package: &OsvPackage) -> Option<String> {
+    let name = if package.ecosystem == ECOSYSTEM {
+        package.name.clone()
+    } else {
+        let purl = package.purl.as_deref()?.strip_prefix(PURL_PREFIX)?;
+        purl.split(|c: char| c == '@' || c == '?' || c == '#').next()?.to_string()
+    };
+
+    match name.split('/').collect::<Vec<_>>().as_slice() {
+        [origin, name] if !origin.is_empty() && !name.is_empty() => {
+            Some(format!("{}/{}", origin, name))
+        }
+        _ => None,
+    }
+}
+
+fn timestamp(value: &str) -> Option<chrono::NaiveDateTime> {
+    DateTime::parse_from_rfc3339(value).ok().map(|t| t.naive_utc())
+}
+
+/// Imports advisories into the database, replacing the ones already imported.
+pub(crate) fn import(advisories: Vec<OsvImport>,
+                     conn: &mut PgConnection)
+                     -> Result<ImportReport> {
+    let mut report = ImportReport::default();
+    for OsvImport { advisory, withdrawn } in advisories {
+        if withdrawn {
+            // An advisory may be withdrawn after it was imported
+            if Advisory::delete(&advisory.id, conn)? > 0 {
+                report.withdrawn.push(advisory.id);
+            } else {
+                report.skipped.push(advisory.id);
+            }
+            continue;
+        }
+        // Nothing can be matched, which doesn't make an imported advisory void
+        if advisory.ranges.is_empty() {
+            report.skipped.push(advisory.id);
+            continue;
+        }
+        Advisory::upsert(&advisory, conn)?;
+        report.imported.push(advisory.id);
+    }
+    Ok(report)
+}
+
+// Versions are ordered the way the idents of releases of one package are
+fn versioned(package: &str, version: &str) -> PackageIdent {
+    let (origin, name) = package.split_once('/').unwrap_or(("", package));
+    PackageIdent::new(origin, name, Some(version), None)
+}
+
+fn in_range(version: &str, range: &AdvisoryRange) -> bool {
+    if !range.versions.is_empty() {
+        return range.versions.iter().any(|v| v == version);
+    }
+    let version = versioned(&range.package, version);
+    let after_introduced = range.introduced
+                                .as_deref()
+                                .map_or(true, |v| version >= versioned(&range.package, v));
+    let before_fixed = range.fixed
+                            .as_deref()
+                            .map_or(true, |v| version < versioned(&range.package, v));
+    let up_to_last = range.last_affected
+                          .as_deref()
+                          .map_or(true, |v| version <= versioned(&range.package, v));
+    after_introduced && before_fixed && up_to_last
+}
+
+/// Matches the advisory ranges against a release and its transitive dependencies.
+pub(crate) fn match_advisories(release: &BuilderPackageIdent,
+                               tdeps: &[BuilderPackageIdent],
+                               ranges: &[AdvisoryRange],
+                               advisories: &HashMap<String, Advisory>)
+                               -> Vec<AdvisoryMatch> {
+    let mut matches: Vec<AdvisoryMatch> = Vec::new();
+
+    for ident in Some(release).into_iter().chain(tdeps.iter()) {
+        let package = format!("{}/{}", ident.origin, ident.name);
+        let version = match ident.version {
+            Some(ref version) => version,
+            None => continue,
+        };
+
+        let mut affected_by: HashMap<&str, BTreeSet<String>> = HashMap::new();
+        for range in ranges.iter().filter(|r| r.package == package) {
+            if in_range(version, range) {
+                affected_by.entry(range.advisory_id.as_str())
+                           .or_default()
+                           .extend(range.fixed.clone());
+            }
+        }
+
+        for (id, fixed) in affected_by {
+            let advisory = match advisories.get(id) {
+                Some(advisory) => advisory,
+                None => continue,
+            };
+            matches.push(AdvisoryMatch { id:         advisory.id.clone(),
+                                         summary:    advisory.summary.clone(),
+                                         severity:   advisory.severity.clone(),
+                                         aliases:    advisory.aliases.clone(),
+                                         affected:   ident.to_string(),
+                                         transitive: ident != release,
+                                         fixed:      fixed.into_iter().collect(), });
+        }
+    }
+
+    // The release's own advisories first
+    matches.sort_by(|a, b| {
+               (a.transitive, &a.affected, &a.id).cmp(&(b.transitive, &b.affected, &b.id))
+           });
+    matches
+}
+
+/// Loads the advisories that may apply to any of the releases and their dependencies.
+pub(crate) fn load_for<'a, I>(idents: I,
+                              conn: &mut PgConnection)
+                              -> Result<(Vec<AdvisoryRange>, HashMap<String, Advisory>)>
+    where I: IntoIterator<Item = &'a BuilderPackageIdent>
+{
+    let packages = idents.into_iter()
+                         .map(|i| format!("{}/{}", i.origin, i.name))
+                         .collect::<BTreeSet<_>>()
+                         .into_iter()
+                         .collect::<Vec<_>>();
+
+    let ranges = AdvisoryRange::for_packages(&packages, conn).map_err(Error::DieselError)?;
+    let ids = ranges.iter()
+                    .map(|r| r.advisory_id.clone())
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect::<Vec<_>>();
+    let advisories = Advisory::get_all(&ids, conn).map_err(Error::DieselError)?;
+    Ok((ranges, advisories.into_iter().map(|a| (a.id.clone(), a)).collect()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    const OSV: &str = r#"{
+        "id": "BIOME-2025-0001",
+        "aliases": ["CVE-2025-1234"],
+        "summary": "Heap overflow in redis",
+        "modified": "2025-09-01T00:00:00Z",
+        "database_specific": { "severity": "HIGH" },
+        "affected": [
+            { "package": { "ecosystem": "Biome", "name": "core/redis" },
+              "ranges": [{ "type": "ECOSYSTEM",
+                           "events": [{ "introduced": "0" }, { "fixed": "4.0.14" },
+                                      { "introduced": "5.0.0" }, { "fixed": "5.0.5" }] }] },
+            { "package": { "ecosystem": "PyPI", "name": "redis" },
+              "ranges": [{ "type": "ECOSYSTEM", "events": [{ "introduced": "0" }] }] }
+        ]
+    }"#;
+
+    fn advisories() -> HashMap<String, Advisory> {
+        let mut advisories = HashMap::new();
+        advisories.insert("BIOME-2025-0001".to_string(),
+                          Advisory { id:         "BIOME-2025-0001".to_string(),
+                                     summary:    None,
+                                     details:    None,
+                                     aliases:    Vec::new(),
+                                     severity:   None,
+                                     published:  None,
+                                     modified:   None,
+                                     created_at: None,
+                                     updated_at: None, });
+        advisories
+    }
+
+    fn ident(ident: &str) -> BuilderPackageIdent { BuilderPackageIdent::from_str(ident).unwrap() }
+
+    #[test]
+    fn parses_biome_ranges() {
+        let mut advisories = parse(OSV).unwrap();
+
+        assert_eq!(advisories.len(), 1);
+        assert!(!advisories[0].withdrawn);
+        let advisory = advisories.remove(0).advisory;
+        assert_eq!(advisory.severity.as_deref(), Some("HIGH"));
+        assert_eq!(advisory.ranges.len(), 2);
+        assert_eq!(advisory.ranges[0].introduced, None);
+        assert_eq!(advisory.ranges[0].fixed.as_deref(), Some("4.0.14"));
+        assert_eq!(advisory.ranges[1].introduced.as_deref(), Some("5.0.0"));
+        assert!(advisory.ranges.iter().all(|r| r.package == "core/redis"));
+    }
+
+    #[test]
+    fn withdrawn_advisories_have_no_ranges() {
+        let osv = OSV.replacen("\"aliases\"",
+                               "\"withdrawn\": \"2025-09-02T00:00:00Z\", \"aliases\"",
+                               1);
+
+        let advisories = parse(&osv).unwrap();
+        assert!(advisories[0].withdrawn);
+        assert!(advisories[0].advisory.ranges.is_empty());
+    }
+
+    #[test]
+    fn git_only_advisories_are_not_withdrawn() {
+        let osv = OSV.replace("\"ECOSYSTEM\"", "\"GIT\"");
+
+        let advisories = parse(&osv).unwrap();
+        assert!(!advisories[0].withdrawn);
+        assert!(advisories[0].advisory.ranges.is_empty());
+    }
+
+    #[test]
+    fn versions_compare_like_idents() {
+        let range = AdvisoryRange { package: "core/redis".to_string(),
+                                    introduced: Some("4.0".to_string()),
+                                    fixed: Some("4.0.14".to_string()),
+                                    ..Default::default() };
+
+        assert!(in_range("4.0.9", &range));
+        assert!(in_range("4.0.1", &range));
+        assert!(!in_range("4.0.14", &range));
+        assert!(!in_range("3.2.12", &range));
+    }
+
+    #[test]
+    fn matches_releases_and_their_dependencies() {
+        let ranges = parse(OSV).unwrap().remove(0).advisory.ranges;
+        let ranges = ranges.into_iter()
+                           .map(|r| AdvisoryRange { advisory_id: "BIOME-2025-0001".to_string(),
+                                                    ..r })
+                           .collect::<Vec<_>>();
+
+        let matches = match_advisories(&ident("core/app/1.0.0/20250101000000"),
+                                       &[ident("core/redis/4.0.9/20190101000000")],
+                                       &ranges,
+                                       &advisories());
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].affected, "core/redis/4.0.9/20190101000000");
+        assert!(matches[0].transitive);
+        assert_eq!(matches[0].fixed, vec!["4.0.14".to_string()]);
+
+        let matches = match_advisories(&ident("core/redis/5.0.5/20190712150217"),
+                                       &[],
+                                       &ranges,
+                                       &advisories());
+        assert!(matches.is_empty());
+    }
+}
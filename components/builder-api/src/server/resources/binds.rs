@@ -0,0 +1,184 @@
+// Biome project based on Chef Habitat's code (c) 2016-2020 Chef Software, Inc
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use diesel::{debug_query,
+             pg::Pg,
+             sql_query,
+             sql_types::{Array,
+                         BigInt,
+                         Nullable,
+                         Text},
+             PgConnection,
+             QueryableByName,
+             RunQueryDsl};
+
+use crate::{db::models::package_binds::{NewPackageBind,
+                                        NewPackageExport},
+            server::error::{Error,
+                            Result}};
+
+// The metafiles of a hart that declare its exports and binds
+pub(crate) const EXPORTS_METAFILE: &str = "EXPORTS";
+pub(crate) const BINDS_METAFILE: &str = "BINDS";
+pub(crate) const BINDS_OPTIONAL_METAFILE: &str = "BINDS_OPTIONAL";
+
+// The latest release of a package providing every export of a bind contract
+#[derive(Clone, Debug, QueryableByName, Serialize)]
+pub(crate) struct BindProvider {
+    #[sql_type = "Text"]
+    pub ident:       String,
+    #[sql_type = "Text"]
+    pub target:      String,
+    #[serde(skip)]
+    #[sql_type = "BigInt"]
+    pub total_count: i64,
+}
+
+pub(crate) struct ListBindProviders<'a> {
+    pub exports:    &'a [String],
+    pub target:     &'a str,
+    pub origin:     Option<&'a str>,
+    pub channel:    Option<&'a str>,
+    pub account_id: Option<i64>,
+    pub page:       i64,
+    pub limit:      i64,
+}
+
+/// Parses an `EXPORTS` metafile, one `name=config.key` pair per line. Only the first of
+/// several lines exporting the same name is kept.
+pub(crate) fn parse_exports(contents: &str) -> Vec<NewPackageExport> {
+    let mut exports = contents.lines()
+                              .filter_map(|line| line.trim().split_once('='))
+                              .filter(|(name, key)| !name.is_empty() && !key.is_empty())
+                              .map(|(name, key)| {
+                                  NewPackageExport { name:       name.to_string(),
+                                                     config_key: key.to_string(), }
+                              })
+                              .collect::<Vec<_>>();
+    exports.sort_by(|a, b| a.name.cmp(&b.name));
+    exports.dedup_by(|a, b| a.name == b.name);
+    exports
+}
+
+/// Parses a `BINDS` or `BINDS_OPTIONAL` metafile, one `name=export export..` line per bind.
+pub(crate) fn parse_binds(contents: &str, optional: bool) -> Vec<NewPackageBind> {
+    contents.lines()
+            .filter_map(|line| line.trim().split_once('='))
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, exports)| {
+                NewPackageBind { name: name.to_string(),
+                                 exports: exports.split_whitespace().map(str::to_string).collect(),
+                                 optional }
+            })
+            .collect()
+}
+
+/// Keeps a single bind per name, as only one can be recorded for a package. A bind declared
+/// in both `BINDS` and `BINDS_OPTIONAL` is required.
+pub(crate) fn dedup_binds(binds: &mut Vec<NewPackageBind>) {
+    binds.sort_by(|a, b| a.name.cmp(&b.name).then(a.optional.cmp(&b.optional)));
+    binds.dedup_by(|a, b| a.name == b.name);
+}
+
+/// Lists the packages whose latest release exports all of `exports`, so any of them can be
+/// bound to by a service declaring that contract. Older releases that happen to export them
+/// don't make a package a provider.
+pub(crate) fn get_bind_providers(conn: &mut PgConnection,
+                                 req: &ListBindProviders)
+                                 -> Result<(Vec<BindProvider>, i64)> {
+    let sql_stmt = r###"
+        select ident, target, count(*) over () as total_count from (
+            select distinct on (op.origin, op.name) op.id, op.ident, op.target
+              from origin_packages_with_version_array as op
+             where op.target = $2
+               and op.status != 'yanked'
+               and ($3::text is null or op.origin = $3)
+               and ($4::text is null
+                    or exists (select 1 from origin_channel_packages as ocp
+                                 join origin_channels as oc on oc.id = ocp.channel_id
+                                where ocp.package_id = op.id
+                                  and oc.origin = op.origin
+                                  and oc.name = $4))
+               and (op.visibility = 'public'
+                    or op.origin in (select origin from origin_members where account_id = $5))
+             order by op.origin, op.name,
+                      string_to_array(op.version_array[1],'.')::numeric[] desc,
+                      op.version_array[2] desc,
+                      op.ident_array[4] desc
+        ) as latest
+         where (select coalesce(array_agg(ope.name), '{}') from origin_package_exports as ope
+                 where ope.package_id = latest.id) @> $1
+         order by ident
+         limit $6 offset $7"###;
+
+    let query = sql_query(sql_stmt).bind::<Array<Text>, _>(req.exports)
+                                   .bind::<Text, _>(req.target)
+                                   .bind::<Nullable<Text>, _>(req.origin)
+                                   .bind::<Nullable<Text>, _>(req.channel)
+                                   .bind::<Nullable<BigInt>, _>(req.account_id)
+                                   .bind::<BigInt, _>(req.limit)
+                                   .bind::<BigInt, _>((req.page - 1) * req.limit);
+
+    debug!("debug_query {}", debug_query::<Pg, _>(&query));
+
+    let providers = query.load::<BindProvider>(conn)
+                         .map_err(Error::DieselError)?;
+    let total_count = providers.first().map_or(0, |p| p.total_count);
+    Ok((providers, total_count))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_exports() {
+        let exports = parse_exports("port=port\nsuperuser_name=superuser.name\n\nbroken\n");
+
+        assert_eq!(exports,
+                   vec![NewPackageExport { name:       "port".to_string(),
+                                           config_key: "port".to_string(), },
+                        NewPackageExport { name:       "superuser_name".to_string(),
+                                           config_key: "superuser.name".to_string(), },]);
+    }
+
+    #[test]
+    fn parses_binds() {
+        let binds = parse_binds("database=port superuser_name superuser_password\n", true);
+
+        assert_eq!(binds,
+                   vec![NewPackageBind { name:     "database".to_string(),
+                                         exports:  vec!["port".to_string(),
+                                                        "superuser_name".to_string(),
+                                                        "superuser_password".to_string()],
+                                         optional: true, }]);
+    }
+
+    #[test]
+    fn dedups_binds_declared_twice() {
+        let mut binds = parse_binds("database=port\n", true);
+        binds.extend(parse_binds("database=port superuser_name\ncache=port\n", false));
+        dedup_binds(&mut binds);
+
+        assert_eq!(binds,
+                   vec![NewPackageBind { name:     "cache".to_string(),
+                                         exports:  vec!["port".to_string()],
+                                         optional: false, },
+                        NewPackageBind { name:     "database".to_string(),
+                                         exports:  vec!["port".to_string(),
+                                                        "superuser_name".to_string()],
+                                         optional: false, },]);
+        assert_eq!(parse_exports("port=port\nport=other.port\n").len(), 1);
+    }
+}
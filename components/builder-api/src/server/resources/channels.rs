@@ -12,13 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::str::FromStr;
+use std::{collections::{BTreeMap,
+                        HashMap},
+          str::FromStr};
 
 use actix_web::{body::BoxBody,
                 http::{self,
                        StatusCode},
                 web::{self,
                       Data,
+                      Json,
                       Path,
                       Query,
                       ServiceConfig},
@@ -40,7 +43,8 @@ use crate::db::models::{channel::*,
                         package::{BuilderPackageIdent,
                                   GetPackageGroup,
                                   Package,
-                                  PackageVisibility}};
+                                  PackageVisibility},
+                        settings::PackageLabels};
 
 use crate::server::{authorize::authorize_session,
                     error::{Error,
@@ -50,8 +54,18 @@ use crate::server::{authorize::authorize_session,
                               req_state,
                               visibility_for_optional_session,
                               Pagination,
+                              SbomQuery,
                               Target,
                               ToChannel},
+                    resources::{advisories::{self,
+                                             PackageAdvisories},
+                                pkgs::{self,
+                                       LatestForTargets},
+                                sbom::{self,
+                                       Sbom,
+                                       SbomFormat,
+                                       SbomPackage,
+                                       SbomSubject}},
                     services::metrics::Counter,
                     AppState};
 
@@ -62,6 +76,86 @@ struct SandboxBool {
     sandbox: bool,
 }
 
+#[derive(Debug, Default, Clone, Deserialize)]
+struct ChannelLabel {
+    label: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+struct ChannelTtl {
+    // Lifetime of the channel in seconds, after which it is reaped
+    ttl: Option<i64>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UpdateChannelReq {
+    // Missing leaves the description as is, null or an empty string clears it
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub description: Option<Option<String>>,
+    pub labels:      Option<Vec<String>>,
+}
+
+// Tells a field set to null apart from a missing one, which the default leaves as None
+fn deserialize_some<'de, T, D>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+    where T: serde::Deserialize<'de>,
+          D: serde::Deserializer<'de>
+{
+    serde::Deserialize::deserialize(deserializer).map(Some)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChannelMember {
+    pub origin:  String,
+    pub channel: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChannelMembers {
+    pub members: Vec<ChannelMember>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChannelLockfile {
+    pub origin:   String,
+    pub channel:  String,
+    pub packages: Vec<LockedPackage>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub ident:    String,
+    pub target:   String,
+    pub checksum: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OutdatedDependency {
+    pub ident:           String,
+    pub latest:          String,
+    pub latest_channels: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OutdatedPackage {
+    pub ident: String,
+    pub deps:  Vec<OutdatedDependency>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OutdatedReport {
+    pub channel:  String,
+    pub target:   String,
+    pub packages: Vec<OutdatedPackage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChannelAdvisoryReport {
+    pub channel:  String,
+    pub target:   String,
+    // Only the releases affected by at least one advisory
+    pub packages: Vec<PackageAdvisories>,
+}
+
 pub struct Channels;
 
 impl Channels {
@@ -71,20 +165,44 @@ impl Channels {
         cfg.route("/depot/channels/{origin}", web::get().to(get_channels))
            .route("/depot/channels/{origin}/{channel}",
                   web::post().to(create_channel))
+           .route("/depot/channels/{origin}/{channel}",
+                  web::patch().to(update_channel))
            .route("/depot/channels/{origin}/{channel}",
                   web::delete().to(delete_channel))
+           .route("/depot/channels/{origin}/{channel}/rename",
+                  web::put().to(rename_channel))
+           .route("/depot/channels/{origin}/{channel}/copy",
+                  web::post().to(copy_channel))
+           .route("/depot/channels/{origin}/{channel}/members",
+                  web::get().to(get_channel_members))
+           .route("/depot/channels/{origin}/{channel}/members",
+                  web::put().to(set_channel_members))
+           .route("/depot/channels/{origin}/{channel}/lockfile",
+                  web::get().to(export_channel_lockfile))
+           .route("/depot/channels/{origin}/{channel}/lockfile",
+                  web::put().to(import_channel_lockfile))
+           .route("/depot/channels/{origin}/{channel}/advisories",
+                  web::get().to(get_channel_advisories))
+           .route("/depot/channels/{origin}/{channel}/sbom",
+                  web::get().to(get_channel_sbom))
            .route("/depot/channels/{origin}/{channel}/pkgs",
                   web::get().to(get_packages_for_origin_channel))
            .route("/depot/channels/{origin}/{channel}/pkgs/_latest",
                   web::get().to(get_latest_packages_for_origin_channel))
+           .route("/depot/channels/{origin}/{channel}/pkgs/_outdated",
+                  web::get().to(get_outdated_packages_for_origin_channel))
            .route("/depot/channels/{origin}/{channel}/pkgs/{pkg}",
                   web::get().to(get_packages_for_origin_channel_package))
            .route("/depot/channels/{origin}/{channel}/pkgs/{pkg}/latest",
                   web::get().to(get_latest_package_for_origin_channel_package))
+           .route("/depot/channels/{origin}/{channel}/pkgs/{pkg}/latest/_targets",
+                  web::get().to(get_latest_channel_packages_for_all_targets))
            .route("/depot/channels/{origin}/{channel}/pkgs/{pkg}/{version}",
                   web::get().to(get_packages_for_origin_channel_package_version))
            .route("/depot/channels/{origin}/{channel}/pkgs/{pkg}/{version}/latest",
                   web::get().to(get_latest_package_for_origin_channel_package_version))
+           .route("/depot/channels/{origin}/{channel}/pkgs/{pkg}/{version}/latest/_targets",
+                  web::get().to(get_latest_channel_version_packages_for_all_targets))
            .route("/depot/channels/{origin}/{channel}/pkgs/{pkg}/{version}/{release}",
                   web::get().to(get_package_fully_qualified))
            .route("/depot/channels/{origin}/{channel}/pkgs/promote",
@@ -103,6 +221,7 @@ impl Channels {
 #[allow(clippy::needless_pass_by_value)]
 async fn get_channels(path: Path<String>,
                       sandbox: Query<SandboxBool>,
+                      label: Query<ChannelLabel>,
                       state: Data<AppState>)
                       -> HttpResponse {
     let origin = path.into_inner();
@@ -112,17 +231,26 @@ async fn get_channels(path: Path<String>,
         Err(err) => return err.into(),
     };
 
-    match Channel::list(&origin, sandbox.sandbox, &mut conn).map_err(Error::DieselError) {
+    match Channel::list(&origin, sandbox.sandbox, label.label.as_deref(), &mut conn)
+        .map_err(Error::DieselError)
+    {
         Ok(list) => {
             // TED: This is to maintain backwards API compat while killing some proto definitions
             // currently the output looks like [{"name": "foo"}] when it probably should be ["foo"]
             #[derive(Serialize)]
             struct Temp {
-                name: String,
+                name:        String,
+                description: Option<String>,
+                labels:      Vec<String>,
             }
-            let ident_list: Vec<Temp> = list.iter()
-                                            .map(|channel| Temp { name: channel.name.clone(), })
-                                            .collect();
+            let ident_list: Vec<Temp> =
+                list.into_iter()
+                    .map(|channel| {
+                        Temp { name:        channel.name,
+                               description: channel.description,
+                               labels:      channel.labels, }
+                    })
+                    .collect();
             HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
                                               headers::Cache::NoCache.to_string()))
                               .json(ident_list)
@@ -138,6 +266,7 @@ async fn get_channels(path: Path<String>,
 #[allow(clippy::needless_pass_by_value)]
 async fn create_channel(req: HttpRequest,
                         path: Path<(String, String)>,
+                        qttl: Query<ChannelTtl>,
                         state: Data<AppState>)
                         -> HttpResponse {
     let (origin, channel) = path.into_inner();
@@ -148,14 +277,29 @@ async fn create_channel(req: HttpRequest,
             Err(_) => return HttpResponse::new(StatusCode::UNAUTHORIZED),
         };
 
+    let expires_at = match qttl.ttl {
+        Some(ttl) if ttl > 0 => {
+            match chrono::Duration::try_seconds(ttl).and_then(|d| {
+                                                        chrono::Utc::now().naive_utc()
+                                                                          .checked_add_signed(d)
+                                                    }) {
+                Some(expires_at) => Some(expires_at),
+                None => return HttpResponse::new(StatusCode::BAD_REQUEST),
+            }
+        }
+        Some(_) => return HttpResponse::new(StatusCode::BAD_REQUEST),
+        None => None,
+    };
+
     let mut conn = match state.db.get_conn().map_err(Error::DbError) {
         Ok(conn_ref) => conn_ref,
         Err(err) => return err.into(),
     };
 
-    match Channel::create(&CreateChannel { name:     &channel,
-                                           origin:   &origin,
-                                           owner_id: session_id as i64, },
+    match Channel::create(&CreateChannel { name: &channel,
+                                           origin: &origin,
+                                           owner_id: session_id as i64,
+                                           expires_at },
                           &mut conn)
     {
         Ok(channel) => HttpResponse::Created().json(channel),
@@ -169,6 +313,60 @@ async fn create_channel(req: HttpRequest,
     }
 }
 
+#[allow(clippy::needless_pass_by_value)]
+async fn update_channel(req: HttpRequest,
+                        path: Path<(String, String)>,
+                        body: Json<UpdateChannelReq>,
+                        state: Data<AppState>)
+                        -> HttpResponse {
+    let (origin, channel) = path.into_inner();
+    let channel = ChannelIdent::from(channel);
+
+    if let Err(_err) = authorize_session(&req, Some(&origin), Some(OriginMemberRole::Maintainer)) {
+        return HttpResponse::new(StatusCode::UNAUTHORIZED);
+    }
+
+    if body.description.is_none() && body.labels.is_none() {
+        return HttpResponse::new(StatusCode::BAD_REQUEST);
+    }
+
+    // Labels are free-form, but blank and repeated ones carry no meaning
+    let labels = body.labels.as_ref().map(|labels| {
+                                         let mut labels: Vec<String> =
+                                             labels.iter()
+                                                   .map(|l| l.trim().to_string())
+                                                   .filter(|l| !l.is_empty())
+                                                   .collect();
+                                         labels.sort();
+                                         labels.dedup();
+                                         labels
+                                     });
+
+    let description = body.description.as_ref().map(|description| {
+                                                   description.as_deref()
+                                                              .map(str::trim)
+                                                              .filter(|d| !d.is_empty())
+                                               });
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    match Channel::update(&origin,
+                          &channel,
+                          &UpdateChannel { description, labels },
+                          &mut conn)
+    {
+        Ok(channel) => HttpResponse::Ok().json(channel),
+        Err(NotFound) => HttpResponse::new(StatusCode::NOT_FOUND),
+        Err(err) => {
+            debug!("Failed to update channel, err={}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 async fn delete_channel(req: HttpRequest,
                         path: Path<(String, String)>,
@@ -203,6 +401,500 @@ async fn delete_channel(req: HttpRequest,
     }
 }
 
+#[allow(clippy::needless_pass_by_value)]
+async fn rename_channel(req: HttpRequest,
+                        path: Path<(String, String)>,
+                        to_channel: Query<ToChannel>,
+                        state: Data<AppState>)
+                        -> HttpResponse {
+    let (origin, channel) = path.into_inner();
+    let channel = ChannelIdent::from(channel);
+    let new_channel = ChannelIdent::from(to_channel.channel.as_ref());
+
+    let session = match authorize_session(&req, Some(&origin), Some(OriginMemberRole::Maintainer)) {
+        Ok(session) => session,
+        Err(_) => return HttpResponse::new(StatusCode::UNAUTHORIZED),
+    };
+
+    if new_channel.as_str().is_empty() || new_channel == channel {
+        return HttpResponse::new(StatusCode::BAD_REQUEST);
+    }
+
+    if channel == ChannelIdent::stable()
+       || channel == ChannelIdent::unstable()
+       || new_channel == ChannelIdent::stable()
+       || new_channel == ChannelIdent::unstable()
+    {
+        return HttpResponse::new(StatusCode::FORBIDDEN);
+    }
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    match Channel::rename(&origin, &channel, &new_channel, &mut conn) {
+        Ok(renamed) => {
+            // Lookups of the new name may have cached a 404 before the rename
+            {
+                let mut memcache = state.memcache.borrow_mut();
+                memcache.clear_cache_for_channel(&origin, &channel);
+                memcache.clear_cache_for_channel(&origin, &new_channel);
+            }
+            origin_audit(&origin,
+                         OriginOperation::ChannelRename,
+                         &format!("{} -> {}", channel, new_channel),
+                         session.get_id() as i64,
+                         session.get_name(),
+                         &mut conn);
+            HttpResponse::Ok().json(renamed)
+        }
+        Err(NotFound) => HttpResponse::new(StatusCode::NOT_FOUND),
+        Err(DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+            HttpResponse::Conflict().into()
+        }
+        Err(err) => {
+            debug!("Failed to rename channel, err={}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn copy_channel(req: HttpRequest,
+                      path: Path<(String, String)>,
+                      to_channel: Query<ToChannel>,
+                      state: Data<AppState>)
+                      -> HttpResponse {
+    let (origin, channel) = path.into_inner();
+    let channel = ChannelIdent::from(channel);
+    let new_channel = ChannelIdent::from(to_channel.channel.as_ref());
+
+    let session = match authorize_session(&req, Some(&origin), Some(OriginMemberRole::Maintainer)) {
+        Ok(session) => session,
+        Err(_) => return HttpResponse::new(StatusCode::UNAUTHORIZED),
+    };
+
+    if new_channel.as_str().is_empty() {
+        return HttpResponse::new(StatusCode::BAD_REQUEST);
+    }
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let source = match Channel::get(&origin, &channel, &mut conn) {
+        Ok(source) => source,
+        Err(NotFound) => return HttpResponse::new(StatusCode::NOT_FOUND),
+        Err(err) => {
+            debug!("Failed to get channel, err={}", err);
+            return Error::DieselError(err).into();
+        }
+    };
+
+    match Channel::copy(&source,
+                        &CreateChannel { name: new_channel.as_str(),
+                                         origin: &origin,
+                                         owner_id: session.get_id() as i64,
+                                         expires_at: None },
+                        &mut conn)
+    {
+        Ok((copy, pkg_ids)) => {
+            if !pkg_ids.is_empty() {
+                match PackageGroupChannelAudit::audit(
+                    PackageGroupChannelAudit {
+                        origin: &origin,
+                        channel: new_channel.as_str(),
+                        package_ids: pkg_ids,
+                        operation: PackageChannelOperation::Promote,
+                        trigger: helpers::trigger_from_request_model(&req),
+                        requester_id: session.get_id() as i64,
+                        requester_name: session.get_name(),
+                        group_id: 0_i64,
+                    },
+                    &mut conn,
+                ) {
+                    Ok(_) => {}
+                    Err(e) => debug!("Failed to save rank change to audit log: {}", e),
+                };
+            }
+            HttpResponse::Created().json(copy)
+        }
+        Err(DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+            HttpResponse::Conflict().into()
+        }
+        Err(err) => {
+            debug!("Failed to copy channel, err={}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_channel_members(path: Path<(String, String)>, state: Data<AppState>) -> HttpResponse {
+    let (origin, channel) = path.into_inner();
+    let channel = ChannelIdent::from(channel);
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let composite = match Channel::get(&origin, &channel, &mut conn) {
+        Ok(composite) => composite,
+        Err(NotFound) => return HttpResponse::new(StatusCode::NOT_FOUND),
+        Err(err) => {
+            debug!("Failed to get channel, err={}", err);
+            return Error::DieselError(err).into();
+        }
+    };
+
+    match Channel::list_members(composite.id, &mut conn) {
+        Ok(list) => {
+            let members = list.into_iter()
+                              .map(|member| {
+                                  ChannelMember { origin:  member.origin,
+                                                  channel: member.name, }
+                              })
+                              .collect();
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                              headers::Cache::NoCache.to_string()))
+                              .json(ChannelMembers { members })
+        }
+        Err(err) => {
+            debug!("Failed to list channel members, err={}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn set_channel_members(req: HttpRequest,
+                             path: Path<(String, String)>,
+                             body: Json<ChannelMembers>,
+                             state: Data<AppState>)
+                             -> HttpResponse {
+    let (origin, channel) = path.into_inner();
+    let channel = ChannelIdent::from(channel);
+
+    if let Err(_err) = authorize_session(&req, Some(&origin), Some(OriginMemberRole::Maintainer)) {
+        return HttpResponse::new(StatusCode::UNAUTHORIZED);
+    }
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let composite = match Channel::get(&origin, &channel, &mut conn) {
+        Ok(composite) => composite,
+        Err(NotFound) => return HttpResponse::new(StatusCode::NOT_FOUND),
+        Err(err) => {
+            debug!("Failed to get channel, err={}", err);
+            return Error::DieselError(err).into();
+        }
+    };
+
+    let mut member_ids: Vec<i64> = Vec::new();
+    for member in body.members.iter() {
+        let member_channel = ChannelIdent::from(member.channel.as_str());
+        let id = match Channel::get(&member.origin, &member_channel, &mut conn) {
+            Ok(member_channel) => member_channel.id,
+            Err(NotFound) => {
+                let body = Bytes::from(format!("Channel '{}/{}' does not exist",
+                                               member.origin, member.channel));
+                return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY,
+                                               BoxBody::new(body));
+            }
+            Err(err) => {
+                debug!("Failed to get member channel, err={}", err);
+                return Error::DieselError(err).into();
+            }
+        };
+        if id == composite.id || member_ids.contains(&id) {
+            let body = Bytes::from(format!("Channel '{}/{}' can not be added more than once \
+                                            or to itself",
+                                           member.origin, member.channel));
+            return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body));
+        }
+        member_ids.push(id);
+    }
+
+    match Channel::set_members(composite.id, &member_ids, &mut conn) {
+        Ok(_) => {
+            state.memcache
+                 .borrow_mut()
+                 .clear_cache_for_channel(&origin, &channel);
+            HttpResponse::Ok().json(&body.0)
+        }
+        Err(err) => {
+            debug!("Failed to set channel members, err={}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn export_channel_lockfile(req: HttpRequest,
+                                 path: Path<(String, String)>,
+                                 state: Data<AppState>)
+                                 -> HttpResponse {
+    let (origin, channel) = path.into_inner();
+    let channel = ChannelIdent::from(channel);
+
+    let opt_session_id = match authorize_session(&req, None, None) {
+        Ok(session) => Some(session.get_id()),
+        Err(_) => None,
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    if let Err(err) = Channel::get(&origin, &channel, &mut conn) {
+        return match err {
+            NotFound => HttpResponse::new(StatusCode::NOT_FOUND),
+            err => {
+                debug!("Failed to get channel, err={}", err);
+                Error::DieselError(err).into()
+            }
+        };
+    }
+
+    match Channel::list_all_packages_with_checksums(
+        &ListAllChannelPackages {
+            visibility: &visibility_for_optional_session(&req, opt_session_id, &origin),
+            origin: &origin,
+            channel: &channel,
+        },
+        &mut conn,
+    ) {
+        Ok(list) => {
+            let packages = list.into_iter()
+                               .map(|p| {
+                                   LockedPackage { ident:    p.ident.to_string(),
+                                                   target:   p.target.to_string(),
+                                                   checksum: p.checksum, }
+                               })
+                               .collect();
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                              headers::Cache::NoCache.to_string()))
+                              .json(ChannelLockfile { origin,
+                                                      channel: channel.to_string(),
+                                                      packages })
+        }
+        Err(err) => {
+            debug!("Failed to export channel lockfile, err={}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_channel_advisories(req: HttpRequest,
+                                path: Path<(String, String)>,
+                                qtarget: Query<Target>)
+                                -> HttpResponse {
+    let (origin, channel) = path.into_inner();
+    let channel = ChannelIdent::from(channel);
+
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    match do_get_channel_advisories(&req, &origin, &channel, target) {
+        Ok(packages) => {
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                              headers::Cache::NoCache.to_string()))
+                              .json(ChannelAdvisoryReport { channel: channel.to_string(),
+                                                            target: target.to_string(),
+                                                            packages })
+        }
+        Err(Error::NotFound) => HttpResponse::new(StatusCode::NOT_FOUND),
+        Err(err) => {
+            debug!("Failed to get channel advisories, err={}", err);
+            err.into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_channel_sbom(req: HttpRequest,
+                          path: Path<(String, String)>,
+                          qsbom: Query<SbomQuery>)
+                          -> HttpResponse {
+    let (origin, channel) = path.into_inner();
+    let channel = ChannelIdent::from(channel);
+
+    let format = match qsbom.format.as_deref().map(SbomFormat::from_str) {
+        None => SbomFormat::CycloneDx,
+        Some(Ok(format)) => format,
+        Some(Err(msg)) => {
+            let body = Bytes::from(msg.into_bytes());
+            return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body));
+        }
+    };
+
+    let target = match helpers::requested_target(&req, qsbom.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    match do_get_channel_sbom(&req, &origin, &channel, target) {
+        Ok(sbom) => {
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                              headers::Cache::NoCache.to_string()))
+                              .json(sbom.render(format))
+        }
+        Err(Error::NotFound) => HttpResponse::new(StatusCode::NOT_FOUND),
+        Err(err) => {
+            debug!("Failed to get channel SBOM, err={}", err);
+            err.into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn import_channel_lockfile(req: HttpRequest,
+                                 path: Path<(String, String)>,
+                                 body: Json<ChannelLockfile>,
+                                 state: Data<AppState>)
+                                 -> HttpResponse {
+    let (origin, channel) = path.into_inner();
+    let channel = ChannelIdent::from(channel);
+
+    let session = match authorize_session(&req, Some(&origin), Some(OriginMemberRole::Maintainer)) {
+        Ok(session) => session,
+        Err(_) => return HttpResponse::new(StatusCode::UNAUTHORIZED),
+    };
+
+    // Packages can't be demoted from unstable, so it can never be made to match a lockfile
+    if channel == ChannelIdent::unstable() {
+        return HttpResponse::new(StatusCode::FORBIDDEN);
+    }
+
+    let mut idents = Vec::new();
+    for locked in body.packages.iter() {
+        let ident = match PackageIdent::from_str(&locked.ident) {
+            Ok(ident) if ident.fully_qualified() && ident.origin == origin => ident,
+            _ => {
+                let body = Bytes::from(format!("Invalid package ident '{}'", locked.ident));
+                return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY,
+                                               BoxBody::new(body));
+            }
+        };
+        if let Err(resp) = helpers::enabled_target(&state.config.api, &locked.target) {
+            return resp;
+        }
+        idents.push(BuilderPackageIdent(ident));
+    }
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let pkgs = match Package::get_group(GetPackageGroup { pkgs:       idents,
+                                                          visibility: PackageVisibility::all(), },
+                                        &mut conn)
+    {
+        Ok(pkgs) => pkgs,
+        Err(err) => {
+            debug!("Failed to get lockfile packages, err={}", err);
+            return Error::DieselError(err).into();
+        }
+    };
+
+    let pkgs: HashMap<(String, String), &Package> =
+        pkgs.iter()
+            .map(|p| ((p.ident.to_string(), p.target.to_string()), p))
+            .collect();
+
+    let mut pkg_ids = Vec::new();
+    let mut missing = Vec::new();
+    let mut mismatched = Vec::new();
+    for locked in body.packages.iter() {
+        match pkgs.get(&(locked.ident.clone(), locked.target.clone())) {
+            Some(pkg) if pkg.checksum == locked.checksum => pkg_ids.push(pkg.id),
+            Some(_) => mismatched.push(locked.clone()),
+            None => missing.push(locked.clone()),
+        }
+    }
+
+    if !mismatched.is_empty() {
+        return HttpResponse::Conflict().json(json!({ "mismatched": mismatched }));
+    }
+    if !missing.is_empty() {
+        return HttpResponse::UnprocessableEntity().json(json!({ "missing": missing }));
+    }
+
+    let target_channel = match Channel::get(&origin, &channel, &mut conn) {
+        Ok(target_channel) => target_channel,
+        Err(NotFound) if channel != ChannelIdent::stable() => {
+            match Channel::create(&CreateChannel { name: channel.as_str(),
+                                                   origin: &origin,
+                                                   owner_id: session.get_id() as i64,
+                                                   expires_at: None },
+                                  &mut conn)
+            {
+                Ok(target_channel) => target_channel,
+                Err(err) => {
+                    debug!("Failed to create channel, err={}", err);
+                    return Error::DieselError(err).into();
+                }
+            }
+        }
+        Err(err) => {
+            debug!("Failed to get channel, err={}", err);
+            return Error::DieselError(err).into();
+        }
+    };
+
+    let (promoted, demoted) =
+        match Channel::replace_packages(target_channel.id, &pkg_ids, &mut conn) {
+            Ok(changes) => changes,
+            Err(err) => {
+                debug!("Failed to import channel lockfile, err={}", err);
+                return Error::DieselError(err).into();
+            }
+        };
+
+    state.memcache
+         .borrow_mut()
+         .clear_cache_for_channel(&origin, &channel);
+
+    let result = json!({ "promoted": promoted.len(), "demoted": demoted.len() });
+
+    for (package_ids, operation) in [(promoted, PackageChannelOperation::Promote),
+                                     (demoted, PackageChannelOperation::Demote)]
+    {
+        if package_ids.is_empty() {
+            continue;
+        }
+        match PackageGroupChannelAudit::audit(
+            PackageGroupChannelAudit {
+                origin: &origin,
+                channel: channel.as_str(),
+                package_ids,
+                operation,
+                trigger: helpers::trigger_from_request_model(&req),
+                requester_id: session.get_id() as i64,
+                requester_name: session.get_name(),
+                group_id: 0_i64,
+            },
+            &mut conn,
+        ) {
+            Ok(_) => {}
+            Err(e) => debug!("Failed to save rank change to audit log: {}", e),
+        };
+    }
+
+    HttpResponse::Ok().json(result)
+}
+
 #[allow(clippy::needless_pass_by_value)]
 async fn promote_channel_packages(req: HttpRequest,
                                   path: Path<(String, String)>,
@@ -345,9 +1037,10 @@ fn do_promote_or_demote_channel_packages(req: &HttpRequest,
             if (ch_target != &ChannelIdent::stable()) && (ch_target != &ChannelIdent::unstable()) {
                 Channel::create(
                     &CreateChannel {
-                        name:     ch_target.as_str(),
+                        name:       ch_target.as_str(),
                         origin,
-                        owner_id: session_id,
+                        owner_id:   session_id,
+                        expires_at: None,
                     },
                 &mut conn)?
             } else {
@@ -400,20 +1093,9 @@ async fn promote_package(req: HttpRequest,
     let ident = PackageIdent::new(origin.clone(), pkg, Some(version), Some(release));
 
     // TODO: Deprecate target from headers
-    let target = match qtarget.target {
-        Some(ref t) => {
-            trace!("Query requested target = {}", t);
-            match PackageTarget::from_str(t) {
-                Ok(t) => t,
-                Err(err) => {
-                    debug!("Invalid target requested: {}, err = {:?}", t, err);
-                    let body = Bytes::from(format!("Invalid package target '{}'", t).into_bytes());
-                    return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY,
-                                                   BoxBody::new(body));
-                }
-            }
-        }
-        None => helpers::target_from_headers(&req),
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
     };
 
     let mut conn = match state.db.get_conn().map_err(Error::DbError) {
@@ -480,20 +1162,9 @@ async fn demote_package(req: HttpRequest,
     let ident = PackageIdent::new(origin.clone(), pkg, Some(version), Some(release));
 
     // TODO: Deprecate target from headers
-    let target = match qtarget.target {
-        Some(ref t) => {
-            trace!("Query requested target = {}", t);
-            match PackageTarget::from_str(t) {
-                Ok(t) => t,
-                Err(err) => {
-                    debug!("Invalid target requested: {}, err = {:?}", t, err);
-                    let body = Bytes::from(format!("Invalid package target '{}'", t).into_bytes());
-                    return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY,
-                                                   BoxBody::new(body));
-                }
-            }
-        }
-        None => helpers::target_from_headers(&req),
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
     };
 
     let mut conn = match state.db.get_conn().map_err(Error::DbError) {
@@ -619,7 +1290,13 @@ async fn get_latest_package_for_origin_channel_package(req: HttpRequest,
 
     let ident = PackageIdent::new(origin, pkg, None, None);
 
-    match do_get_channel_package(&req, &qtarget, &ident, &channel) {
+    // TODO: Deprecate target from headers
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    match do_get_channel_package_for_target(&req, &ident, &channel, target) {
         Ok(json_body) => {
             HttpResponse::Ok().append_header((http::header::CONTENT_TYPE,
                                               headers::APPLICATION_JSON))
@@ -635,6 +1312,78 @@ async fn get_latest_package_for_origin_channel_package(req: HttpRequest,
     }
 }
 
+#[allow(clippy::needless_pass_by_value)]
+async fn get_latest_channel_packages_for_all_targets(req: HttpRequest,
+                                                     path: Path<(String, String, String)>)
+                                                     -> HttpResponse {
+    let (origin, channel, pkg) = path.into_inner();
+    let channel = ChannelIdent::from(channel);
+
+    let ident = PackageIdent::new(origin, pkg, None, None);
+
+    latest_channel_packages_for_all_targets(&req, &ident, &channel)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_latest_channel_version_packages_for_all_targets(req: HttpRequest,
+                                                             path: Path<(String,
+                                                                   String,
+                                                                   String,
+                                                                   String)>)
+                                                             -> HttpResponse {
+    let (origin, channel, pkg, version) = path.into_inner();
+    let channel = ChannelIdent::from(channel);
+
+    let ident = PackageIdent::new(origin, pkg, Some(version), None);
+
+    latest_channel_packages_for_all_targets(&req, &ident, &channel)
+}
+
+fn latest_channel_packages_for_all_targets(req: &HttpRequest,
+                                           ident: &PackageIdent,
+                                           channel: &ChannelIdent)
+                                           -> HttpResponse {
+    let mut latest = LatestForTargets::default();
+    for target in req_state(req).config.api.targets.iter() {
+        let found = do_get_channel_package_for_target(req, ident, channel, *target);
+        if let Err(err) = latest.add(*target, found) {
+            debug!("Failed to get latest packages, err={}", err);
+            return err.into();
+        }
+    }
+
+    // Nothing resolving for an unknown channel or package is a 404, not a list of misses
+    if latest.is_empty() {
+        let mut conn = match req_state(req).db.get_conn().map_err(Error::DbError) {
+            Ok(conn_ref) => conn_ref,
+            Err(err) => return err.into(),
+        };
+
+        match Channel::get(&ident.origin, channel, &mut conn) {
+            Ok(_) => (),
+            Err(NotFound) => return HttpResponse::new(StatusCode::NOT_FOUND),
+            Err(err) => {
+                debug!("Failed to get channel, err={}", err);
+                return Error::DieselError(err).into();
+            }
+        }
+        drop(conn);
+
+        match pkgs::package_exists(req, ident) {
+            Ok(true) => (),
+            Ok(false) => return HttpResponse::new(StatusCode::NOT_FOUND),
+            Err(err) => {
+                debug!("Failed to get package, err={}", err);
+                return err.into();
+            }
+        }
+    }
+
+    HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                      headers::Cache::NoCache.to_string()))
+                      .json(latest)
+}
+
 #[allow(clippy::needless_pass_by_value)]
 async fn get_latest_package_for_origin_channel_package_version(req: HttpRequest,
                                                                path: Path<(String,
@@ -648,7 +1397,13 @@ async fn get_latest_package_for_origin_channel_package_version(req: HttpRequest,
 
     let ident = PackageIdent::new(origin, pkg, Some(version), None);
 
-    match do_get_channel_package(&req, &qtarget, &ident, &channel) {
+    // TODO: Deprecate target from headers
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    match do_get_channel_package_for_target(&req, &ident, &channel, target) {
         Ok(json_body) => {
             HttpResponse::Ok().append_header((http::header::CONTENT_TYPE,
                                               headers::APPLICATION_JSON))
@@ -674,7 +1429,13 @@ async fn get_package_fully_qualified(req: HttpRequest,
 
     let ident = PackageIdent::new(origin, pkg, Some(version), Some(release));
 
-    match do_get_channel_package(&req, &qtarget, &ident, &channel) {
+    // TODO: Deprecate target from headers
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    match do_get_channel_package_for_target(&req, &ident, &channel, target) {
         Ok(json_body) => {
             HttpResponse::Ok().append_header((http::header::CONTENT_TYPE,
                                               headers::APPLICATION_JSON))
@@ -716,9 +1477,253 @@ async fn get_latest_packages_for_origin_channel(req: HttpRequest,
     }
 }
 
+#[allow(clippy::needless_pass_by_value)]
+async fn get_outdated_packages_for_origin_channel(req: HttpRequest,
+                                                  path: Path<(String, String)>,
+                                                  qtarget: Query<Target>)
+                                                  -> HttpResponse {
+    let (origin, channel) = path.into_inner();
+    let channel = ChannelIdent::from(channel);
+
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    match do_get_outdated_channel_packages(&req, &origin, &channel, target) {
+        Ok(packages) => {
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                              headers::Cache::NoCache.to_string()))
+                              .json(OutdatedReport { channel: channel.to_string(),
+                                                     target: target.to_string(),
+                                                     packages })
+        }
+        Err(Error::NotFound) => HttpResponse::new(StatusCode::NOT_FOUND),
+        Err(err) => {
+            debug!("Failed to get outdated packages, err={}", err);
+            err.into()
+        }
+    }
+}
+
 // Internal - these functions should return Result<..>
 //
 
+// Walks the runtime deps of the latest packages in the channel, reporting each dep with a
+// newer release in the same channel of the dep's origin.
+fn do_get_outdated_channel_packages(req: &HttpRequest,
+                                    origin: &str,
+                                    channel: &ChannelIdent,
+                                    target: PackageTarget)
+                                    -> Result<Vec<OutdatedPackage>> {
+    let opt_session_id = match authorize_session(req, None, None) {
+        Ok(session) => Some(session.get_id()),
+        Err(_) => None,
+    };
+
+    let mut conn = req_state(req).db.get_conn().map_err(Error::DbError)?;
+
+    match Channel::get(origin, channel, &mut conn) {
+        Ok(_) => (),
+        Err(NotFound) => return Err(Error::NotFound),
+        Err(err) => return Err(Error::DieselError(err)),
+    }
+
+    let target_str = target.to_string();
+
+    // Latest release of each package in the channel, keyed by origin and then name
+    let mut latest: HashMap<String, HashMap<String, BuilderPackageIdent>> = HashMap::new();
+    load_latest_channel_packages(req,
+                                 opt_session_id,
+                                 origin,
+                                 channel,
+                                 &target_str,
+                                 &mut latest,
+                                 &mut conn)?;
+
+    let packages: Vec<BuilderPackageIdent> = latest[origin].values().cloned().collect();
+
+    let mut packages = Package::get_group(
+        GetPackageGroup {
+            pkgs: packages,
+            visibility: visibility_for_optional_session(req, opt_session_id, origin),
+        },
+        &mut conn,
+    )?;
+    packages.retain(|p| p.target.0 == target);
+    packages.sort_by(|a, b| a.ident.to_string().cmp(&b.ident.to_string()));
+
+    let mut channels_of: HashMap<String, Vec<String>> = HashMap::new();
+    let mut outdated = Vec::new();
+
+    for package in packages {
+        let mut deps = Vec::new();
+        for dep in package.deps.iter() {
+            load_latest_channel_packages(req,
+                                         opt_session_id,
+                                         &dep.origin,
+                                         channel,
+                                         &target_str,
+                                         &mut latest,
+                                         &mut conn)?;
+            let newer = match latest[&dep.origin].get(&dep.name) {
+                Some(newer) if newer.0 > dep.0 => newer.clone(),
+                _ => continue,
+            };
+
+            let newer_str = newer.to_string();
+            if !channels_of.contains_key(&newer_str) {
+                let channels = Package::list_package_channels(
+                    &newer,
+                    target,
+                    visibility_for_optional_session(req, opt_session_id, &newer.origin),
+                    &mut conn,
+                )?;
+                channels_of.insert(newer_str.clone(),
+                                   channels.into_iter().map(|c| c.name).collect());
+            }
+
+            deps.push(OutdatedDependency { ident:           dep.to_string(),
+                                           latest_channels: channels_of[&newer_str].clone(),
+                                           latest:          newer_str, });
+        }
+
+        if !deps.is_empty() {
+            outdated.push(OutdatedPackage { ident: package.ident.to_string(),
+                                            deps });
+        }
+    }
+
+    Ok(outdated)
+}
+
+// Lists every release of the channel for the target, along with the runtime dependencies
+// they pull in from outside the channel
+fn do_get_channel_sbom(req: &HttpRequest,
+                       origin: &str,
+                       channel: &ChannelIdent,
+                       target: PackageTarget)
+                       -> Result<Sbom> {
+    let opt_session_id = match authorize_session(req, None, None) {
+        Ok(session) => Some(session.get_id()),
+        Err(_) => None,
+    };
+
+    let mut conn = req_state(req).db.get_conn().map_err(Error::DbError)?;
+    let packages = channel_packages_for_target(req, opt_session_id, origin, channel, target,
+                                               &mut conn)?;
+
+    let mut sbom_packages = packages.iter()
+                                    .map(|p| (p.ident.to_string(), SbomPackage::from_package(p)))
+                                    .collect::<BTreeMap<_, _>>();
+    let in_channel = sbom_packages.keys().cloned().collect::<Vec<_>>();
+
+    let mut deps = packages.iter()
+                           .flat_map(|p| p.tdeps.iter())
+                           .filter(|d| !sbom_packages.contains_key(&d.to_string()))
+                           .cloned()
+                           .collect::<Vec<_>>();
+    deps.sort_by_key(|d| d.to_string());
+    deps.dedup();
+    for dep in sbom::load_packages(req, opt_session_id, deps, target, &mut conn)? {
+        sbom_packages.insert(dep.ident.to_string(), dep);
+    }
+
+    Ok(Sbom { subject:  SbomSubject::Channel { origin:   origin.to_string(),
+                                               channel:  channel.to_string(),
+                                               packages: in_channel, },
+              packages: sbom_packages.into_values().collect(), })
+}
+
+// Lists the advisories affecting the releases of the channel for the target, directly or
+// through their transitive dependencies
+fn do_get_channel_advisories(req: &HttpRequest,
+                             origin: &str,
+                             channel: &ChannelIdent,
+                             target: PackageTarget)
+                             -> Result<Vec<PackageAdvisories>> {
+    let opt_session_id = match authorize_session(req, None, None) {
+        Ok(session) => Some(session.get_id()),
+        Err(_) => None,
+    };
+
+    let mut conn = req_state(req).db.get_conn().map_err(Error::DbError)?;
+    let packages = channel_packages_for_target(req, opt_session_id, origin, channel, target,
+                                               &mut conn)?;
+
+    let idents = packages.iter()
+                         .flat_map(|p| Some(&p.ident).into_iter().chain(p.tdeps.iter()));
+    let (ranges, known) = advisories::load_for(idents, &mut conn)?;
+
+    let mut report = packages.iter()
+                             .map(|p| {
+                                 PackageAdvisories { ident:      p.ident.to_string(),
+                                                     target:     p.target.to_string(),
+                                                     advisories:
+                                                         advisories::match_advisories(&p.ident,
+                                                                                      &p.tdeps,
+                                                                                      &ranges,
+                                                                                      &known), }
+                             })
+                             .filter(|p| !p.advisories.is_empty())
+                             .collect::<Vec<_>>();
+    report.sort_by(|a, b| a.ident.cmp(&b.ident));
+    Ok(report)
+}
+
+// Every release in the channel for the target the requester may see
+fn channel_packages_for_target(req: &HttpRequest,
+                               opt_session_id: Option<u64>,
+                               origin: &str,
+                               channel: &ChannelIdent,
+                               target: PackageTarget,
+                               conn: &mut PgConnection)
+                               -> Result<Vec<Package>> {
+    match Channel::get(origin, channel, conn) {
+        Ok(_) => (),
+        Err(NotFound) => return Err(Error::NotFound),
+        Err(err) => return Err(Error::DieselError(err)),
+    }
+
+    let visibility = visibility_for_optional_session(req, opt_session_id, origin);
+    let idents = Channel::list_all_packages(&ListAllChannelPackages { visibility: &visibility,
+                                                                      origin,
+                                                                      channel },
+                                            conn)?;
+    let mut packages = Package::get_group(GetPackageGroup { pkgs: idents,
+                                                            visibility },
+                                          conn)?;
+    packages.retain(|p| p.target.0 == target);
+    Ok(packages)
+}
+
+fn load_latest_channel_packages(req: &HttpRequest,
+                                opt_session_id: Option<u64>,
+                                origin: &str,
+                                channel: &ChannelIdent,
+                                target: &str,
+                                latest: &mut HashMap<String, HashMap<String, BuilderPackageIdent>>,
+                                conn: &mut PgConnection)
+                                -> Result<()> {
+    if latest.contains_key(origin) {
+        return Ok(());
+    }
+
+    let (_, _, idents) = Channel::list_latest_packages(
+        &ListAllChannelPackagesForTarget {
+            visibility: &visibility_for_optional_session(req, opt_session_id, origin),
+            channel,
+            origin,
+            target,
+        },
+        conn,
+    )?;
+
+    latest.insert(origin.to_string(),
+                  idents.into_iter().map(|i| (i.name.clone(), i)).collect());
+    Ok(())
+}
+
 fn do_get_latest_channel_packages(req: &HttpRequest,
                                   qtarget: &Query<Target>,
                                   origin: &str,
@@ -795,11 +1800,11 @@ fn do_get_all_channel_packages(req: &HttpRequest,
                                &mut conn).map_err(Error::DieselError)
 }
 
-fn do_get_channel_package(req: &HttpRequest,
-                          qtarget: &Query<Target>,
-                          ident: &PackageIdent,
-                          channel: &ChannelIdent)
-                          -> Result<String> {
+fn do_get_channel_package_for_target(req: &HttpRequest,
+                                     ident: &PackageIdent,
+                                     channel: &ChannelIdent,
+                                     target: PackageTarget)
+                                     -> Result<String> {
     let opt_session_id = match authorize_session(req, None, None) {
         Ok(session) => Some(session.get_id()),
         Err(_) => None,
@@ -808,15 +1813,6 @@ fn do_get_channel_package(req: &HttpRequest,
 
     let req_ident = ident.clone();
 
-    // TODO: Deprecate target from headers
-    let target = match qtarget.target {
-        Some(ref t) => {
-            trace!("Query requested target = {}", t);
-            PackageTarget::from_str(t)?
-        }
-        None => helpers::target_from_headers(req),
-    };
-
     // Scope this memcache usage so the reference goes out of
     // scope before the visibility_for_optional_session call
     // below
@@ -865,26 +1861,28 @@ fn do_get_channel_package(req: &HttpRequest,
         Err(e) => return Err(e.into()),
     };
 
-    let pkg: Package = match Channel::get_latest_package(
-        &GetLatestPackage {
-            ident: &BuilderPackageIdent(ident.clone()),
-            channel,
-            target: &target,
-            visibility: &helpers::visibility_for_optional_session(
-                req,
-                opt_session_id,
-                &ident.origin,
-            ),
-        },
-        &mut conn,
-    ) {
-        Ok(pkg) => pkg.into(),
-        Err(NotFound) => {
-            let mut memcache = req_state(req).memcache.borrow_mut();
-            memcache.set_package(&req_ident, None, channel, &target, opt_session_id);
+    let found = match get_latest_channel_or_member_package(req,
+                                                           ident,
+                                                           channel,
+                                                           &target,
+                                                           opt_session_id,
+                                                           &mut conn)
+    {
+        Ok(found) => found,
+        Err(NotFound) => ChannelPackage { package:   None,
+                                          cacheable: true, },
+        Err(err) => return Err(err.into()),
+    };
+
+    let pkg: Package = match found.package {
+        Some(pkg) => pkg,
+        None => {
+            if found.cacheable {
+                let mut memcache = req_state(req).memcache.borrow_mut();
+                memcache.set_package(&req_ident, None, channel, &target, opt_session_id);
+            }
             return Err(Error::NotFound);
         }
-        Err(err) => return Err(err.into()),
     };
 
     let mut pkg_json = serde_json::to_value(pkg.clone()).unwrap();
@@ -892,10 +1890,12 @@ fn do_get_channel_package(req: &HttpRequest,
 
     pkg_json["channels"] = json!(channels);
     pkg_json["is_a_service"] = json!(pkg.is_a_service());
+    let labels = PackageLabels::effective(&pkg.origin, &pkg.name, pkg.id, &mut conn)?;
+    pkg_json["labels"] = json!(labels);
 
     let json_body = serde_json::to_string(&pkg_json).unwrap();
 
-    {
+    if found.cacheable {
         let mut memcache = req_state(req).memcache.borrow_mut();
         memcache.set_package(&req_ident,
                              Some(&json_body),
@@ -907,6 +1907,73 @@ fn do_get_channel_package(req: &HttpRequest,
     Ok(json_body)
 }
 
+// The outcome of a channel package lookup. Answers that involved member channels can't be
+// cached under the composite channel: promoting to or demoting from a member channel only
+// clears the member channel's cache.
+struct ChannelPackage {
+    package:   Option<Package>,
+    cacheable: bool,
+}
+
+// Looks the package up in the channel itself first and then, if it is a composite
+// channel, in each of its member channels in order of precedence. Member channels
+// are searched for the package name under their own origin.
+fn get_latest_channel_or_member_package(req: &HttpRequest,
+                                        ident: &PackageIdent,
+                                        channel: &ChannelIdent,
+                                        target: &PackageTarget,
+                                        opt_session_id: Option<u64>,
+                                        conn: &mut PgConnection)
+                                        -> diesel::QueryResult<ChannelPackage> {
+    match Channel::get_latest_package(
+        &GetLatestPackage {
+            ident: &BuilderPackageIdent(ident.clone()),
+            channel,
+            target,
+            visibility: &visibility_for_optional_session(req, opt_session_id, &ident.origin),
+        },
+        conn,
+    ) {
+        Ok(pkg) => {
+            return Ok(ChannelPackage { package:   Some(pkg.into()),
+                                       cacheable: true, })
+        }
+        Err(NotFound) => {}
+        Err(err) => return Err(err),
+    }
+
+    let composite = Channel::get(&ident.origin, channel, conn)?;
+    let members = Channel::list_members(composite.id, conn)?;
+    let cacheable = members.is_empty();
+    for member in members {
+        let member_ident = PackageIdent::new(member.origin.clone(),
+                                             ident.name.clone(),
+                                             ident.version.clone(),
+                                             ident.release.clone());
+        let member_channel = ChannelIdent::from(member.name.as_str());
+
+        match Channel::get_latest_package(
+            &GetLatestPackage {
+                ident: &BuilderPackageIdent(member_ident),
+                channel: &member_channel,
+                target,
+                visibility: &visibility_for_optional_session(req, opt_session_id, &member.origin),
+            },
+            conn,
+        ) {
+            Ok(pkg) => {
+                return Ok(ChannelPackage { package:   Some(pkg.into()),
+                                           cacheable: false, })
+            }
+            Err(NotFound) => continue,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(ChannelPackage { package: None,
+                        cacheable })
+}
+
 pub fn channels_for_package_ident(req: &HttpRequest,
                                   package: &BuilderPackageIdent,
                                   target: PackageTarget,
@@ -0,0 +1,206 @@
+// Biome project based on Chef Habitat's code (c) 2016-2020 Chef Software, Inc
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeSet,
+                       HashMap,
+                       HashSet};
+
+use crate::db::models::package::BuilderPackageIdent;
+
+// The stored dependency lists of a single package, keyed by its ident when building a tree
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PackageDeps {
+    pub deps:       Vec<String>,
+    pub build_deps: Vec<String>,
+}
+
+impl PackageDeps {
+    pub fn new(deps: &[BuilderPackageIdent], build_deps: &[BuilderPackageIdent]) -> Self {
+        PackageDeps { deps:       deps.iter().map(|d| d.to_string()).collect(),
+                      build_deps: build_deps.iter().map(|d| d.to_string()).collect(), }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct DependencyNode {
+    pub ident:      String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub build:      bool,
+    // Set when the package is unknown to this builder or not visible to the requester,
+    // in which case its own dependencies can't be shown
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub unresolved: bool,
+    // Set when the package was already expanded earlier in the tree, its dependencies are
+    // listed there
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub repeated:   bool,
+    pub deps:       Vec<DependencyNode>,
+}
+
+/// Builds the nested dependency tree of `root` from the stored `deps` of each package in
+/// `packages`. Build dependencies are only followed from the root, as the build
+/// dependencies of a dependency play no part in the root package. Every package is
+/// expanded once, where it first appears, so shared dependencies like core/glibc don't
+/// blow the tree up.
+pub(crate) fn build_tree(root: &str,
+                         packages: &HashMap<String, PackageDeps>,
+                         include_build: bool)
+                         -> DependencyNode {
+    let mut seen = HashSet::new();
+    seen.insert(root.to_string());
+    let mut deps: Vec<DependencyNode> = Vec::new();
+
+    if let Some(root_deps) = packages.get(root) {
+        for dep in root_deps.deps.iter() {
+            deps.push(build_node(dep, false, packages, &mut seen));
+        }
+        if include_build {
+            for dep in root_deps.build_deps.iter() {
+                deps.push(build_node(dep, true, packages, &mut seen));
+            }
+        }
+    }
+
+    DependencyNode { ident: root.to_string(),
+                     build: false,
+                     unresolved: !packages.contains_key(root),
+                     repeated: false,
+                     deps }
+}
+
+fn build_node(ident: &str,
+              build: bool,
+              packages: &HashMap<String, PackageDeps>,
+              seen: &mut HashSet<String>)
+              -> DependencyNode {
+    let pkg_deps = match packages.get(ident) {
+        Some(pkg_deps) => pkg_deps,
+        None => {
+            return DependencyNode { ident: ident.to_string(),
+                                    build,
+                                    unresolved: true,
+                                    repeated: false,
+                                    deps: Vec::new() };
+        }
+    };
+
+    // This also stops on cycles, which stored dependencies should never have
+    if !seen.insert(ident.to_string()) {
+        return DependencyNode { ident: ident.to_string(),
+                                build,
+                                unresolved: false,
+                                repeated: true,
+                                deps: Vec::new() };
+    }
+
+    let deps = pkg_deps.deps
+                       .iter()
+                       .map(|dep| build_node(dep, false, packages, seen))
+                       .collect();
+
+    DependencyNode { ident: ident.to_string(),
+                     build,
+                     unresolved: false,
+                     repeated: false,
+                     deps }
+}
+
+/// Renders the tree as a Graphviz digraph. Shared dependencies appear once, and build
+/// dependency edges are dashed.
+pub(crate) fn to_dot(root: &DependencyNode) -> String {
+    let mut edges = BTreeSet::new();
+    collect_edges(root, &mut edges);
+
+    let mut dot = format!("digraph \"{}\" {{\n", root.ident);
+    dot.push_str(&format!("    \"{}\";\n", root.ident));
+    for (from, to, build) in edges {
+        if build {
+            dot.push_str(&format!("    \"{}\" -> \"{}\" [style=dashed];\n", from, to));
+        } else {
+            dot.push_str(&format!("    \"{}\" -> \"{}\";\n", from, to));
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn collect_edges<'a>(node: &'a DependencyNode, edges: &mut BTreeSet<(&'a str, &'a str, bool)>) {
+    for dep in node.deps.iter() {
+        edges.insert((&node.ident, &dep.ident, dep.build));
+        collect_edges(dep, edges);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn packages() -> HashMap<String, PackageDeps> {
+        let mut packages = HashMap::new();
+        packages.insert("core/app/1.0/1".to_string(),
+                        PackageDeps { deps:       vec!["core/openssl/1.1/1".to_string(),
+                                                       "core/glibc/2.3/1".to_string()],
+                                      build_deps: vec!["core/gcc/9.1/1".to_string()], });
+        packages.insert("core/openssl/1.1/1".to_string(),
+                        PackageDeps { deps:       vec!["core/glibc/2.3/1".to_string()],
+                                      build_deps: vec!["core/perl/5.3/1".to_string()], });
+        packages.insert("core/glibc/2.3/1".to_string(), PackageDeps::default());
+        packages
+    }
+
+    #[test]
+    fn tree_follows_runtime_deps() {
+        let tree = build_tree("core/app/1.0/1", &packages(), false);
+
+        assert_eq!(tree.deps.len(), 2);
+        assert_eq!(tree.deps[0].ident, "core/openssl/1.1/1");
+        assert_eq!(tree.deps[0].deps.len(), 1);
+        assert_eq!(tree.deps[0].deps[0].ident, "core/glibc/2.3/1");
+        assert!(!tree.deps[0].deps[0].unresolved);
+    }
+
+    #[test]
+    fn tree_includes_root_build_deps_only() {
+        let tree = build_tree("core/app/1.0/1", &packages(), true);
+
+        assert_eq!(tree.deps.len(), 3);
+        let gcc = &tree.deps[2];
+        assert_eq!(gcc.ident, "core/gcc/9.1/1");
+        assert!(gcc.build);
+        assert!(gcc.unresolved);
+        assert!(tree.deps[0].deps.iter().all(|d| !d.build));
+    }
+
+    #[test]
+    fn shared_deps_are_expanded_once() {
+        let tree = build_tree("core/app/1.0/1", &packages(), false);
+
+        let glibc = &tree.deps[1];
+        assert_eq!(glibc.ident, "core/glibc/2.3/1");
+        assert!(glibc.repeated);
+        assert!(!tree.deps[0].deps[0].repeated);
+    }
+
+    #[test]
+    fn dot_output_dedups_shared_edges() {
+        let tree = build_tree("core/app/1.0/1", &packages(), true);
+        let dot = to_dot(&tree);
+
+        assert!(dot.starts_with("digraph \"core/app/1.0/1\" {\n"));
+        assert_eq!(dot.matches("\"core/openssl/1.1/1\" -> \"core/glibc/2.3/1\";")
+                      .count(),
+                   1);
+        assert!(dot.contains("\"core/app/1.0/1\" -> \"core/gcc/9.1/1\" [style=dashed];"));
+    }
+}
@@ -22,7 +22,8 @@ use actix_web::{web::{self,
                 HttpRequest,
                 HttpResponse};
 
-use crate::protocol::originsrv::OriginPackageIdent;
+use crate::{bio_core::ChannelIdent,
+            protocol::originsrv::OriginPackageIdent};
 
 use crate::db::models::{origin::*,
                         package::*};
@@ -30,17 +31,47 @@ use crate::db::models::{origin::*,
 use crate::server::{authorize::authorize_session,
                     error::{Error,
                             Result},
-                    helpers::{req_state,
+                    helpers::{self,
+                              req_state,
                               Target}};
 
-use super::reverse_dependencies::{self,
-                                  ReverseDependencies};
+use super::{rebuild_plan::{self,
+                           ListRebuildDependents},
+            reverse_dependencies::{self,
+                                   ReverseDependencies}};
+
+#[derive(Deserialize)]
+pub struct RebuildPlanQuery {
+    #[serde(default)]
+    target:  Option<String>,
+    #[serde(default)]
+    channel: Option<String>,
+    // Comma separated list of origins to plan across, defaults to the package origin
+    #[serde(default)]
+    origins: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    release: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RebuildPlanResponse {
+    origin:  String,
+    name:    String,
+    target:  String,
+    channel: String,
+    layers:  Vec<Vec<String>>,
+    cycles:  Vec<Vec<String>>,
+}
 
 pub struct Jobs;
 
 impl Jobs {
     pub fn register(cfg: &mut ServiceConfig) {
-        cfg.route("/rdeps/{origin}/{name}", web::get().to(get_rdeps));
+        cfg.route("/rdeps/{origin}/{name}", web::get().to(get_rdeps))
+           .route("/rebuild_plan/{origin}/{name}",
+                  web::get().to(get_rebuild_plan));
     }
 }
 
@@ -51,9 +82,10 @@ async fn get_rdeps(req: HttpRequest,
                    -> HttpResponse {
     let (origin, name) = path.into_inner();
 
-    let target: String = qtarget.target
-                                .clone()
-                                .unwrap_or_else(|| "x86_64-linux".to_string());
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target.to_string(),
+        Err(resp) => return resp,
+    };
 
     let mut connection = req_state(&req).db
                                         .get_conn()
@@ -79,6 +111,82 @@ async fn get_rdeps(req: HttpRequest,
     }
 }
 
+#[allow(clippy::needless_pass_by_value)]
+async fn get_rebuild_plan(req: HttpRequest,
+                          path: Path<(String, String)>,
+                          qplan: Query<RebuildPlanQuery>)
+                          -> HttpResponse {
+    let (origin, name) = path.into_inner();
+
+    let opt_session_id = match authorize_session(&req, None, None) {
+        Ok(session) => Some(session.get_id() as i64),
+        Err(_) => None,
+    };
+
+    let target = match helpers::requested_target(&req, qplan.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let channel = match qplan.channel {
+        Some(ref c) => ChannelIdent::from(c.as_str()),
+        None => ChannelIdent::stable(),
+    };
+
+    let mut origins: Vec<String> = match qplan.origins {
+        Some(ref o) => {
+            o.split(',')
+             .map(str::trim)
+             .filter(|o| !o.is_empty())
+             .map(str::to_string)
+             .collect()
+        }
+        None => Vec::new(),
+    };
+    if origins.is_empty() {
+        origins.push(origin.clone());
+    }
+
+    if qplan.release.is_some() && qplan.version.is_none() {
+        return HttpResponse::BadRequest().json("A release requires a version");
+    }
+
+    let mut conn = match req_state(&req).db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let target_str = target.to_string();
+    let graph = match rebuild_plan::get_rebuild_graph(
+        &mut conn,
+        &ListRebuildDependents {
+            origin: &origin,
+            name: &name,
+            version: qplan.version.as_deref(),
+            release: qplan.release.as_deref(),
+            origins: &origins,
+            channel: channel.as_str(),
+            target: &target_str,
+            account_id: opt_session_id,
+        },
+    ) {
+        Ok(graph) => graph,
+        Err(err) => {
+            debug!("{}", err);
+            return err.into();
+        }
+    };
+
+    let plan = rebuild_plan::plan(&graph);
+
+    HttpResponse::Ok().json(RebuildPlanResponse { origin,
+                                                  name,
+                                                  target: target_str,
+                                                  channel: channel.to_string(),
+                                                  layers: plan.layers,
+                                                  cycles: plan.cycles })
+}
+
 fn filtered_rdeps(req: &HttpRequest,
                   reverse_dependencies: &ReverseDependencies)
                   -> Result<ReverseDependencies> {
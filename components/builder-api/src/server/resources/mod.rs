@@ -1,11 +1,18 @@
+pub(crate) mod advisories;
 pub mod authenticate;
+pub(crate) mod binds;
 pub mod channels;
+pub(crate) mod dependency_tree;
 pub mod events;
 pub mod ext;
 pub mod jobs;
 pub mod origins;
+pub(crate) mod package_diff;
 pub mod pkgs;
 pub mod profile;
+pub(crate) mod rebuild_plan;
 pub(crate) mod reverse_dependencies;
+pub(crate) mod sbom;
+pub(crate) mod search;
 pub mod settings;
 pub mod user;
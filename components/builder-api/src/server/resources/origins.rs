@@ -4,6 +4,9 @@
 use crate::{bldr_core::crypto,
             db::models::{account::*,
                          channel::Channel,
+                         downloads::{Downloads,
+                                     ListOriginDownloads,
+                                     PackageDownloads},
                          integration::*,
                          invitations::*,
                          keys as db_keys,
@@ -13,6 +16,10 @@ use crate::{bldr_core::crypto,
                                    Package,
                                    PackageVisibility},
                          projects::Project,
+                         retention::{ListRetentionReports,
+                                     NewRetentionPolicy,
+                                     RetentionPolicy,
+                                     RetentionReport},
                          secrets::*,
                          settings::OriginPackageSettings},
             protocol::originsrv::OriginKeyIdent,
@@ -25,7 +32,8 @@ use crate::{bldr_core::crypto,
                      helpers::{self,
                                role_results_json,
                                Pagination,
-                               Role},
+                               Role,
+                               StatsQuery},
                      resources::pkgs::postprocess_package_list,
                      AppState}};
 use actix_web::{body::BoxBody,
@@ -81,6 +89,20 @@ pub struct UpdateOriginHandlerReq {
     pub default_package_visibility: Option<PackageVisibility>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RetentionPolicyReq {
+    pub keep_unstable_releases:  Option<i32>,
+    pub unpromoted_max_age_days: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct OriginStats {
+    pub origin:   String,
+    pub since:    String,
+    pub total:    i64,
+    pub packages: Vec<PackageDownloads>,
+}
+
 pub struct Origins {}
 
 impl Origins {
@@ -144,7 +166,17 @@ impl Origins {
            .route("/depot/origins/{origin}/integrations/{integration}/{name}",
                   web::delete().to(delete_origin_integration))
            .route("/depot/origins/{origin}/integrations/{integration}/{name}",
-                  web::put().to(create_origin_integration));
+                  web::put().to(create_origin_integration))
+           .route("/depot/origins/{origin}/stats",
+                  web::get().to(get_origin_stats))
+           .route("/depot/origins/{origin}/retention",
+                  web::get().to(get_retention_policy))
+           .route("/depot/origins/{origin}/retention",
+                  web::put().to(update_retention_policy))
+           .route("/depot/origins/{origin}/retention",
+                  web::delete().to(delete_retention_policy))
+           .route("/depot/origins/{origin}/retention/reports",
+                  web::get().to(list_retention_reports));
     }
 }
 
@@ -843,7 +875,8 @@ async fn list_unique_packages(req: HttpRequest,
                                                                                   opt_session_id,
                                                                                   &origin),
                              page:       page as i64,
-                             limit:      per_page as i64, };
+                             limit:      per_page as i64,
+                             labels:     Vec::new(), };
 
     match Package::distinct_for_origin(&lpr, &mut conn) {
         Ok((packages, count)) => {
@@ -856,6 +889,55 @@ async fn list_unique_packages(req: HttpRequest,
     }
 }
 
+#[allow(clippy::needless_pass_by_value)]
+async fn get_origin_stats(req: HttpRequest,
+                          path: Path<String>,
+                          qstats: Query<StatsQuery>,
+                          state: Data<AppState>)
+                          -> HttpResponse {
+    let origin = path.into_inner();
+
+    let opt_session_id = match authorize_session(&req, None, None) {
+        Ok(session) => Some(session.get_id()),
+        Err(_) => None,
+    };
+
+    let since = match helpers::stats_since(qstats.days) {
+        Some(since) => since,
+        None => {
+            let body = Bytes::from(format!("Days must be between 1 and {}",
+                                           helpers::STATS_DAYS_MAX).into_bytes());
+            return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body));
+        }
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let visibility = helpers::visibility_for_optional_session(&req, opt_session_id, &origin);
+    let lod = ListOriginDownloads { origin: &origin,
+                                    visibility: &visibility,
+                                    since };
+
+    match Downloads::by_package(&lod, &mut conn) {
+        Ok(packages) => {
+            let total = packages.iter().filter_map(|p| p.downloads).sum();
+            let stats = OriginStats { origin,
+                                      since: since.to_string(),
+                                      total,
+                                      packages };
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL, headers::NO_CACHE))
+                              .json(stats)
+        }
+        Err(err) => {
+            debug!("{}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 async fn download_latest_origin_encryption_key(req: HttpRequest,
                                                path: Path<String>,
@@ -1387,6 +1469,137 @@ async fn origin_member_delete(req: HttpRequest,
     }
 }
 
+#[allow(clippy::needless_pass_by_value)]
+async fn get_retention_policy(req: HttpRequest,
+                              path: Path<String>,
+                              state: Data<AppState>)
+                              -> HttpResponse {
+    let origin = path.into_inner();
+
+    if let Err(err) = authorize_session(&req, Some(&origin), Some(OriginMemberRole::Member)) {
+        return err.into();
+    }
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    match RetentionPolicy::get(&origin, &mut conn) {
+        Ok(Some(policy)) => {
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL, headers::NO_CACHE))
+                              .json(policy)
+        }
+        Ok(None) => HttpResponse::NotFound().into(),
+        Err(err) => {
+            debug!("{}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn update_retention_policy(req: HttpRequest,
+                                 path: Path<String>,
+                                 body: Json<RetentionPolicyReq>,
+                                 state: Data<AppState>)
+                                 -> HttpResponse {
+    let origin = path.into_inner();
+
+    if let Err(err) = authorize_session(&req, Some(&origin), Some(OriginMemberRole::Owner)) {
+        return err.into();
+    }
+
+    // A policy without any rule would never remove anything
+    if body.keep_unstable_releases.is_none() && body.unpromoted_max_age_days.is_none() {
+        return HttpResponse::new(StatusCode::BAD_REQUEST);
+    }
+
+    if body.keep_unstable_releases.unwrap_or(1) < 1
+       || body.unpromoted_max_age_days.unwrap_or(1) < 1
+    {
+        let body = Bytes::from_static(b"Retention rules must be greater than zero");
+        return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body));
+    }
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let new_policy = NewRetentionPolicy { origin:                  &origin,
+                                          keep_unstable_releases:  body.keep_unstable_releases,
+                                          unpromoted_max_age_days: body.unpromoted_max_age_days, };
+
+    match RetentionPolicy::set(&new_policy, &mut conn) {
+        Ok(policy) => HttpResponse::Ok().json(policy),
+        Err(err) => {
+            debug!("{}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn delete_retention_policy(req: HttpRequest,
+                                 path: Path<String>,
+                                 state: Data<AppState>)
+                                 -> HttpResponse {
+    let origin = path.into_inner();
+
+    if let Err(err) = authorize_session(&req, Some(&origin), Some(OriginMemberRole::Owner)) {
+        return err.into();
+    }
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    match RetentionPolicy::delete(&origin, &mut conn) {
+        Ok(0) => HttpResponse::NotFound().into(),
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => {
+            debug!("{}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn list_retention_reports(req: HttpRequest,
+                                pagination: Query<Pagination>,
+                                path: Path<String>,
+                                state: Data<AppState>)
+                                -> HttpResponse {
+    let origin = path.into_inner();
+
+    if let Err(err) = authorize_session(&req, Some(&origin), Some(OriginMemberRole::Member)) {
+        return err.into();
+    }
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let (page, per_page) = helpers::extract_pagination_in_pages(&pagination);
+
+    let lrr = ListRetentionReports { origin: &origin,
+                                     page:   page as i64,
+                                     limit:  per_page as i64, };
+
+    match RetentionReport::list(&lrr, &mut conn) {
+        Ok((reports, count)) => {
+            postprocess_package_list(&req, reports.as_slice(), count, &pagination)
+        }
+        Err(err) => {
+            debug!("{}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 async fn fetch_origin_integrations(req: HttpRequest,
                                    path: Path<String>,
@@ -0,0 +1,228 @@
+// Biome project based on Chef Habitat's code (c) 2016-2020 Chef Software, Inc
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap,
+                       BTreeSet};
+
+use crate::db::models::package_metadata::PackageMetadata;
+
+// Past this many lines on either side a config isn't diffed line by line, the quadratic table
+// would get too big
+const MAX_CONFIG_DIFF_LINES: usize = 1000;
+
+// How the dependencies of a package moved between two of its releases. Dependencies are
+// matched on origin and name, so a new release of the same dependency is a change.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub(crate) struct DepsDiff {
+    pub added:   Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<Change<String>>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Change<T> {
+    pub from: T,
+    pub to:   T,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct ListDiff<T> {
+    pub added:   Vec<T>,
+    pub removed: Vec<T>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct FieldChange {
+    pub field: &'static str,
+    pub from:  Option<String>,
+    pub to:    Option<String>,
+}
+
+/// Compares two dependency lists of fully qualified idents.
+pub(crate) fn diff_deps(from: &[String], to: &[String]) -> DepsDiff {
+    let from = by_origin_name(from);
+    let to = by_origin_name(to);
+    let mut diff = DepsDiff::default();
+
+    for (key, from_ident) in from.iter() {
+        match to.get(key) {
+            Some(to_ident) if to_ident != from_ident => {
+                diff.changed.push(Change { from: from_ident.to_string(),
+                                           to:   to_ident.to_string(), })
+            }
+            Some(_) => (),
+            None => diff.removed.push(from_ident.to_string()),
+        }
+    }
+    diff.added = to.iter()
+                   .filter(|(key, _)| !from.contains_key(*key))
+                   .map(|(_, ident)| ident.to_string())
+                   .collect();
+    diff
+}
+
+fn by_origin_name(idents: &[String]) -> BTreeMap<String, &str> {
+    idents.iter()
+          .map(|ident| {
+              let key = ident.splitn(3, '/').take(2).collect::<Vec<_>>().join("/");
+              (key, ident.as_str())
+          })
+          .collect()
+}
+
+/// Compares two lists as sets, the result is sorted.
+pub(crate) fn diff_list<T: Clone + Ord>(from: &[T], to: &[T]) -> ListDiff<T> {
+    let from = from.iter().collect::<BTreeSet<_>>();
+    let to = to.iter().collect::<BTreeSet<_>>();
+
+    ListDiff { added:   to.difference(&from).map(|t| (*t).clone()).collect(),
+               removed: from.difference(&to).map(|t| (*t).clone()).collect(), }
+}
+
+/// A line diff of two configs, every line prefixed with ` `, `-` or `+` the way a unified
+/// diff shows them. Identical configs give no lines at all, configs too long to diff have all
+/// of their lines removed and added.
+pub(crate) fn diff_config(from: &str, to: &str) -> Vec<String> {
+    if from == to {
+        return Vec::new();
+    }
+    let from = from.lines().collect::<Vec<_>>();
+    let to = to.lines().collect::<Vec<_>>();
+
+    if from.len() > MAX_CONFIG_DIFF_LINES || to.len() > MAX_CONFIG_DIFF_LINES {
+        return from.iter()
+                   .map(|line| format!("-{}", line))
+                   .chain(to.iter().map(|line| format!("+{}", line)))
+                   .collect();
+    }
+
+    // Longest common subsequence table, bounded by the line limit above
+    let mut lcs = vec![vec![0usize; to.len() + 1]; from.len() + 1];
+    for i in (0..from.len()).rev() {
+        for j in (0..to.len()).rev() {
+            lcs[i][j] = if from[i] == to[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < from.len() && j < to.len() {
+        if from[i] == to[j] {
+            lines.push(format!(" {}", from[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(format!("-{}", from[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+{}", to[j]));
+            j += 1;
+        }
+    }
+    lines.extend(from[i..].iter().map(|line| format!("-{}", line)));
+    lines.extend(to[j..].iter().map(|line| format!("+{}", line)));
+    lines
+}
+
+/// The plan metadata fields that differ between two releases.
+pub(crate) fn diff_metadata(from: &PackageMetadata, to: &PackageMetadata) -> Vec<FieldChange> {
+    let licenses = |m: &PackageMetadata| {
+        if m.licenses.is_empty() {
+            None
+        } else {
+            Some(m.licenses.join(" "))
+        }
+    };
+    let fields = vec![("description", from.description.clone(), to.description.clone()),
+                      ("upstream_url", from.upstream_url.clone(), to.upstream_url.clone()),
+                      ("licenses", licenses(from), licenses(to)),
+                      ("maintainer", from.maintainer.clone(), to.maintainer.clone())];
+
+    fields.into_iter()
+          .filter(|(_, from, to)| from != to)
+          .map(|(field, from, to)| FieldChange { field, from, to })
+          .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn idents(idents: &[&str]) -> Vec<String> { idents.iter().map(|i| i.to_string()).collect() }
+
+    #[test]
+    fn deps_are_matched_on_origin_and_name() {
+        let from = idents(&["core/glibc/2.27/1", "core/openssl/1.0/1", "core/zlib/1.2/1"]);
+        let to = idents(&["core/glibc/2.27/1", "core/openssl/1.1/2", "core/pcre/8.4/1"]);
+
+        let diff = diff_deps(&from, &to);
+
+        assert_eq!(diff.added, vec!["core/pcre/8.4/1".to_string()]);
+        assert_eq!(diff.removed, vec!["core/zlib/1.2/1".to_string()]);
+        assert_eq!(diff.changed,
+                   vec![Change { from: "core/openssl/1.0/1".to_string(),
+                                 to:   "core/openssl/1.1/2".to_string(), }]);
+    }
+
+    #[test]
+    fn lists_are_compared_as_sets() {
+        let diff = diff_list(&[80, 443, 8080], &[8443, 443, 80]);
+
+        assert_eq!(diff.added, vec![8443]);
+        assert_eq!(diff.removed, vec![8080]);
+    }
+
+    #[test]
+    fn config_diff_keeps_unchanged_lines() {
+        let diff = diff_config("port = 6379\nbind = []\n", "port = 6380\nbind = []\nsave = true\n");
+
+        assert_eq!(diff,
+                   vec!["-port = 6379".to_string(),
+                        "+port = 6380".to_string(),
+                        " bind = []".to_string(),
+                        "+save = true".to_string()]);
+        assert!(diff_config("port = 6379\n", "port = 6379\n").is_empty());
+    }
+
+    #[test]
+    fn long_configs_are_replaced_whole() {
+        let from = "a = 1\n".repeat(MAX_CONFIG_DIFF_LINES + 1);
+        let to = "a = 2\n";
+
+        let diff = diff_config(&from, to);
+
+        assert_eq!(diff.len(), MAX_CONFIG_DIFF_LINES + 2);
+        assert_eq!(diff.last(), Some(&"+a = 2".to_string()));
+        assert!(diff[..MAX_CONFIG_DIFF_LINES + 1].iter().all(|line| line == "-a = 1"));
+    }
+
+    #[test]
+    fn metadata_diff_only_has_changed_fields() {
+        let from = PackageMetadata { licenses: vec!["MIT".to_string()],
+                                     maintainer: Some("Alice".to_string()),
+                                     ..Default::default() };
+        let to = PackageMetadata { licenses: vec!["Apache-2.0".to_string()],
+                                   maintainer: Some("Alice".to_string()),
+                                   ..Default::default() };
+
+        assert_eq!(diff_metadata(&from, &to),
+                   vec![FieldChange { field: "licenses",
+                                      from:  Some("MIT".to_string()),
+                                      to:    Some("Apache-2.0".to_string()), }]);
+    }
+}
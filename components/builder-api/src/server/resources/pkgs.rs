@@ -12,27 +12,63 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::reverse_dependencies::{self};
+use super::{advisories::{self,
+                         PackageAdvisories},
+            binds::{self,
+                    ListBindProviders},
+            dependency_tree::{self,
+                              PackageDeps},
+            package_diff::{self,
+                           DepsDiff,
+                           FieldChange,
+                           ListDiff},
+            reverse_dependencies::{self,
+                                   ReleaseDependent},
+            sbom::{self,
+                   Sbom,
+                   SbomFormat,
+                   SbomPackage,
+                   SbomSubject},
+            search::{self,
+                     SearchFacets,
+                     SearchFilesByPath,
+                     SearchPackagesByTerms,
+                     SearchTerms}};
 use crate::{bldr_core::metrics::CounterMetric,
-            db::models::{channel::{Channel,
-                                   ChannelWithPromotion},
+            db::models::{channel::{ChannelWithPromotion,
+                                   PackageChannelAudit,
+                                   PackageChannelOperation},
+                         downloads::{DailyDownloads,
+                                     Downloads,
+                                     ListPackageDownloads,
+                                     ReleaseDownloads},
                          license_keys::*,
                          origin::*,
                          package::{BuilderPackageIdent,
                                    BuilderPackageTarget,
-                                   DeletePackage,
                                    GetLatestPackage,
                                    GetPackage,
                                    ListPackages,
                                    NewPackage,
                                    Package,
                                    PackageIdentWithChannelPlatform,
+                                   PackageStatus,
                                    PackageVisibility,
-                                   SearchPackages},
+                                   SearchPackages,
+                                   UpdatePackageStatus},
+                         package_binds::{NewPackageBind,
+                                         NewPackageExport,
+                                         PackageBind,
+                                         PackageBinds,
+                                         PackageExport},
+                         package_files::PackageFiles,
+                         package_metadata::PackageMetadata,
                          settings::{GetOriginPackageSettings,
                                     NewOriginPackageSettings,
-                                    OriginPackageSettings}},
-            bio_core::{package::{FromArchive,
+                                    OriginPackageSettings,
+                                    PackageLabels}},
+            bio_core::{crypto::artifact,
+                       package::{FromArchive,
                                  Identifiable,
                                  PackageArchive,
                                  PackageIdent,
@@ -46,10 +82,15 @@ use crate::{bldr_core::metrics::CounterMetric,
                      helpers::{self,
                                fetch_license_expiration,
                                req_state,
+                               LabelQuery,
                                Pagination,
+                               SbomQuery,
+                               StatsQuery,
                                Target},
                      resources::channels::channels_for_package_ident,
-                     services::metrics::Counter,
+                     services::{metrics::Counter,
+                                package_delete::{delete_blocker,
+                                                 do_delete_package}},
                      AppState}};
 use actix_web::{body::BoxBody,
                 http::{self,
@@ -60,17 +101,23 @@ use actix_web::{body::BoxBody,
                        StatusCode},
                 web::{self,
                       Data,
+                      Json,
                       Path,
                       Query,
                       ServiceConfig},
                 HttpRequest,
                 HttpResponse};
 use bytes::Bytes;
-use diesel::result::Error::NotFound;
+use diesel::{pg::PgConnection,
+             result::Error::NotFound,
+             Connection};
 use futures::{channel::mpsc,
               StreamExt};
 use serde::ser::Serialize;
-use std::{convert::Infallible,
+use std::{collections::{BTreeMap,
+                        HashMap,
+                        HashSet},
+          convert::Infallible,
           fs::{self,
                remove_file,
                File},
@@ -95,6 +142,199 @@ pub struct Upload {
     forced:   bool,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RdepsQuery {
+    #[serde(default)]
+    target:  Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    release: Option<String>,
+    #[serde(default)]
+    channel: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DepsTreeQuery {
+    #[serde(default)]
+    target: Option<String>,
+    #[serde(default)]
+    build:  bool,
+    #[serde(default)]
+    format: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PackageStatusReq {
+    pub status: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageImpact {
+    pub ident:            String,
+    pub target:           String,
+    pub in_stable:        bool,
+    pub dependents:       Vec<ReleaseDependent>,
+    // Dependents the requester isn't allowed to see
+    pub restricted_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeletePlanBlocker {
+    pub ident:  String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeletePlan {
+    pub ident:            String,
+    pub target:           String,
+    // Releases to delete, dependents first and the requested release last
+    pub packages:         Vec<String>,
+    pub blockers:         Vec<DeletePlanBlocker>,
+    pub restricted_count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeletePlanReq {
+    pub packages: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct PackageFileListing {
+    pub ident:  String,
+    pub target: String,
+    pub files:  Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct PackageBindListing {
+    pub ident:   String,
+    pub target:  String,
+    pub exports: Vec<PackageExport>,
+    pub binds:   Vec<PackageBind>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BindProvidersQuery {
+    // Comma separated list of the exports a provider has to have
+    pub exports: String,
+    #[serde(default)]
+    pub target:  Option<String>,
+    #[serde(default)]
+    pub origin:  Option<String>,
+    #[serde(default)]
+    pub channel: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FileSearchQuery {
+    // The path to look for, `*` matches any run of characters
+    pub path:    String,
+    #[serde(default)]
+    pub target:  Option<String>,
+    #[serde(default)]
+    pub origin:  Option<String>,
+    #[serde(default)]
+    pub channel: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiffQuery {
+    // Fully qualified idents of the two releases to compare
+    pub from:   String,
+    pub to:     String,
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PackageDiff {
+    pub from:     String,
+    pub to:       String,
+    pub target:   String,
+    pub deps:     DepsDiff,
+    pub tdeps:    DepsDiff,
+    pub exposes:  ListDiff<i32>,
+    // Exports, binds and files are unset when either release was uploaded before the hart
+    // contents were captured
+    pub exports:  Option<ListDiff<String>>,
+    pub binds:    Option<ListDiff<String>>,
+    pub metadata: Vec<FieldChange>,
+    // Line diff of the default.toml of both releases
+    pub config:   Vec<String>,
+    pub files:    Option<ListDiff<String>>,
+}
+
+// What is read from the hart contents at upload, the manifest is parsed separately
+#[derive(Default)]
+struct ArchiveContents {
+    files:   Vec<String>,
+    exports: Vec<NewPackageExport>,
+    binds:   Vec<NewPackageBind>,
+}
+
+#[derive(Serialize)]
+pub struct PackageStats {
+    pub origin:   String,
+    pub name:     String,
+    pub target:   String,
+    pub since:    String,
+    pub total:    i64,
+    pub releases: Vec<ReleaseDownloads>,
+    pub daily:    Vec<DailyDownloads>,
+}
+
+#[derive(Serialize)]
+pub struct SearchResults<'a> {
+    pub range_start: isize,
+    pub range_end:   isize,
+    pub total_count: isize,
+    pub data:        &'a [BuilderPackageIdent],
+    pub facets:      SearchFacets,
+}
+
+// The latest release of a package for each enabled target. Targets without a release the
+// caller can see are listed as missing instead of failing the request.
+#[derive(Default, Serialize)]
+pub struct LatestForTargets {
+    pub packages: BTreeMap<String, serde_json::Value>,
+    pub missing:  Vec<String>,
+}
+
+impl LatestForTargets {
+    pub fn add(&mut self, target: PackageTarget, found: Result<String>) -> Result<()> {
+        match found {
+            Ok(json_body) => {
+                self.packages.insert(target.to_string(), serde_json::from_str(&json_body)?);
+            }
+            Err(Error::NotFound) => self.missing.push(target.to_string()),
+            Err(err) => return Err(err),
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool { self.packages.is_empty() }
+}
+
+// Whether the caller can see a release of the package, for any target. Only releases of
+// the version are looked at when the ident has one.
+pub fn package_exists(req: &HttpRequest, ident: &PackageIdent) -> Result<bool> {
+    let opt_session_id = match authorize_session(req, None, None) {
+        Ok(session) => Some(session.get_id()),
+        Err(_) => None,
+    };
+
+    let mut conn = req_state(req).db.get_conn().map_err(Error::DbError)?;
+    let visibility = helpers::visibility_for_optional_session(req, opt_session_id, &ident.origin);
+    let platforms = Package::list_package_platforms(&BuilderPackageIdent(ident.clone()),
+                                                    visibility,
+                                                    &mut conn)?;
+    Ok(!platforms.is_empty())
+}
+
 pub struct Packages {}
 
 impl Packages {
@@ -104,16 +344,29 @@ impl Packages {
         cfg.route("/depot/pkgs/{origin}",
                   web::get().to(get_packages_for_origin))
            .route("/depot/pkgs/search/{query}", web::get().to(search_packages))
+           .route("/depot/binds/providers", web::get().to(get_bind_providers))
+           .route("/depot/files/search", web::get().to(search_files))
+           .route("/depot/targets", web::get().to(get_targets))
            .route("/depot/pkgs/{origin}/{pkg}",
                   web::get().to(get_packages_for_origin_package))
            .route("/depot/pkgs/{origin}/{pkg}/latest",
                   web::get().to(get_latest_package_for_origin_package))
+           .route("/depot/pkgs/{origin}/{pkg}/latest/_targets",
+                  web::get().to(get_latest_packages_for_all_targets))
            .route("/depot/pkgs/{origin}/{pkg}/versions",
                   web::get().to(list_package_versions))
+           .route("/depot/pkgs/{origin}/{pkg}/rdeps",
+                  web::get().to(get_package_rdeps))
+           .route("/depot/pkgs/{origin}/{pkg}/stats",
+                  web::get().to(get_package_stats))
+           .route("/depot/pkgs/{origin}/{pkg}/diff",
+                  web::get().to(get_package_diff))
            .route("/depot/pkgs/{origin}/{pkg}/{version}",
                   web::get().to(get_packages_for_origin_package_version))
            .route("/depot/pkgs/{origin}/{pkg}/{version}/latest",
                   web::get().to(get_latest_package_for_origin_package_version))
+           .route("/depot/pkgs/{origin}/{pkg}/{version}/latest/_targets",
+                  web::get().to(get_latest_version_packages_for_all_targets))
            .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}",
                   web::post().to(upload_package))
            .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}",
@@ -124,6 +377,28 @@ impl Packages {
                   web::get().to(download_package))
            .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}/channels",
                   web::get().to(get_package_channels))
+           .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}/manifest",
+                  web::get().to(get_package_manifest))
+           .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}/config",
+                  web::get().to(get_package_config))
+           .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}/binds",
+                  web::get().to(get_package_binds))
+           .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}/files",
+                  web::get().to(get_package_files))
+           .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}/advisories",
+                  web::get().to(get_package_advisories))
+           .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}/sbom",
+                  web::get().to(get_package_sbom))
+           .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}/deps/tree",
+                  web::get().to(get_package_deps_tree))
+           .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}/status",
+                  web::put().to(update_package_status))
+           .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}/impact",
+                  web::get().to(get_package_impact))
+           .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}/delete_plan",
+                  web::get().to(get_package_delete_plan))
+           .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}/delete_plan",
+                  web::post().to(execute_package_delete_plan))
            .route("/depot/pkgs/{origin}/{pkg}/{version}/{release}/{visibility}",
                   web::patch().to(package_privacy_toggle));
     }
@@ -135,12 +410,18 @@ impl Packages {
 #[allow(clippy::needless_pass_by_value)]
 async fn get_packages_for_origin(req: HttpRequest,
                                  path: Path<String>,
+                                 qlabels: Query<LabelQuery>,
                                  pagination: Query<Pagination>)
                                  -> HttpResponse {
     let origin = path.into_inner();
     let ident = PackageIdent::new(origin, String::from(""), None, None);
 
-    match do_get_packages(&req, &ident, &pagination) {
+    let labels = match helpers::label_filter(qlabels.label.as_deref()) {
+        Ok(labels) => labels,
+        Err(msg) => return HttpResponse::UnprocessableEntity().body(msg),
+    };
+
+    match do_get_packages(&req, &ident, labels, &pagination) {
         Ok((packages, count)) => {
             postprocess_extended_package_list(&req, &packages, count, &pagination)
         }
@@ -154,13 +435,19 @@ async fn get_packages_for_origin(req: HttpRequest,
 #[allow(clippy::needless_pass_by_value)]
 async fn get_packages_for_origin_package(req: HttpRequest,
                                          path: Path<(String, String)>,
+                                         qlabels: Query<LabelQuery>,
                                          pagination: Query<Pagination>)
                                          -> HttpResponse {
     let (origin, pkg) = path.into_inner();
 
     let ident = PackageIdent::new(origin, pkg, None, None);
 
-    match do_get_packages(&req, &ident, &pagination) {
+    let labels = match helpers::label_filter(qlabels.label.as_deref()) {
+        Ok(labels) => labels,
+        Err(msg) => return HttpResponse::UnprocessableEntity().body(msg),
+    };
+
+    match do_get_packages(&req, &ident, labels, &pagination) {
         Ok((packages, count)) => {
             postprocess_extended_package_list(&req, &packages, count, &pagination)
         }
@@ -180,7 +467,7 @@ async fn get_packages_for_origin_package_version(req: HttpRequest,
 
     let ident = PackageIdent::new(origin, pkg, Some(version), None);
 
-    match do_get_packages(&req, &ident, &pagination) {
+    match do_get_packages(&req, &ident, Vec::new(), &pagination) {
         Ok((packages, count)) => {
             postprocess_extended_package_list(&req, &packages, count, &pagination)
         }
@@ -200,7 +487,13 @@ async fn get_latest_package_for_origin_package(req: HttpRequest,
 
     let ident = PackageIdent::new(origin, pkg, None, None);
 
-    match do_get_package(&req, &qtarget, &ident).await {
+    // TODO: Deprecate target from headers
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    match do_get_package_for_target(&req, &ident, target).await {
         Ok(json_body) => {
             HttpResponse::Ok().append_header((http::header::CONTENT_TYPE,
                                               headers::APPLICATION_JSON))
@@ -215,6 +508,55 @@ async fn get_latest_package_for_origin_package(req: HttpRequest,
     }
 }
 
+#[allow(clippy::needless_pass_by_value)]
+async fn get_latest_packages_for_all_targets(req: HttpRequest,
+                                             path: Path<(String, String)>)
+                                             -> HttpResponse {
+    let (origin, pkg) = path.into_inner();
+
+    let ident = PackageIdent::new(origin, pkg, None, None);
+
+    latest_packages_for_all_targets(&req, &ident).await
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_latest_version_packages_for_all_targets(req: HttpRequest,
+                                                     path: Path<(String, String, String)>)
+                                                     -> HttpResponse {
+    let (origin, pkg, version) = path.into_inner();
+
+    let ident = PackageIdent::new(origin, pkg, Some(version), None);
+
+    latest_packages_for_all_targets(&req, &ident).await
+}
+
+async fn latest_packages_for_all_targets(req: &HttpRequest, ident: &PackageIdent) -> HttpResponse {
+    let targets = req_state(req).config.api.targets.clone();
+    let mut latest = LatestForTargets::default();
+    for target in targets {
+        let found = do_get_package_for_target(req, ident, target).await;
+        if let Err(err) = latest.add(target, found) {
+            debug!("{}", err);
+            return err.into();
+        }
+    }
+
+    if latest.is_empty() {
+        match package_exists(req, ident) {
+            Ok(true) => (),
+            Ok(false) => return HttpResponse::NotFound().finish(),
+            Err(err) => {
+                debug!("{}", err);
+                return err.into();
+            }
+        }
+    }
+
+    HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                      headers::Cache::NoCache.to_string()))
+                      .json(latest)
+}
+
 #[allow(clippy::needless_pass_by_value)]
 async fn get_latest_package_for_origin_package_version(req: HttpRequest,
                                                        path: Path<(String, String, String)>,
@@ -224,7 +566,13 @@ async fn get_latest_package_for_origin_package_version(req: HttpRequest,
 
     let ident = PackageIdent::new(origin, pkg, Some(version), None);
 
-    match do_get_package(&req, &qtarget, &ident).await {
+    // TODO: Deprecate target from headers
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    match do_get_package_for_target(&req, &ident, target).await {
         Ok(json_body) => {
             HttpResponse::Ok().append_header((http::header::CONTENT_TYPE,
                                               headers::APPLICATION_JSON))
@@ -248,7 +596,13 @@ async fn get_package(req: HttpRequest,
 
     let ident = PackageIdent::new(origin, pkg, Some(version), Some(release));
 
-    match do_get_package(&req, &qtarget, &ident).await {
+    // TODO: Deprecate target from headers
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    match do_get_package_for_target(&req, &ident, target).await {
         Ok(json_body) => {
             HttpResponse::Ok().append_header((http::header::CONTENT_TYPE,
                                               headers::APPLICATION_JSON))
@@ -263,6 +617,217 @@ async fn get_package(req: HttpRequest,
     }
 }
 
+#[allow(clippy::needless_pass_by_value)]
+async fn get_package_manifest(req: HttpRequest,
+                              path: Path<(String, String, String, String)>,
+                              qtarget: Query<Target>,
+                              state: Data<AppState>)
+                              -> HttpResponse {
+    let (origin, pkg, version, release) = path.into_inner();
+    let ident = PackageIdent::new(origin, pkg, Some(version), Some(release));
+
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    match get_visible_package(&req, &ident, target, &mut conn) {
+        Ok(package) => {
+            HttpResponse::Ok().content_type(ContentType::plaintext())
+                              .append_header((http::header::CACHE_CONTROL,
+                                              headers::Cache::default().to_string()))
+                              .body(package.manifest)
+        }
+        Err(err) => {
+            debug!("{}", err);
+            err.into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_package_config(req: HttpRequest,
+                            path: Path<(String, String, String, String)>,
+                            qtarget: Query<Target>,
+                            state: Data<AppState>)
+                            -> HttpResponse {
+    let (origin, pkg, version, release) = path.into_inner();
+    let ident = PackageIdent::new(origin, pkg, Some(version), Some(release));
+
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    match get_visible_package(&req, &ident, target, &mut conn) {
+        // Packages without a default.toml are stored with an empty config
+        Ok(package) if package.config.is_empty() => HttpResponse::NotFound().finish(),
+        Ok(package) => {
+            HttpResponse::Ok().content_type(ContentType::plaintext())
+                              .append_header((http::header::CACHE_CONTROL,
+                                              headers::Cache::default().to_string()))
+                              .body(package.config)
+        }
+        Err(err) => {
+            debug!("{}", err);
+            err.into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_package_files(req: HttpRequest,
+                           path: Path<(String, String, String, String)>,
+                           qtarget: Query<Target>,
+                           state: Data<AppState>)
+                           -> HttpResponse {
+    let (origin, pkg, version, release) = path.into_inner();
+    let ident = PackageIdent::new(origin, pkg, Some(version), Some(release));
+
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let package = match get_visible_package(&req, &ident, target, &mut conn) {
+        Ok(package) => package,
+        Err(err) => {
+            debug!("{}", err);
+            return err.into();
+        }
+    };
+
+    // Packages uploaded before file listings were captured have none
+    match PackageFiles::get(package.id, &mut conn) {
+        Ok(files) => {
+            let listing = PackageFileListing { ident:  package.ident.to_string(),
+                                               target: package.target.to_string(),
+                                               files:  files.files, };
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                              headers::Cache::default().to_string()))
+                              .json(listing)
+        }
+        Err(err) => {
+            debug!("{}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_package_binds(req: HttpRequest,
+                           path: Path<(String, String, String, String)>,
+                           qtarget: Query<Target>,
+                           state: Data<AppState>)
+                           -> HttpResponse {
+    let (origin, pkg, version, release) = path.into_inner();
+    let ident = PackageIdent::new(origin, pkg, Some(version), Some(release));
+
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let package = match get_visible_package(&req, &ident, target, &mut conn) {
+        Ok(package) => package,
+        Err(err) => {
+            debug!("{}", err);
+            return err.into();
+        }
+    };
+
+    // Packages uploaded before binds were captured have none
+    let listing = PackageBinds::exports(package.id, &mut conn).and_then(|exports| {
+                      let binds = PackageBinds::binds(package.id, &mut conn)?;
+                      Ok(PackageBindListing { ident: package.ident.to_string(),
+                                              target: package.target.to_string(),
+                                              exports,
+                                              binds })
+                  });
+
+    match listing {
+        Ok(listing) => {
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                              headers::Cache::default().to_string()))
+                              .json(listing)
+        }
+        Err(err) => {
+            debug!("{}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_bind_providers(req: HttpRequest,
+                            qproviders: Query<BindProvidersQuery>,
+                            pagination: Query<Pagination>,
+                            state: Data<AppState>)
+                            -> HttpResponse {
+    let opt_session_id = match authorize_session(&req, None, None) {
+        Ok(session) => Some(session.get_id() as i64),
+        Err(_) => None,
+    };
+
+    let exports = qproviders.exports
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|e| !e.is_empty())
+                            .map(str::to_string)
+                            .collect::<Vec<String>>();
+    if exports.is_empty() {
+        return HttpResponse::new(StatusCode::BAD_REQUEST);
+    }
+
+    let target = match helpers::requested_target(&req, qproviders.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let (page, per_page) = helpers::extract_pagination_in_pages(&pagination);
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let list = ListBindProviders { exports:    &exports,
+                                   target:     &target,
+                                   origin:     qproviders.origin.as_deref(),
+                                   channel:    qproviders.channel.as_deref(),
+                                   account_id: opt_session_id,
+                                   page:       page as i64,
+                                   limit:      per_page as i64, };
+
+    match binds::get_bind_providers(&mut conn, &list) {
+        Ok((providers, count)) => postprocess_package_list(&req, &providers, count, &pagination),
+        Err(err) => {
+            debug!("{}", err);
+            err.into()
+        }
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 async fn delete_package(req: HttpRequest,
                         path: Path<(String, String, String, String)>,
@@ -271,27 +836,178 @@ async fn delete_package(req: HttpRequest,
                         -> HttpResponse {
     let (origin, pkg, version, release) = path.into_inner();
 
-    if let Err(err) = authorize_session(&req, Some(&origin), Some(OriginMemberRole::Member)) {
-        return err.into();
-    }
+    let session = match authorize_session(&req, Some(&origin), Some(OriginMemberRole::Member)) {
+        Ok(session) => session,
+        Err(err) => return err.into(),
+    };
 
-    let ident = PackageIdent::new(origin.clone(), pkg.clone(), Some(version), Some(release));
+    let ident = PackageIdent::new(origin, pkg, Some(version), Some(release));
 
     // TODO: Deprecate target from headers
-    let target = match qtarget.target {
-        Some(ref t) => {
-            trace!("Query requested target = {}", t);
-            match PackageTarget::from_str(t) {
-                Ok(t) => t,
-                Err(err) => {
-                    debug!("Invalid target requested: {}, err = {:?}", t, err);
-                    let body = Bytes::from(format!("Invalid package target '{}'", t).into_bytes());
-                    return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY,
-                                                   BoxBody::new(body));
-                }
-            }
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    match delete_blocker(&ident, target, &mut conn) {
+        Ok(None) => (),
+        Ok(Some(reason)) => {
+            debug!("{}", reason);
+            let body = Bytes::from(reason.into_bytes());
+            return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body));
+        }
+        Err(err) => {
+            debug!("{}", err);
+            return err.into();
+        }
+    }
+
+    match do_delete_package(&ident, target, &mut conn) {
+        Ok(_) => {
+            origin_audit(&ident.origin,
+                         OriginOperation::PackageDelete,
+                         &format!("{} ({})", ident, target),
+                         session.get_id() as i64,
+                         session.get_name(),
+                         &mut conn);
+            delete_archive(&state, &ident, target).await;
+            state.memcache.borrow_mut().clear_cache_for_package(&ident);
+            HttpResponse::NoContent().finish()
+        }
+        Err(err) => {
+            debug!("{}", err);
+            err.into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_package_impact(req: HttpRequest,
+                            path: Path<(String, String, String, String)>,
+                            qtarget: Query<Target>,
+                            state: Data<AppState>)
+                            -> HttpResponse {
+    let (origin, pkg, version, release) = path.into_inner();
+
+    let session = match authorize_session(&req, Some(&origin), Some(OriginMemberRole::Member)) {
+        Ok(session) => session,
+        Err(err) => return err.into(),
+    };
+
+    let ident = PackageIdent::new(origin, pkg, Some(version), Some(release));
+
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let channels = match Package::list_package_channels(&BuilderPackageIdent(ident.clone()),
+                                                        target,
+                                                        PackageVisibility::all(),
+                                                        &mut conn)
+    {
+        Ok(channels) => channels,
+        Err(err) => {
+            debug!("{}", err);
+            return Error::DieselError(err).into();
         }
-        None => helpers::target_from_headers(&req),
+    };
+
+    match reverse_dependencies::get_release_rdeps(&mut conn,
+                                                  &ident.to_string(),
+                                                  &target.to_string(),
+                                                  Some(session.get_id() as i64),
+                                                  false)
+    {
+        Ok(rdeps) => {
+            let (dependents, restricted): (Vec<_>, Vec<_>) =
+                rdeps.into_iter().partition(|d| d.visible);
+            let in_stable = channels.iter()
+                                    .any(|c| c.name == ChannelIdent::stable().to_string());
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                              headers::Cache::NoCache.to_string()))
+                              .json(PackageImpact { ident: ident.to_string(),
+                                                    target: target.to_string(),
+                                                    in_stable,
+                                                    dependents,
+                                                    restricted_count: restricted.len() })
+        }
+        Err(err) => {
+            debug!("{}", err);
+            err.into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_package_delete_plan(req: HttpRequest,
+                                 path: Path<(String, String, String, String)>,
+                                 qtarget: Query<Target>,
+                                 state: Data<AppState>)
+                                 -> HttpResponse {
+    let (origin, pkg, version, release) = path.into_inner();
+
+    let session = match authorize_session(&req, Some(&origin), Some(OriginMemberRole::Owner)) {
+        Ok(session) => session,
+        Err(err) => return err.into(),
+    };
+
+    let ident = PackageIdent::new(origin, pkg, Some(version), Some(release));
+
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    match do_get_delete_plan(&ident, target, session.get_id() as i64, &mut conn) {
+        Ok(plan) => {
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                              headers::Cache::NoCache.to_string()))
+                              .json(plan)
+        }
+        Err(err) => {
+            debug!("{}", err);
+            err.into()
+        }
+    }
+}
+
+// Executes a delete plan previously reviewed with get_package_delete_plan. The plan is
+// computed again and only carried out when it still matches the reviewed one.
+#[allow(clippy::needless_pass_by_value)]
+async fn execute_package_delete_plan(req: HttpRequest,
+                                     path: Path<(String, String, String, String)>,
+                                     qtarget: Query<Target>,
+                                     body: Json<DeletePlanReq>,
+                                     state: Data<AppState>)
+                                     -> HttpResponse {
+    let (origin, pkg, version, release) = path.into_inner();
+
+    let session = match authorize_session(&req, Some(&origin), Some(OriginMemberRole::Owner)) {
+        Ok(session) => session,
+        Err(err) => return err.into(),
+    };
+
+    let ident = PackageIdent::new(origin, pkg, Some(version), Some(release));
+
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
     };
 
     let mut conn = match state.db.get_conn().map_err(Error::DbError) {
@@ -299,69 +1015,54 @@ async fn delete_package(req: HttpRequest,
         Err(err) => return err.into(),
     };
 
-    // Check whether package is in stable channel
-    match Package::list_package_channels(&BuilderPackageIdent(ident.clone()),
-                                         target,
-                                         PackageVisibility::all(),
-                                         &mut conn)
-    {
-        Ok(channels) => {
-            if channels.iter()
-                       .any(|c| c.name == ChannelIdent::stable().to_string())
-            {
-                debug!("Deleting package in stable channel not allowed: {}", ident);
-                let body = Bytes::from(format!("Deleting package in stable channel not allowed \
-                                                '{}'",
-                                               ident).into_bytes());
-                return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY,
-                                               BoxBody::new(body));
-            }
-        }
-        Err(err) => {
-            debug!("{}", err);
-            return Error::DieselError(err).into();
-        }
-    }
-
-    match reverse_dependencies::get_rdeps(&mut conn, &origin, &pkg, &target).await {
-        Ok(reverse_depenencies) => {
-            if !reverse_depenencies.rdeps.is_empty() {
-                let body = Bytes::from(format!("Deleting package with rdeps not allowed '{}'",
-                                               ident).into_bytes());
-                return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY,
-                                               BoxBody::new(body));
-            }
-        }
+    let plan = match do_get_delete_plan(&ident, target, session.get_id() as i64, &mut conn) {
+        Ok(plan) => plan,
         Err(err) => {
             debug!("{}", err);
             return err.into();
         }
+    };
+
+    if !plan.blockers.is_empty() || plan.restricted_count > 0 {
+        return HttpResponse::UnprocessableEntity().json(plan);
     }
 
-    // TODO (SA): Wrap in transaction, or better yet, eliminate need to do
-    // channel package deletion
-    let pkg = match Package::get(GetPackage { ident:      BuilderPackageIdent(ident.clone()),
-                                              visibility: PackageVisibility::all(),
-                                              target:     BuilderPackageTarget(target), },
-                                 &mut conn).map_err(Error::DieselError)
-    {
-        Ok(pkg) => pkg,
-        Err(err) => return err.into(),
-    };
+    if plan.packages != body.packages {
+        debug!("Delete plan for {} changed since it was reviewed", ident);
+        return HttpResponse::Conflict().json(plan);
+    }
 
-    if let Err(err) = Channel::delete_channel_package(pkg.id, &mut conn).map_err(Error::DieselError)
-    {
-        debug!("{}", err);
-        return err.into();
+    let mut idents = Vec::with_capacity(plan.packages.len());
+    for p in plan.packages.iter() {
+        match PackageIdent::from_str(p) {
+            Ok(p) => idents.push(p),
+            Err(err) => return Error::BiomeCore(err).into(),
+        }
     }
 
-    match Package::delete(DeletePackage { ident:  BuilderPackageIdent(ident.clone()),
-                                          target: BuilderPackageTarget(target), },
-                          &mut conn).map_err(Error::DieselError)
-    {
+    let result = conn.transaction::<_, Error, _>(|conn| {
+                         for p in idents.iter() {
+                             do_delete_package(p, target, conn)?;
+                         }
+                         Ok(())
+                     });
+
+    match result {
         Ok(_) => {
-            state.memcache.borrow_mut().clear_cache_for_package(&ident);
-            HttpResponse::NoContent().finish()
+            for p in idents.iter() {
+                origin_audit(&p.origin,
+                             OriginOperation::PackageDelete,
+                             &format!("{} ({})", p, target),
+                             session.get_id() as i64,
+                             session.get_name(),
+                             &mut conn);
+                delete_archive(&state, p, target).await;
+            }
+            let mut memcache = state.memcache.borrow_mut();
+            for p in idents.iter() {
+                memcache.clear_cache_for_package(p);
+            }
+            HttpResponse::Ok().json(serde_json::json!({ "deleted": plan.packages }))
         }
         Err(err) => {
             debug!("{}", err);
@@ -395,27 +1096,11 @@ async fn download_package(req: HttpRequest,
     vis.push(PackageVisibility::Hidden);
 
     // TODO: Deprecate target from headers
-    let target = match qtarget.target {
-        Some(ref t) => {
-            trace!("Query requested target = {}", t);
-            match PackageTarget::from_str(t) {
-                Ok(t) => t,
-                Err(err) => {
-                    debug!("Invalid target requested: {}, err = {:?}", t, err);
-                    let body = Bytes::from(format!("Invalid package target '{}'", t).into_bytes());
-                    return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY,
-                                                   BoxBody::new(body));
-                }
-            }
-        }
-        None => helpers::target_from_headers(&req),
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
     };
 
-    if !state.config.api.targets.contains(&target) {
-        let body = Bytes::from(format!("Invalid package target '{}'", target).into_bytes());
-        return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body));
-    }
-
     match Package::get(GetPackage { ident:      BuilderPackageIdent(ident.clone()),
                                     visibility: vis,
                                     target:     BuilderPackageTarget(target), },
@@ -520,6 +1205,7 @@ async fn download_package(req: HttpRequest,
                            .await
                 {
                     Ok(archive) => {
+                        record_download(package.id, &mut conn);
                         download_response_for_archive(&archive, &file_path, is_private, &state)
                     }
                     Err(e) => {
@@ -534,6 +1220,7 @@ async fn download_package(req: HttpRequest,
                            .await
                 {
                     Ok(archive) => {
+                        record_download(package.id, &mut conn);
                         download_response_for_archive(&archive, &file_path, is_private, &state)
                     }
                     Err(e) => {
@@ -567,7 +1254,13 @@ async fn upload_package(req: HttpRequest,
         return Ok(HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body)));
     }
 
-    match do_upload_package_start(&req, &qupload, &ident) {
+    // TODO: Deprecate target from headers
+    let target = match helpers::requested_target(&req, qupload.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return Ok(resp),
+    };
+
+    match do_upload_package_start(&req, &qupload, &ident, target) {
         Ok((temp_path, writer)) => {
             state.memcache.borrow_mut().clear_cache_for_package(&ident);
             do_upload_package_async(req, stream, qupload, ident, temp_path, writer).await
@@ -612,20 +1305,9 @@ async fn get_package_channels(req: HttpRequest,
     }
 
     // TODO: Deprecate target from headers
-    let target = match qtarget.target {
-        Some(ref t) => {
-            trace!("Query requested target = {}", t);
-            match PackageTarget::from_str(t) {
-                Ok(t) => t,
-                Err(err) => {
-                    debug!("Invalid target requested: {}, err = {:?}", t, err);
-                    let body = Bytes::from(format!("Invalid package target '{}'", t).into_bytes());
-                    return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY,
-                                                   BoxBody::new(body));
-                }
-            }
-        }
-        None => helpers::target_from_headers(&req),
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
     };
 
     match Package::list_package_channels(&BuilderPackageIdent(ident.clone()),
@@ -639,7 +1321,361 @@ async fn get_package_channels(req: HttpRequest,
             let list: Vec<ChannelWithPromotion> =
                 channels.into_iter().map(|channel| channel.into()).collect();
             HttpResponse::Ok().append_header((http::header::CACHE_CONTROL, headers::NO_CACHE))
-                              .json(list)
+                              .json(list)
+        }
+        Err(err) => {
+            debug!("{}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_package_deps_tree(req: HttpRequest,
+                               path: Path<(String, String, String, String)>,
+                               qtree: Query<DepsTreeQuery>,
+                               state: Data<AppState>)
+                               -> HttpResponse {
+    let (origin, name, version, release) = path.into_inner();
+
+    let opt_session_id = match authorize_session(&req, None, None) {
+        Ok(session) => Some(session.get_id()),
+        Err(_) => None,
+    };
+
+    let dot = match qtree.format.as_deref() {
+        None | Some("json") => false,
+        Some("dot") => true,
+        Some(f) => {
+            let body = Bytes::from(format!("Invalid format '{}'", f).into_bytes());
+            return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body));
+        }
+    };
+
+    let target = match helpers::requested_target(&req, qtree.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let ident = PackageIdent::new(origin, name, Some(version), Some(release));
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let root = match Package::get(
+        GetPackage {
+            ident: BuilderPackageIdent(ident.clone()),
+            visibility: helpers::visibility_for_optional_session(&req,
+                                                                 opt_session_id,
+                                                                 &ident.origin),
+            target: BuilderPackageTarget(target),
+        },
+        &mut conn,
+    ) {
+        Ok(root) => root,
+        Err(NotFound) => return HttpResponse::new(StatusCode::NOT_FOUND),
+        Err(err) => {
+            debug!("{}", err);
+            return Error::DieselError(err).into();
+        }
+    };
+
+    let mut idents: Vec<BuilderPackageIdent> = root.tdeps.clone();
+    if qtree.build {
+        idents.extend(root.build_deps.iter().cloned());
+        idents.extend(root.build_tdeps.iter().cloned());
+    }
+
+    let dependencies =
+        match helpers::visible_package_group(&req, opt_session_id, idents, target, &mut conn) {
+            Ok(dependencies) => dependencies,
+            Err(err) => {
+                debug!("{}", err);
+                return Error::DieselError(err).into();
+            }
+        };
+
+    let mut packages: HashMap<String, PackageDeps> = HashMap::new();
+    packages.insert(root.ident.to_string(),
+                    PackageDeps::new(&root.deps, &root.build_deps));
+    for pkg in dependencies.iter() {
+        packages.insert(pkg.ident.to_string(),
+                        PackageDeps::new(&pkg.deps, &pkg.build_deps));
+    }
+
+    let tree = dependency_tree::build_tree(&root.ident.to_string(), &packages, qtree.build);
+
+    if dot {
+        HttpResponse::Ok().append_header((http::header::CONTENT_TYPE, "text/vnd.graphviz"))
+                          .append_header((http::header::CACHE_CONTROL, headers::NO_CACHE))
+                          .body(dependency_tree::to_dot(&tree))
+    } else {
+        HttpResponse::Ok().append_header((http::header::CACHE_CONTROL, headers::NO_CACHE))
+                          .json(tree)
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_package_advisories(req: HttpRequest,
+                                path: Path<(String, String, String, String)>,
+                                qtarget: Query<Target>,
+                                state: Data<AppState>)
+                                -> HttpResponse {
+    let (origin, name, version, release) = path.into_inner();
+    let ident = PackageIdent::new(origin, name, Some(version), Some(release));
+
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    // Dependencies are matched by their stored idents, whether or not they are visible
+    let result = get_visible_package(&req, &ident, target, &mut conn).and_then(|package| {
+        let (ranges, known) =
+            advisories::load_for(Some(&package.ident).into_iter().chain(package.tdeps.iter()),
+                                 &mut conn)?;
+        Ok(PackageAdvisories { ident:      package.ident.to_string(),
+                               target:     package.target.to_string(),
+                               advisories: advisories::match_advisories(&package.ident,
+                                                                        &package.tdeps,
+                                                                        &ranges,
+                                                                        &known), })
+    });
+
+    match result {
+        Ok(report) => {
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL, headers::NO_CACHE))
+                              .json(report)
+        }
+        Err(err) => {
+            debug!("{}", err);
+            err.into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_package_sbom(req: HttpRequest,
+                          path: Path<(String, String, String, String)>,
+                          qsbom: Query<SbomQuery>,
+                          state: Data<AppState>)
+                          -> HttpResponse {
+    let (origin, name, version, release) = path.into_inner();
+    let ident = PackageIdent::new(origin, name, Some(version), Some(release));
+
+    let opt_session_id = match authorize_session(&req, None, None) {
+        Ok(session) => Some(session.get_id()),
+        Err(_) => None,
+    };
+
+    let format = match qsbom.format.as_deref().map(SbomFormat::from_str) {
+        None => SbomFormat::CycloneDx,
+        Some(Ok(format)) => format,
+        Some(Err(msg)) => {
+            let body = Bytes::from(msg.into_bytes());
+            return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body));
+        }
+    };
+
+    let target = match helpers::requested_target(&req, qsbom.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let result = get_visible_package(&req, &ident, target, &mut conn).and_then(|root| {
+                     let packages =
+                         sbom::load_packages(&req, opt_session_id, root.tdeps.clone(), target,
+                                             &mut conn)?;
+                     Ok(Sbom { subject: SbomSubject::Release(SbomPackage::from_package(&root)),
+                               packages })
+                 });
+
+    // Every rendering has its own serial number and timestamp
+    match result {
+        Ok(sbom) => {
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                              headers::Cache::NoCache.to_string()))
+                              .json(sbom.render(format))
+        }
+        Err(err) => {
+            debug!("{}", err);
+            err.into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn list_package_versions(req: HttpRequest,
+                               path: Path<(String, String)>,
+                               state: Data<AppState>)
+                               -> HttpResponse {
+    let (origin, name) = path.into_inner();
+
+    let opt_session_id = match authorize_session(&req, None, None) {
+        Ok(session) => Some(session.get_id()),
+        Err(_) => None,
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let ident = PackageIdent::new(origin.to_string(), name, None, None);
+
+    match Package::list_package_versions(&BuilderPackageIdent(ident.clone()),
+                                         helpers::visibility_for_optional_session(&req,
+                                                                                  opt_session_id,
+                                                                                  &origin),
+                                         &mut conn)
+    {
+        Ok(packages) => {
+            trace!(target: "biome_builder_api::server::resources::pkgs::versions", "list_package_versions for {} found {} package versions: {:?}", ident, packages.len(), packages);
+
+            // A version groups several releases, so it only carries the package labels
+            let labels = match PackageLabels::package(&ident.origin, &ident.name, &mut conn) {
+                Ok(labels) => labels,
+                Err(err) => {
+                    debug!("{}", err);
+                    return Error::DieselError(err).into();
+                }
+            };
+            let versions = packages.into_iter()
+                                   .map(|version| {
+                                       let mut version_json = json!(version);
+                                       version_json["labels"] = json!(labels);
+                                       version_json
+                                   })
+                                   .collect::<Vec<_>>();
+
+            let body = serde_json::to_string(&versions).unwrap();
+            HttpResponse::Ok().append_header((http::header::CONTENT_TYPE,
+                                              headers::APPLICATION_JSON))
+                              .append_header((http::header::CACHE_CONTROL, headers::NO_CACHE))
+                              .body(body)
+        }
+        Err(err) => {
+            debug!("{}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_package_rdeps(req: HttpRequest,
+                           path: Path<(String, String)>,
+                           qrdeps: Query<RdepsQuery>,
+                           pagination: Query<Pagination>,
+                           state: Data<AppState>)
+                           -> HttpResponse {
+    let (origin, name) = path.into_inner();
+
+    let opt_session_id = match authorize_session(&req, None, None) {
+        Ok(session) => Some(session.get_id() as i64),
+        Err(_) => None,
+    };
+
+    let target = match helpers::requested_target(&req, qrdeps.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let (page, per_page) = helpers::extract_pagination_in_pages(&pagination);
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    match reverse_dependencies::get_versioned_rdeps(
+        &mut conn,
+        &reverse_dependencies::ListVersionedRdeps {
+            origin: &origin,
+            name: &name,
+            target: &target,
+            version: qrdeps.version.as_deref(),
+            release: qrdeps.release.as_deref(),
+            channel: qrdeps.channel.as_deref(),
+            account_id: opt_session_id,
+            page: page as i64,
+            limit: per_page as i64,
+        },
+    ) {
+        Ok((rdeps, count)) => postprocess_package_list(&req, &rdeps, count, &pagination),
+        Err(err) => {
+            debug!("{}", err);
+            err.into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_package_stats(req: HttpRequest,
+                           path: Path<(String, String)>,
+                           qstats: Query<StatsQuery>,
+                           state: Data<AppState>)
+                           -> HttpResponse {
+    let (origin, name) = path.into_inner();
+
+    let opt_session_id = match authorize_session(&req, None, None) {
+        Ok(session) => Some(session.get_id()),
+        Err(_) => None,
+    };
+
+    let target = match helpers::requested_target(&req, qstats.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let since = match helpers::stats_since(qstats.days) {
+        Some(since) => since,
+        None => {
+            let body = Bytes::from(format!("Days must be between 1 and {}",
+                                           helpers::STATS_DAYS_MAX).into_bytes());
+            return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body));
+        }
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let visibility = helpers::visibility_for_optional_session(&req, opt_session_id, &origin);
+    let lpd = ListPackageDownloads { origin: &origin,
+                                     name: &name,
+                                     target: BuilderPackageTarget(target),
+                                     visibility: &visibility,
+                                     since };
+
+    let result = Downloads::by_release(&lpd, &mut conn).and_then(|releases| {
+                     Downloads::by_day(&lpd, &mut conn).map(|daily| (releases, daily))
+                 });
+
+    match result {
+        Ok((releases, daily)) => {
+            let total = releases.iter().filter_map(|r| r.downloads).sum();
+            let stats = PackageStats { origin,
+                                       name,
+                                       target: target.to_string(),
+                                       since: since.to_string(),
+                                       total,
+                                       releases,
+                                       daily };
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL, headers::NO_CACHE))
+                              .json(stats)
         }
         Err(err) => {
             debug!("{}", err);
@@ -649,15 +1685,34 @@ async fn get_package_channels(req: HttpRequest,
 }
 
 #[allow(clippy::needless_pass_by_value)]
-async fn list_package_versions(req: HttpRequest,
-                               path: Path<(String, String)>,
-                               state: Data<AppState>)
-                               -> HttpResponse {
+async fn get_package_diff(req: HttpRequest,
+                          path: Path<(String, String)>,
+                          qdiff: Query<DiffQuery>,
+                          state: Data<AppState>)
+                          -> HttpResponse {
     let (origin, name) = path.into_inner();
 
-    let opt_session_id = match authorize_session(&req, None, None) {
-        Ok(session) => Some(session.get_id()),
-        Err(_) => None,
+    // Both releases have to belong to the package in the path
+    let mut idents = Vec::new();
+    for ident in [&qdiff.from, &qdiff.to].iter() {
+        match PackageIdent::from_str(ident) {
+            Ok(ident)
+                if ident.fully_qualified() && ident.origin == origin && ident.name == name =>
+            {
+                idents.push(ident)
+            }
+            _ => {
+                let body = Bytes::from(format!("Invalid release '{}' of {}/{}", ident, origin,
+                                               name).into_bytes());
+                return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY,
+                                               BoxBody::new(body));
+            }
+        }
+    }
+
+    let target = match helpers::requested_target(&req, qdiff.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
     };
 
     let mut conn = match state.db.get_conn().map_err(Error::DbError) {
@@ -665,30 +1720,36 @@ async fn list_package_versions(req: HttpRequest,
         Err(err) => return err.into(),
     };
 
-    let ident = PackageIdent::new(origin.to_string(), name, None, None);
-
-    match Package::list_package_versions(&BuilderPackageIdent(ident.clone()),
-                                         helpers::visibility_for_optional_session(&req,
-                                                                                  opt_session_id,
-                                                                                  &origin),
-                                         &mut conn)
-    {
-        Ok(packages) => {
-            trace!(target: "biome_builder_api::server::resources::pkgs::versions", "list_package_versions for {} found {} package versions: {:?}", ident, packages.len(), packages);
+    let packages = get_visible_package(&req, &idents[0], target, &mut conn).and_then(|from| {
+                       let to = get_visible_package(&req, &idents[1], target, &mut conn)?;
+                       Ok((from, to))
+                   });
 
-            let body = serde_json::to_string(&packages).unwrap();
-            HttpResponse::Ok().append_header((http::header::CONTENT_TYPE,
-                                              headers::APPLICATION_JSON))
-                              .append_header((http::header::CACHE_CONTROL, headers::NO_CACHE))
-                              .body(body)
+    match packages.and_then(|(from, to)| do_get_package_diff(&from, &to, &mut conn)) {
+        Ok(diff) => {
+            HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                              headers::Cache::default().to_string()))
+                              .json(diff)
         }
         Err(err) => {
             debug!("{}", err);
-            Error::DieselError(err).into()
+            err.into()
         }
     }
 }
 
+// The targets packages can be uploaded and fetched for, and the one used when a request
+// doesn't name any
+async fn get_targets(req: HttpRequest) -> HttpResponse {
+    let api = &req_state(&req).config.api;
+    let targets = api.targets.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+    HttpResponse::Ok().append_header((http::header::CACHE_CONTROL,
+                                      headers::Cache::NoCache.to_string()))
+                      .json(json!({ "targets": targets,
+                                    "default": api.default_target.to_string() }))
+}
+
 #[allow(clippy::needless_pass_by_value)]
 async fn search_packages(req: HttpRequest,
                          path: Path<String>,
@@ -711,17 +1772,8 @@ async fn search_packages(req: HttpRequest,
 
     let (page, per_page) = helpers::extract_pagination_in_pages(&pagination);
 
-    // First, try to parse the query like it's a PackageIdent, since it seems reasonable to expect
-    // that many people will try searching using that kind of string, e.g. core/redis.  If that
-    // works, set the origin appropriately and do a regular search.  If that doesn't work, do a
-    // search across all origins, similar to how the "distinct" search works now, but returning all
-    // the details instead of just names.
-    let decoded_query = match percent_encoding::percent_decode(query.as_bytes()).decode_utf8() {
-        // TODO There might be a case where there is a package with the same name as the origin.
-        // And the search with the query 'origin/' could end up finding the matches in 'origin' and
-        // the package names. Ideally, it should filter the matches to match the 'origin'
-        // only in this case.
-        Ok(q) => q.to_string().trim_end_matches('/').replace('/', " & "),
+    let decoded = match percent_encoding::percent_decode(query.as_bytes()).decode_utf8() {
+        Ok(q) => q.to_string(),
         Err(err) => {
             debug!("{}", err);
             let body =
@@ -730,14 +1782,19 @@ async fn search_packages(req: HttpRequest,
         }
     };
 
-    debug!("search_packages called with: {}", decoded_query);
-
-    let search_packages = SearchPackages { query:      decoded_query,
-                                           page:       page as i64,
-                                           limit:      per_page as i64,
-                                           account_id: opt_session_id, };
+    debug!("search_packages called with: {}", decoded);
 
     if pagination.distinct {
+        // TODO There might be a case where there is a package with the same name as the origin.
+        // And the search with the query 'origin/' could end up finding the matches in 'origin' and
+        // the package names. Ideally, it should filter the matches to match the 'origin'
+        // only in this case.
+        let search_packages =
+            SearchPackages { query:      decoded.trim_end_matches('/').replace('/', " & "),
+                             page:       page as i64,
+                             limit:      per_page as i64,
+                             account_id: opt_session_id, };
+
         return match Package::search_distinct(&search_packages, &mut conn) {
             Ok((packages, count)) => postprocess_package_list(&req, &packages, count, &pagination),
             Err(err) => {
@@ -747,11 +1804,91 @@ async fn search_packages(req: HttpRequest,
         };
     }
 
-    match Package::search(&search_packages, &mut conn) {
-        Ok((packages, count)) => postprocess_package_list(&req, &packages, count, &pagination),
+    // Bare words match the package idents and descriptions, `field:value` qualifiers narrow the
+    // results down, e.g. `redis origin:core exposes:6379`
+    let terms = match SearchTerms::from_str(&decoded) {
+        Ok(terms) => terms,
+        Err(msg) => {
+            debug!("{}", msg);
+            let body = Bytes::from(msg.into_bytes());
+            return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body));
+        }
+    };
+    if let Some(target) = terms.target.as_deref() {
+        if let Err(resp) = helpers::enabled_target(&state.config.api, target) {
+            return resp;
+        }
+    }
+
+    let search_req = SearchPackagesByTerms { terms:      &terms,
+                                             account_id: opt_session_id,
+                                             page:       page as i64,
+                                             limit:      per_page as i64, };
+
+    match search::search_packages(&mut conn, &search_req) {
+        Ok((packages, count, facets)) => {
+            postprocess_search_results(&packages, count, facets, &pagination)
+        }
+        Err(err) => {
+            debug!("{}", err);
+            err.into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn search_files(req: HttpRequest,
+                      qsearch: Query<FileSearchQuery>,
+                      pagination: Query<Pagination>,
+                      state: Data<AppState>)
+                      -> HttpResponse {
+    Counter::SearchFiles.increment();
+
+    let opt_session_id = match authorize_session(&req, None, None) {
+        Ok(session) => Some(session.get_id() as i64),
+        Err(_) => None,
+    };
+
+    let pattern = match search::path_pattern(&qsearch.path) {
+        Ok(pattern) => pattern,
+        Err(msg) => {
+            debug!("{}", msg);
+            let body = Bytes::from(msg.into_bytes());
+            return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body));
+        }
+    };
+
+    // Every target is searched unless one is asked for
+    let target = match qsearch.target.as_deref() {
+        Some(t) => {
+            match helpers::requested_target(&req, Some(t)) {
+                Ok(target) => Some(target.to_string()),
+                Err(resp) => return resp,
+            }
+        }
+        None => None,
+    };
+
+    let (page, per_page) = helpers::extract_pagination_in_pages(&pagination);
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let search_req = SearchFilesByPath { pattern:    &pattern,
+                                         target:     target.as_deref(),
+                                         origin:     qsearch.origin.as_deref(),
+                                         channel:    qsearch.channel.as_deref(),
+                                         account_id: opt_session_id,
+                                         page:       page as i64,
+                                         limit:      per_page as i64, };
+
+    match search::search_files(&mut conn, &search_req) {
+        Ok((matches, count)) => postprocess_package_list(&req, &matches, count, &pagination),
         Err(err) => {
             debug!("{}", err);
-            Error::DieselError(err).into()
+            err.into()
         }
     }
 }
@@ -809,6 +1946,122 @@ async fn package_privacy_toggle(req: HttpRequest,
     }
 }
 
+#[allow(clippy::needless_pass_by_value)]
+async fn update_package_status(req: HttpRequest,
+                               path: Path<(String, String, String, String)>,
+                               qtarget: Query<Target>,
+                               body: Json<PackageStatusReq>,
+                               state: Data<AppState>)
+                               -> HttpResponse {
+    let (origin, name, version, release) = path.into_inner();
+
+    let session = match authorize_session(&req, Some(&origin), Some(OriginMemberRole::Maintainer)) {
+        Ok(session) => session,
+        Err(err) => return err.into(),
+    };
+
+    let ident = PackageIdent::new(origin, name, Some(version), Some(release));
+
+    if !ident.valid() {
+        debug!("Invalid package identifier: {}", ident);
+        let body = Bytes::from(format!("Invalid package identifier '{}'", ident).into_bytes());
+        return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body));
+    }
+
+    let target = match helpers::requested_target(&req, qtarget.target.as_deref()) {
+        Ok(target) => target,
+        Err(resp) => return resp,
+    };
+
+    let status = match PackageStatus::from_str(&body.status) {
+        Ok(status) => status,
+        Err(_) => {
+            let body = Bytes::from(format!("Invalid package status '{}'", body.status).into_bytes());
+            return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY, BoxBody::new(body));
+        }
+    };
+
+    // Yanking or deprecating a package has to be explained, restoring it clears the reason
+    let reason = match status {
+        PackageStatus::Active => None,
+        _ => {
+            match body.reason.as_deref().map(str::trim) {
+                Some(reason) if !reason.is_empty() => Some(reason),
+                _ => return HttpResponse::new(StatusCode::BAD_REQUEST),
+            }
+        }
+    };
+
+    let operation = match status {
+        PackageStatus::Active => PackageChannelOperation::Restore,
+        PackageStatus::Deprecated => PackageChannelOperation::Deprecate,
+        PackageStatus::Yanked => PackageChannelOperation::Yank,
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let pkg = match Package::update_status(
+        UpdatePackageStatus {
+            ident: BuilderPackageIdent(ident.clone()),
+            target: BuilderPackageTarget(target),
+            status,
+            reason,
+        },
+        &mut conn,
+    ) {
+        Ok(pkg) => pkg,
+        Err(err) => {
+            debug!("{}", err);
+            return Error::DieselError(err).into();
+        }
+    };
+
+    state.memcache.borrow_mut().clear_cache_for_package(&ident);
+
+    let change = match reason {
+        Some(reason) => format!("{} ({}) {}: {}", pkg.ident, target, status, reason),
+        None => format!("{} ({}) {}", pkg.ident, target, status),
+    };
+    origin_audit(&pkg.origin,
+                 OriginOperation::PackageStatusChange,
+                 &change,
+                 session.get_id() as i64,
+                 session.get_name(),
+                 &mut conn);
+
+    // The package events are per channel, so record the change in each of them too
+    match Package::list_package_channels(&pkg.ident,
+                                         target,
+                                         PackageVisibility::all(),
+                                         &mut conn)
+    {
+        Ok(channels) => {
+            for channel in channels {
+                if let Err(err) = PackageChannelAudit::audit(
+                    &PackageChannelAudit {
+                        package_ident: pkg.ident.clone(),
+                        channel: &channel.name,
+                        operation,
+                        trigger: helpers::trigger_from_request_model(&req),
+                        requester_id: session.get_id() as i64,
+                        requester_name: session.get_name(),
+                        origin: &pkg.origin,
+                    },
+                    &mut conn,
+                ) {
+                    debug!("Failed to save status change to audit log: {}", err);
+                }
+            }
+        }
+        Err(err) => debug!("Failed to list channels for audit log: {}", err),
+    }
+
+    HttpResponse::Ok().json(pkg)
+}
+
 // Public helpers
 //
 
@@ -840,6 +2093,34 @@ pub fn postprocess_package_list<T: Serialize>(_req: &HttpRequest,
             .body(body)
 }
 
+// Same paging as postprocess_package_list, with the facet counts of the whole result set
+fn postprocess_search_results(packages: &[BuilderPackageIdent],
+                              count: i64,
+                              facets: SearchFacets,
+                              pagination: &Query<Pagination>)
+                              -> HttpResponse {
+    let (start, _) = helpers::extract_pagination(pagination);
+    let stop = match packages.len() as isize {
+        0 => count as isize,
+        pkg_count => start + pkg_count - 1,
+    };
+
+    let results = SearchResults { range_start: start,
+                                  range_end: stop,
+                                  total_count: count as isize,
+                                  data: packages,
+                                  facets };
+
+    let mut response = if count as isize > (stop + 1) {
+        HttpResponse::PartialContent()
+    } else {
+        HttpResponse::Ok()
+    };
+
+    response.append_header((http::header::CACHE_CONTROL, headers::NO_CACHE))
+            .json(results)
+}
+
 pub fn postprocess_extended_package_list(_req: &HttpRequest,
                                          packages: &[PackageIdentWithChannelPlatform],
                                          count: i64,
@@ -875,8 +2156,222 @@ pub fn postprocess_extended_package_list(_req: &HttpRequest,
 
 // Internal - these functions should return Result<..>
 //
+
+// Download counts are best effort, failing to record one never fails the download
+fn record_download(package_id: i64, conn: &mut PgConnection) {
+    if let Err(err) = Downloads::record(package_id, conn) {
+        warn!("Unable to record download of package {}, err = {}",
+              package_id, err);
+    }
+}
+
+// Returns the fully qualified package if the requester may see it
+fn get_visible_package(req: &HttpRequest,
+                       ident: &PackageIdent,
+                       target: PackageTarget,
+                       conn: &mut PgConnection)
+                       -> Result<Package> {
+    let opt_session_id = match authorize_session(req, None, None) {
+        Ok(session) => Some(session.get_id()),
+        Err(_) => None,
+    };
+
+    let visibility = helpers::visibility_for_optional_session(req, opt_session_id, &ident.origin);
+
+    Package::get(GetPackage { ident:      BuilderPackageIdent(ident.clone()),
+                              visibility,
+                              target:     BuilderPackageTarget(target), },
+                 conn).map_err(Error::DieselError)
+}
+
+// Lists the files shipped in a hart, relative to the package install directory, and reads
+// the exports and binds it declares. They are only captured here, at upload, so packages
+// uploaded before that have none.
+fn archive_contents(hart: PathBuf) -> Result<ArchiveContents> {
+    let mut archive = PackageArchive::new(hart)?;
+    let prefix = format!("hab/pkgs/{}/", archive.ident()?);
+    let reader = artifact::get_archive_reader(&archive.path)?;
+
+    let mut tarball = tar::Archive::new(xz2::read::XzDecoder::new(reader));
+    let mut contents = ArchiveContents::default();
+    for entry in tarball.entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type().is_dir() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let file = match path.trim_start_matches('/').strip_prefix(&prefix) {
+            Some(file) => file.to_string(),
+            None => continue,
+        };
+
+        match file.as_str() {
+            binds::EXPORTS_METAFILE => {
+                let mut metafile = String::new();
+                entry.read_to_string(&mut metafile)?;
+                contents.exports = binds::parse_exports(&metafile);
+            }
+            binds::BINDS_METAFILE | binds::BINDS_OPTIONAL_METAFILE => {
+                let mut metafile = String::new();
+                entry.read_to_string(&mut metafile)?;
+                let optional = file == binds::BINDS_OPTIONAL_METAFILE;
+                contents.binds.extend(binds::parse_binds(&metafile, optional));
+            }
+            _ => (),
+        }
+        contents.files.push(file);
+    }
+    contents.files.sort();
+    binds::dedup_binds(&mut contents.binds);
+    Ok(contents)
+}
+
+fn do_get_package_diff(from: &Package,
+                       to: &Package,
+                       conn: &mut PgConnection)
+                       -> Result<PackageDiff> {
+    let idents = |idents: &[BuilderPackageIdent]| {
+        idents.iter().map(|i| i.to_string()).collect::<Vec<String>>()
+    };
+
+    // The files, exports and binds are captured together at upload. Releases uploaded before
+    // that have none, which doesn't mean they were all removed or added.
+    let contents = match (PackageFiles::get(from.id, conn), PackageFiles::get(to.id, conn)) {
+        (Ok(from_files), Ok(to_files)) => Some((from_files, to_files)),
+        (Err(NotFound), _) | (_, Err(NotFound)) => None,
+        (Err(err), _) | (_, Err(err)) => return Err(Error::DieselError(err)),
+    };
+    let (exports, binds, files) = match contents {
+        Some((from_files, to_files)) => {
+            let (from_exports, from_binds) = release_binds(from.id, conn)?;
+            let (to_exports, to_binds) = release_binds(to.id, conn)?;
+            (Some(package_diff::diff_list(&from_exports, &to_exports)),
+             Some(package_diff::diff_list(&from_binds, &to_binds)),
+             Some(package_diff::diff_list(&from_files.files, &to_files.files)))
+        }
+        None => (None, None, None),
+    };
+
+    let from_metadata = PackageMetadata::from_manifest(from.id, &from.manifest);
+    let to_metadata = PackageMetadata::from_manifest(to.id, &to.manifest);
+
+    Ok(PackageDiff { from: from.ident.to_string(),
+                     to: to.ident.to_string(),
+                     target: to.target.to_string(),
+                     deps: package_diff::diff_deps(&idents(&from.deps), &idents(&to.deps)),
+                     tdeps: package_diff::diff_deps(&idents(&from.tdeps), &idents(&to.tdeps)),
+                     exposes: package_diff::diff_list(&from.exposes, &to.exposes),
+                     exports,
+                     binds,
+                     metadata: package_diff::diff_metadata(&from_metadata, &to_metadata),
+                     config: package_diff::diff_config(&from.config, &to.config),
+                     files })
+}
+
+// The exports and binds of a release in the form of their metafile lines, so a changed
+// export or bind shows up as removed and added
+fn release_binds(package_id: i64, conn: &mut PgConnection) -> Result<(Vec<String>, Vec<String>)> {
+    let exports = PackageBinds::exports(package_id, conn)?;
+    let binds = PackageBinds::binds(package_id, conn)?;
+
+    let exports = exports.iter()
+                         .map(|e| format!("{}={}", e.name, e.config_key))
+                         .collect();
+    let binds = binds.iter()
+                     .map(|b| {
+                         let optional = if b.optional { " (optional)" } else { "" };
+                         format!("{}={}{}", b.name, b.exports.join(" "), optional)
+                     })
+                     .collect();
+    Ok((exports, binds))
+}
+
+// Removes the archive of a deleted package from the package store. The package is already
+// gone at this point, so a failure only leaves an orphan for the storage check to find.
+async fn delete_archive(state: &AppState, ident: &PackageIdent, target: PackageTarget) {
+    if feat::is_enabled(feat::Artifactory) {
+        return;
+    }
+    if let Err(err) = state.packages.delete(ident, target).await {
+        warn!("Unable to delete archive for {} ({}), err = {}",
+              ident, target, err);
+    }
+}
+
+// Everything that depends on the release at runtime has to go with it. Dependents are
+// only deleted from the same origin, and never from the stable channel.
+fn do_get_delete_plan(ident: &PackageIdent,
+                      target: PackageTarget,
+                      account_id: i64,
+                      conn: &mut PgConnection)
+                      -> Result<DeletePlan> {
+    // Make sure the release exists before planning anything around it
+    Package::get(GetPackage { ident:      BuilderPackageIdent(ident.clone()),
+                              visibility: PackageVisibility::all(),
+                              target:     BuilderPackageTarget(target), },
+                 conn)?;
+
+    let mut blockers = Vec::new();
+
+    let channels = Package::list_package_channels(&BuilderPackageIdent(ident.clone()),
+                                                  target,
+                                                  PackageVisibility::all(),
+                                                  conn)?;
+    if channels.iter()
+               .any(|c| c.name == ChannelIdent::stable().to_string())
+    {
+        blockers.push(DeletePlanBlocker { ident:  ident.to_string(),
+                                          reason: "Package is in the stable channel".to_string(), });
+    }
+
+    // The tdeps of a package are transitive, so every package that depends on the release
+    // at runtime, directly or not, is a dependent of it. Dependents in hidden origins are
+    // never visible, so they count as restricted and block the plan.
+    let rdeps = reverse_dependencies::get_release_rdeps(conn,
+                                                        &ident.to_string(),
+                                                        &target.to_string(),
+                                                        Some(account_id),
+                                                        true)?;
+    let (dependents, restricted): (Vec<_>, Vec<_>) = rdeps.into_iter()
+                                                          .filter(ReleaseDependent::is_runtime)
+                                                          .partition(|d| d.visible);
+
+    for dependent in dependents.iter() {
+        if dependent.origin != ident.origin {
+            blockers.push(DeletePlanBlocker { ident:  dependent.ident.clone(),
+                                              reason: "Dependent is in another origin".to_string(), });
+        } else if dependent.in_stable {
+            blockers.push(DeletePlanBlocker { ident:  dependent.ident.clone(),
+                                              reason: "Dependent is in the stable channel"
+                                                      .to_string(), });
+        }
+    }
+
+    // A dependent has every other dependent it depends on in its tdeps, so ordering by
+    // that count deletes each package before anything it depends on
+    let in_plan: HashSet<&str> = dependents.iter().map(|d| d.ident.as_str()).collect();
+    let mut ordered: Vec<(usize, &str)> =
+        dependents.iter()
+                  .map(|d| {
+                      (d.tdeps.iter().filter(|t| in_plan.contains(t.as_str())).count(),
+                       d.ident.as_str())
+                  })
+                  .collect();
+    ordered.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+
+    let mut packages: Vec<String> = ordered.into_iter().map(|(_, i)| i.to_string()).collect();
+    packages.push(ident.to_string());
+
+    Ok(DeletePlan { ident: ident.to_string(),
+                    target: target.to_string(),
+                    packages,
+                    blockers,
+                    restricted_count: restricted.len() })
+}
+
 fn do_get_packages(req: &HttpRequest,
                    ident: &PackageIdent,
+                   labels: Vec<(String, String)>,
                    pagination: &Query<Pagination>)
                    -> Result<(Vec<PackageIdentWithChannelPlatform>, i64)> {
     let opt_session_id = match authorize_session(req, None, None) {
@@ -894,7 +2389,8 @@ fn do_get_packages(req: &HttpRequest,
                                                                                   opt_session_id,
                                                                                   &ident.origin),
                              page:       page as i64,
-                             limit:      limit as i64, };
+                             limit:      limit as i64,
+                             labels, };
 
     if pagination.distinct {
         match Package::list_distinct(&lpr, &mut conn).map_err(Error::DieselError) {
@@ -909,8 +2405,22 @@ fn do_get_packages(req: &HttpRequest,
 
     match Package::list(&lpr, &mut conn).map_err(Error::DieselError) {
         Ok((packages, count)) => {
+            let ids = packages.iter().map(|p| p.id).collect::<Vec<i64>>();
+            let mut metadata = PackageMetadata::for_packages(&ids, &mut conn)?;
             let ident_pkgs: Vec<PackageIdentWithChannelPlatform> =
-                packages.into_iter().map(|p| p.into()).collect();
+                packages.into_iter()
+                        .map(|p| {
+                            // Like a single release, one uploaded before the metadata was
+                            // stored still has it in its manifest
+                            let release_metadata =
+                                metadata.remove(&p.id).unwrap_or_else(|| {
+                                    PackageMetadata::from_manifest(p.id, &p.manifest)
+                                });
+                            let mut ident_pkg = PackageIdentWithChannelPlatform::from(p);
+                            ident_pkg.metadata = Some(release_metadata);
+                            ident_pkg
+                        })
+                        .collect();
 
             trace!(target: "biome_builder_api::server::resources::pkgs::versions", "do_get_packages for {}, got {} packages, idents: {:?}", ident, count, ident_pkgs);
 
@@ -924,7 +2434,8 @@ fn do_get_packages(req: &HttpRequest,
 //
 fn do_upload_package_start(req: &HttpRequest,
                            qupload: &Query<Upload>,
-                           ident: &PackageIdent)
+                           ident: &PackageIdent,
+                           target: PackageTarget)
                            -> Result<(PathBuf, BufWriter<File>)> {
     authorize_session(req, Some(&ident.origin), Some(OriginMemberRole::Member))?;
 
@@ -934,19 +2445,11 @@ fn do_upload_package_start(req: &HttpRequest,
         debug!("Upload was forced (bypassing existing package check) for: {}",
                ident);
     } else {
-        let target = match qupload.target {
-            Some(ref t) => {
-                trace!("Query requested target = {}", t);
-                PackageTarget::from_str(t)?
-            }
-            None => helpers::target_from_headers(req),
-        };
-
         match Package::get(
             GetPackage {
                 ident: BuilderPackageIdent(ident.clone()),
                 visibility: PackageVisibility::all(),
-                target: BuilderPackageTarget(PackageTarget::from_str(&target).unwrap()), // Unwrap OK
+                target: BuilderPackageTarget(target),
             },
             &mut conn,
         ) {
@@ -1197,9 +2700,49 @@ async fn do_upload_package_finish(req: &HttpRequest,
         },
     };
 
+    // The file listing and binds are informational, a hart they can't be read from is
+    // still accepted
+    let hart = filename.clone();
+    let contents = match web::block(move || archive_contents(hart)).await {
+        Ok(Ok(contents)) => Some(contents),
+        Ok(Err(err)) => {
+            warn!("Unable to read contents of {}, err = {}", *package.ident, err);
+            None
+        }
+        Err(err) => {
+            warn!("Unable to read contents of {}, err = {}", *package.ident, err);
+            None
+        }
+    };
+
+    let metadata = match PackageMetadata::from_archive(&mut archive) {
+        Ok(metadata) => Some(metadata),
+        Err(err) => {
+            warn!("Unable to read metadata of {}, err = {}", *package.ident, err);
+            None
+        }
+    };
+
     // Re-create origin package as needed (eg, checksum update)
     match Package::create(&package, &mut conn) {
-        Ok(_) => {}
+        Ok(pkg) => {
+            if let Some(contents) = contents {
+                if let Err(err) = PackageFiles::set(pkg.id, &contents.files, &mut conn) {
+                    warn!("Unable to save files of {}, err = {}", *package.ident, err);
+                }
+                if let Err(err) =
+                    PackageBinds::set(pkg.id, &contents.exports, &contents.binds, &mut conn)
+                {
+                    warn!("Unable to save binds of {}, err = {}", *package.ident, err);
+                }
+            }
+            if let Some(mut metadata) = metadata {
+                metadata.package_id = pkg.id;
+                if let Err(err) = metadata.set(&mut conn) {
+                    warn!("Unable to save metadata of {}, err = {}", *package.ident, err);
+                }
+            }
+        }
         Err(NotFound) => {
             debug!("Package::create returned NotFound (DB conflict handled)");
         }
@@ -1246,10 +2789,10 @@ async fn do_upload_package_async(req: HttpRequest,
     }
 }
 
-async fn do_get_package(req: &HttpRequest,
-                        qtarget: &Query<Target>,
-                        ident: &PackageIdent)
-                        -> Result<String> {
+async fn do_get_package_for_target(req: &HttpRequest,
+                                   ident: &PackageIdent,
+                                   target: PackageTarget)
+                                   -> Result<String> {
     let opt_session_id = match authorize_session(req, None, None) {
         Ok(session) => Some(session.get_id()),
         Err(_) => None,
@@ -1258,14 +2801,6 @@ async fn do_get_package(req: &HttpRequest,
 
     let mut conn = req_state(req).db.get_conn().map_err(Error::DbError)?;
 
-    let target = match qtarget.target {
-        Some(ref t) => {
-            trace!("Query requested target = {}", t);
-            PackageTarget::from_str(t)?
-        }
-        None => helpers::target_from_headers(req),
-    };
-
     // Scope this memcache usage so the reference goes out of
     // scope before the visibility_for_optional_session call
     // below
@@ -1368,6 +2903,19 @@ async fn do_get_package(req: &HttpRequest,
     pkg_json["manifest"] = json!("");
     pkg_json["channels"] = json!(channels);
     pkg_json["is_a_service"] = json!(pkg.is_a_service());
+    let labels = PackageLabels::effective(&pkg.origin, &pkg.name, pkg.id, &mut conn)?;
+    pkg_json["labels"] = json!(labels);
+
+    // Releases uploaded before the metadata was stored still have it in their manifest
+    let metadata = match PackageMetadata::get(pkg.id, &mut conn) {
+        Ok(metadata) => metadata,
+        Err(NotFound) => PackageMetadata::from_manifest(pkg.id, &pkg.manifest),
+        Err(err) => return Err(err.into()),
+    };
+    pkg_json["description"] = json!(metadata.description);
+    pkg_json["upstream_url"] = json!(metadata.upstream_url);
+    pkg_json["licenses"] = json!(metadata.licenses);
+    pkg_json["maintainer"] = json!(metadata.maintainer);
     let size = match req_state(req).packages
                                    .size_of(&pkg.ident, *pkg.target)
                                    .await
@@ -1449,3 +2997,53 @@ fn download_response_for_archive(archive: &PackageArchive,
         .append_header((http::header::CACHE_CONTROL, cache_hdr))
         .streaming(rx_body.map(|s| Ok::<_, Infallible>(s)))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bio_core::package::target;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../test/builder-api/fixtures")
+                                                 .join(name)
+    }
+
+    #[test]
+    fn lists_archive_files() {
+        let contents =
+            archive_contents(fixture("neurosis-neurosis-2.0-20190618173321-x86_64-linux.hart"))
+                .unwrap();
+
+        assert_eq!(contents.files, vec!["FILES", "IDENT", "MANIFEST", "TARGET"]);
+        assert!(contents.exports.is_empty());
+        assert!(contents.binds.is_empty());
+    }
+
+    #[test]
+    fn rejects_unreadable_archive() {
+        assert!(archive_contents(fixture("missing.hart")).is_err());
+    }
+
+    #[test]
+    fn latest_for_targets_lists_missing_targets() {
+        let mut latest = LatestForTargets::default();
+        assert!(latest.is_empty());
+
+        latest.add(target::X86_64_LINUX, Ok(r#"{"ident":{"origin":"core"}}"#.to_string()))
+              .unwrap();
+        latest.add(target::AARCH64_LINUX, Err(Error::NotFound)).unwrap();
+
+        assert!(!latest.is_empty());
+        assert_eq!(latest.packages["x86_64-linux"]["ident"]["origin"], "core");
+        assert_eq!(latest.missing, vec!["aarch64-linux".to_string()]);
+    }
+
+    #[test]
+    fn latest_for_targets_fails_on_other_errors() {
+        let mut latest = LatestForTargets::default();
+
+        assert!(latest.add(target::X86_64_LINUX, Err(Error::Authorization)).is_err());
+        assert!(latest.add(target::X86_64_LINUX, Ok("not json".to_string())).is_err());
+        assert!(latest.is_empty());
+    }
+}
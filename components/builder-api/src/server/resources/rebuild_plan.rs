@@ -0,0 +1,268 @@
+// Biome project based on Chef Habitat's code (c) 2016-2020 Chef Software, Inc
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{cmp,
+          collections::{BTreeMap,
+                        BTreeSet,
+                        HashMap}};
+
+use diesel::{debug_query,
+             pg::Pg,
+             sql_query,
+             sql_types::{Array,
+                         BigInt,
+                         Nullable,
+                         Text},
+             PgConnection,
+             QueryableByName,
+             RunQueryDsl};
+
+use crate::server::error::{Error,
+                           Result};
+
+#[derive(Clone, Debug, QueryableByName)]
+struct DependentRow {
+    #[sql_type = "Text"]
+    short_id: String, // "origin/name"
+    #[sql_type = "Array<Text>"]
+    deps:     Vec<String>,
+}
+
+pub(crate) struct ListRebuildDependents<'a> {
+    pub origin:     &'a str,
+    pub name:       &'a str,
+    pub version:    Option<&'a str>,
+    pub release:    Option<&'a str>,
+    pub origins:    &'a [String],
+    pub channel:    &'a str,
+    pub target:     &'a str,
+    pub account_id: Option<i64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub(crate) struct RebuildPlan {
+    // Each layer only depends on earlier layers, so its packages can be rebuilt in parallel
+    pub layers: Vec<Vec<String>>,
+    // Packages that depend on each other. Each cycle is placed in a single layer and has
+    // to be untangled by hand.
+    pub cycles: Vec<Vec<String>>,
+}
+
+/// Loads the packages in `origins` and `channel` that depend at runtime or build time on
+/// the requested package, returning each one's dependencies on the others as a graph of
+/// `origin/name` short ids.
+pub(crate) fn get_rebuild_graph(conn: &mut PgConnection,
+                                req: &ListRebuildDependents)
+                                -> Result<BTreeMap<String, BTreeSet<String>>> {
+    let sql_stmt = r###"
+        select distinct op.origin||'/'||op.name as short_id, op.tdeps || op.build_tdeps as deps
+          from origin_packages as op
+          join origin_channel_packages as ocp on ocp.package_id = op.id
+          join origin_channels as oc on oc.id = ocp.channel_id
+         where op.origin = any($1) and op.target = $2 and oc.name = $3
+           and op.hidden = false
+           and (op.visibility = 'public'
+                or op.origin in (select origin from origin_members where account_id = $4))
+           and not (op.origin = $5 and op.name = $6)
+           and exists (select 1 from unnest(op.tdeps || op.build_tdeps) as d(ident)
+                        where split_part(d.ident, '/', 1) = $5
+                          and split_part(d.ident, '/', 2) = $6
+                          and ($7::text is null or split_part(d.ident, '/', 3) = $7)
+                          and ($8::text is null or split_part(d.ident, '/', 4) = $8))"###;
+
+    let query = sql_query(sql_stmt).bind::<Array<Text>, _>(req.origins)
+                                   .bind::<Text, _>(req.target)
+                                   .bind::<Text, _>(req.channel)
+                                   .bind::<Nullable<BigInt>, _>(req.account_id)
+                                   .bind::<Text, _>(req.origin)
+                                   .bind::<Text, _>(req.name)
+                                   .bind::<Nullable<Text>, _>(req.version)
+                                   .bind::<Nullable<Text>, _>(req.release);
+
+    debug!("debug_query {}", debug_query::<Pg, _>(&query));
+
+    let rows = query.load::<DependentRow>(conn)
+                    .map_err(Error::DieselError)?;
+
+    // A channel may hold several releases of a dependent, their edges are merged
+    let mut graph: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for row in rows.iter() {
+        graph.entry(row.short_id.clone()).or_default();
+    }
+    for row in rows {
+        let deps: BTreeSet<String> =
+            row.deps
+               .iter()
+               .map(|d| d.splitn(3, '/').take(2).collect::<Vec<_>>().join("/"))
+               .filter(|d| *d != row.short_id && graph.contains_key(d))
+               .collect();
+        if let Some(edges) = graph.get_mut(&row.short_id) {
+            edges.extend(deps);
+        }
+    }
+    Ok(graph)
+}
+
+/// Groups the packages of `graph`, which maps each package to the packages it depends
+/// on, into layers in rebuild order. Packages that form a cycle are reported and kept
+/// together in one layer.
+pub(crate) fn plan(graph: &BTreeMap<String, BTreeSet<String>>) -> RebuildPlan {
+    let components = strongly_connected(graph);
+
+    let mut component_of: HashMap<&str, usize> = HashMap::new();
+    for (i, component) in components.iter().enumerate() {
+        for node in component.iter() {
+            component_of.insert(node, i);
+        }
+    }
+
+    // Components come out of Tarjan's algorithm after everything they depend on, so the
+    // layer of each dependency is known by the time it is needed.
+    let mut layer_of: Vec<usize> = Vec::with_capacity(components.len());
+    let mut plan = RebuildPlan::default();
+    for (i, component) in components.iter().enumerate() {
+        let mut layer = 0;
+        for node in component.iter() {
+            for dep in graph[node].iter() {
+                let dep_component = component_of[dep.as_str()];
+                if dep_component != i {
+                    layer = cmp::max(layer, layer_of[dep_component] + 1);
+                }
+            }
+        }
+        layer_of.push(layer);
+
+        if plan.layers.len() <= layer {
+            plan.layers.resize(layer + 1, Vec::new());
+        }
+        plan.layers[layer].extend(component.iter().cloned());
+
+        if component.len() > 1 {
+            let mut cycle = component.clone();
+            cycle.sort();
+            plan.cycles.push(cycle);
+        }
+    }
+
+    for layer in plan.layers.iter_mut() {
+        layer.sort();
+    }
+    plan.cycles.sort();
+    plan
+}
+
+// Tarjan's strongly connected components algorithm
+fn strongly_connected(graph: &BTreeMap<String, BTreeSet<String>>) -> Vec<Vec<String>> {
+    struct State<'a> {
+        graph:      &'a BTreeMap<String, BTreeSet<String>>,
+        index:      usize,
+        indices:    HashMap<&'a str, usize>,
+        lowlinks:   HashMap<&'a str, usize>,
+        stack:      Vec<&'a str>,
+        on_stack:   BTreeSet<&'a str>,
+        components: Vec<Vec<String>>,
+    }
+
+    fn visit<'a>(state: &mut State<'a>, node: &'a str) {
+        state.indices.insert(node, state.index);
+        state.lowlinks.insert(node, state.index);
+        state.index += 1;
+        state.stack.push(node);
+        state.on_stack.insert(node);
+
+        for dep in state.graph[node].iter() {
+            let dep = dep.as_str();
+            if !state.indices.contains_key(dep) {
+                visit(state, dep);
+                let lowlink = cmp::min(state.lowlinks[node], state.lowlinks[dep]);
+                state.lowlinks.insert(node, lowlink);
+            } else if state.on_stack.contains(dep) {
+                let lowlink = cmp::min(state.lowlinks[node], state.indices[dep]);
+                state.lowlinks.insert(node, lowlink);
+            }
+        }
+
+        if state.lowlinks[node] == state.indices[node] {
+            let mut component = Vec::new();
+            while let Some(member) = state.stack.pop() {
+                state.on_stack.remove(member);
+                component.push(member.to_string());
+                if member == node {
+                    break;
+                }
+            }
+            state.components.push(component);
+        }
+    }
+
+    let mut state = State { graph,
+                            index: 0,
+                            indices: HashMap::new(),
+                            lowlinks: HashMap::new(),
+                            stack: Vec::new(),
+                            on_stack: BTreeSet::new(),
+                            components: Vec::new() };
+
+    for node in graph.keys() {
+        if !state.indices.contains_key(node.as_str()) {
+            visit(&mut state, node);
+        }
+    }
+    state.components
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn graph(edges: &[(&str, &[&str])]) -> BTreeMap<String, BTreeSet<String>> {
+        edges.iter()
+             .map(|(node, deps)| {
+                 (node.to_string(), deps.iter().map(|d| d.to_string()).collect())
+             })
+             .collect()
+    }
+
+    #[test]
+    fn plan_layers_in_dependency_order() {
+        let g = graph(&[("core/app", &["core/lib", "core/tool"]),
+                        ("core/lib", &["core/tool"]),
+                        ("core/tool", &[]),
+                        ("core/other", &[])]);
+
+        let plan = plan(&g);
+
+        assert_eq!(plan.layers,
+                   vec![vec!["core/other".to_string(), "core/tool".to_string()],
+                        vec!["core/lib".to_string()],
+                        vec!["core/app".to_string()]]);
+        assert!(plan.cycles.is_empty());
+    }
+
+    #[test]
+    fn plan_reports_cycles() {
+        let g = graph(&[("core/a", &["core/b"]),
+                        ("core/b", &["core/a"]),
+                        ("core/c", &["core/a"]),
+                        ("core/d", &[])]);
+
+        let plan = plan(&g);
+
+        assert_eq!(plan.cycles,
+                   vec![vec!["core/a".to_string(), "core/b".to_string()]]);
+        assert_eq!(plan.layers,
+                   vec![vec!["core/a".to_string(), "core/b".to_string(), "core/d".to_string()],
+                        vec!["core/c".to_string()]]);
+    }
+}
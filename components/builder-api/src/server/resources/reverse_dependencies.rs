@@ -2,7 +2,11 @@ use diesel::{debug_query,
              pg::Pg,
              r2d2::ConnectionManager,
              sql_query,
-             sql_types::Text,
+             sql_types::{Array,
+                         BigInt,
+                         Bool,
+                         Nullable,
+                         Text},
              PgConnection,
              QueryableByName,
              RunQueryDsl};
@@ -24,6 +28,66 @@ pub(crate) struct ReverseDependencies {
     pub rdeps:  Vec<String>,
 }
 
+// A package that depends on some release of the requested package, either at
+// runtime or at build time.
+#[derive(Clone, Debug, QueryableByName, Serialize, Deserialize)]
+pub(crate) struct VersionedDependent {
+    #[sql_type = "Text"]
+    pub ident:       String,
+    #[sql_type = "Text"]
+    pub target:      String,
+    // The fully qualified release of the requested package this dependent was built against
+    #[sql_type = "Text"]
+    pub pinned:      String,
+    // Any of "deps", "tdeps" (transitive only) and "build_deps"
+    #[sql_type = "Array<Text>"]
+    pub edges:       Vec<String>,
+    #[serde(skip)]
+    #[sql_type = "BigInt"]
+    pub total_count: i64,
+}
+
+// A package that references one exact release of another package
+#[derive(Clone, Debug, QueryableByName, Serialize)]
+pub(crate) struct ReleaseDependent {
+    #[sql_type = "Text"]
+    pub ident:     String,
+    #[sql_type = "Text"]
+    pub target:    String,
+    #[serde(skip)]
+    #[sql_type = "Text"]
+    pub origin:    String,
+    // Any of "deps", "tdeps" and "build_deps", "build_tdeps" (transitive only)
+    #[sql_type = "Array<Text>"]
+    pub edges:     Vec<String>,
+    #[serde(skip)]
+    #[sql_type = "Array<Text>"]
+    pub tdeps:     Vec<String>,
+    #[sql_type = "Bool"]
+    pub in_stable: bool,
+    // Whether the requester may see the dependent
+    #[serde(skip)]
+    #[sql_type = "Bool"]
+    pub visible:   bool,
+}
+
+impl ReleaseDependent {
+    // Runtime dependents break when the release goes away, build time ones don't
+    pub fn is_runtime(&self) -> bool { self.edges.iter().any(|e| e == "deps" || e == "tdeps") }
+}
+
+pub(crate) struct ListVersionedRdeps<'a> {
+    pub origin:     &'a str,
+    pub name:       &'a str,
+    pub target:     &'a str,
+    pub version:    Option<&'a str>,
+    pub release:    Option<&'a str>,
+    pub channel:    Option<&'a str>,
+    pub account_id: Option<i64>,
+    pub page:       i64,
+    pub limit:      i64,
+}
+
 #[allow(clippy::needless_pass_by_value)]
 pub(crate) async fn get_rdeps(conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
                               origin: &str,
@@ -34,11 +98,6 @@ pub(crate) async fn get_rdeps(conn: &mut PooledConnection<ConnectionManager<PgCo
         select * from (
             select distinct op3.origin||'/'||op3.name as short_id
               from origin_packages as op1,
-           lateral (select op2.id, op2.origin, op2.name from origin_packages as op2 where op2.tdeps @> (ARRAY[op1.ident])) as op3
-             where op1.origin = $1 and op1.name = $2 and op1.target = $3
-                union distinct
-            select distinct op3.origin||'/'||op3.name as short_id
-              from origin_packages as op1,
            lateral (select op2.id, op2.origin, op2.name from origin_packages as op2 where op2.tdeps @> (ARRAY[op1.ident])) as op3
              where op1.origin = $1 and op1.name = $2 and op1.target = $3
         ) as ordered_rdeps order by short_id"###;
@@ -58,3 +117,101 @@ pub(crate) async fn get_rdeps(conn: &mut PooledConnection<ConnectionManager<PgCo
     debug!("reverse_dependencies: {:?} ", reverse_dependencies);
     Ok(reverse_dependencies)
 }
+
+pub(crate) fn get_versioned_rdeps(conn: &mut PgConnection,
+                                  req: &ListVersionedRdeps)
+                                  -> Result<(Vec<VersionedDependent>, i64)> {
+    // Dependents and the requested package are only visible to non members of their
+    // origin when public. The channel filter applies to the dependents.
+    let sql_stmt = r###"
+        select op2.ident, op2.target, op1.ident as pinned,
+               array_remove(array[
+                   case when op2.deps @> array[op1.ident] then 'deps' end,
+                   case when op2.tdeps @> array[op1.ident]
+                         and not op2.deps @> array[op1.ident] then 'tdeps' end,
+                   case when op2.build_deps @> array[op1.ident] then 'build_deps' end
+               ], null) as edges,
+               count(*) over () as total_count
+          from origin_packages as op1
+          join origin_packages as op2
+            on op2.target = op1.target
+           and (op2.tdeps @> array[op1.ident] or op2.build_deps @> array[op1.ident])
+         where op1.origin = $1 and op1.name = $2 and op1.target = $3
+           and ($4::text is null or op1.ident_array[3] = $4)
+           and ($5::text is null or op1.ident_array[4] = $5)
+           and op1.hidden = false and op2.hidden = false
+           and ($6::text is null or exists (
+                   select 1 from origin_channel_packages as ocp
+                     join origin_channels as oc on oc.id = ocp.channel_id
+                    where ocp.package_id = op2.id and oc.origin = op2.origin
+                      and oc.name = $6))
+           and (op1.visibility = 'public'
+                or op1.origin in (select origin from origin_members where account_id = $7))
+           and (op2.visibility = 'public'
+                or op2.origin in (select origin from origin_members where account_id = $7))
+         order by op2.ident, op2.target
+         limit $8 offset $9"###;
+
+    let query = sql_query(sql_stmt).bind::<Text, _>(req.origin)
+                                   .bind::<Text, _>(req.name)
+                                   .bind::<Text, _>(req.target)
+                                   .bind::<Nullable<Text>, _>(req.version)
+                                   .bind::<Nullable<Text>, _>(req.release)
+                                   .bind::<Nullable<Text>, _>(req.channel)
+                                   .bind::<Nullable<BigInt>, _>(req.account_id)
+                                   .bind::<BigInt, _>(req.limit)
+                                   .bind::<BigInt, _>((req.page - 1) * req.limit);
+
+    debug!("debug_query {}", debug_query::<Pg, _>(&query));
+
+    let rdeps = query.load::<VersionedDependent>(conn)
+                     .map_err(Error::DieselError)?;
+    let total_count = rdeps.first().map_or(0, |d| d.total_count);
+    Ok((rdeps, total_count))
+}
+
+/// Lists every package of the same target that references the fully qualified `ident`,
+/// whatever its visibility. `visible` is set on the dependents `account_id` may see.
+/// Packages of hidden origins are only listed with `include_hidden`, which checks that
+/// decide whether a release can go away need, and are never `visible`.
+pub(crate) fn get_release_rdeps(conn: &mut PgConnection,
+                                ident: &str,
+                                target: &str,
+                                account_id: Option<i64>,
+                                include_hidden: bool)
+                                -> Result<Vec<ReleaseDependent>> {
+    let sql_stmt = r###"
+        select op.ident, op.target, op.origin, op.tdeps,
+               array_remove(array[
+                   case when op.deps @> array[$1] then 'deps' end,
+                   case when op.tdeps @> array[$1]
+                         and not op.deps @> array[$1] then 'tdeps' end,
+                   case when op.build_deps @> array[$1] then 'build_deps' end,
+                   case when op.build_tdeps @> array[$1]
+                         and not op.build_deps @> array[$1] then 'build_tdeps' end
+               ], null) as edges,
+               exists (select 1 from origin_channel_packages as ocp
+                         join origin_channels as oc on oc.id = ocp.channel_id
+                        where ocp.package_id = op.id and oc.origin = op.origin
+                          and oc.name = 'stable') as in_stable,
+               (op.hidden = false
+                and (op.visibility = 'public'
+                     or op.origin in (select origin from origin_members
+                                       where account_id = $3))) as visible
+          from origin_packages as op
+         where op.target = $2 and ($4 or op.hidden = false)
+           and (op.tdeps @> array[$1]
+                or op.build_deps @> array[$1]
+                or op.build_tdeps @> array[$1])
+         order by op.ident"###;
+
+    let query = sql_query(sql_stmt).bind::<Text, _>(ident)
+                                   .bind::<Text, _>(target)
+                                   .bind::<Nullable<BigInt>, _>(account_id)
+                                   .bind::<Bool, _>(include_hidden);
+
+    debug!("debug_query {}", debug_query::<Pg, _>(&query));
+
+    query.load::<ReleaseDependent>(conn)
+         .map_err(Error::DieselError)
+}
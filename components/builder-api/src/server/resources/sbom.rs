@@ -0,0 +1,439 @@
+// Biome project based on Chef Habitat's code (c) 2016-2020 Chef Software, Inc
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::{BTreeMap,
+                        HashMap,
+                        HashSet},
+          str::FromStr};
+
+use actix_web::HttpRequest;
+use diesel::PgConnection;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{bio_core::package::PackageTarget,
+            db::models::{package::{BuilderPackageIdent,
+                                   Package},
+                         package_metadata::PackageMetadata},
+            server::{error::Result,
+                     helpers}};
+
+// Biome checksums are BLAKE2b hashes of the hart
+const CHECKSUM_ALGORITHM: &str = "BLAKE2b-256";
+const TOOL_NAME: &str = "biome-builder";
+const LICENSE_REF_PREFIX: &str = "LicenseRef-";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SbomFormat {
+    CycloneDx,
+    Spdx,
+}
+
+impl FromStr for SbomFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "cyclonedx" => Ok(SbomFormat::CycloneDx),
+            "spdx" => Ok(SbomFormat::Spdx),
+            _ => Err(format!("Invalid SBOM format '{}'", value)),
+        }
+    }
+}
+
+// A release as it is listed in an SBOM. Dependencies that are unknown to this builder or not
+// visible to the requester are listed by ident alone.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SbomPackage {
+    pub ident:    BuilderPackageIdent,
+    pub target:   String,
+    pub checksum: Option<String>,
+    pub licenses: Vec<String>,
+    // Fully qualified idents of the direct runtime dependencies
+    pub deps:     Vec<String>,
+}
+
+impl SbomPackage {
+    pub fn from_package(package: &Package) -> Self {
+        let metadata = PackageMetadata::from_manifest(package.id, &package.manifest);
+        SbomPackage { ident:    package.ident.clone(),
+                      target:   package.target.to_string(),
+                      checksum: Some(package.checksum.clone()),
+                      licenses: metadata.licenses,
+                      deps:     package.deps.iter().map(|d| d.to_string()).collect(), }
+    }
+
+    pub fn unresolved(ident: &BuilderPackageIdent, target: PackageTarget) -> Self {
+        SbomPackage { ident:    ident.clone(),
+                      target:   target.to_string(),
+                      checksum: None,
+                      licenses: Vec::new(),
+                      deps:     Vec::new(), }
+    }
+
+    fn purl(&self) -> String {
+        format!("pkg:biome/{}/{}@{}?release={}&target={}",
+                self.ident.origin,
+                self.ident.name,
+                self.ident.version.as_deref().unwrap_or_default(),
+                self.ident.release.as_deref().unwrap_or_default(),
+                self.target)
+    }
+
+    fn version(&self) -> String {
+        format!("{}/{}",
+                self.ident.version.as_deref().unwrap_or_default(),
+                self.ident.release.as_deref().unwrap_or_default())
+    }
+}
+
+// What an SBOM describes, a single release or every release in a channel
+pub(crate) enum SbomSubject {
+    Release(SbomPackage),
+    Channel {
+        origin:   String,
+        channel:  String,
+        // Fully qualified idents of the releases in the channel
+        packages: Vec<String>,
+    },
+}
+
+pub(crate) struct Sbom {
+    pub subject:  SbomSubject,
+    // Every package the subject pulls in, the release itself excepted
+    pub packages: Vec<SbomPackage>,
+}
+
+impl Sbom {
+    fn name(&self) -> String {
+        match self.subject {
+            SbomSubject::Release(ref root) => root.ident.to_string(),
+            SbomSubject::Channel { ref origin,
+                                   ref channel,
+                                   .. } => format!("{}/{}", origin, channel),
+        }
+    }
+
+    // The releases the subject directly depends on or contains
+    fn top_level(&self) -> &[String] {
+        match self.subject {
+            SbomSubject::Release(ref root) => &root.deps,
+            SbomSubject::Channel { ref packages, .. } => packages,
+        }
+    }
+
+    fn root(&self) -> Option<&SbomPackage> {
+        match self.subject {
+            SbomSubject::Release(ref root) => Some(root),
+            SbomSubject::Channel { .. } => None,
+        }
+    }
+
+    pub fn render(&self, format: SbomFormat) -> Value {
+        let serial = Uuid::new_v4();
+        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        match format {
+            SbomFormat::CycloneDx => self.cyclonedx(serial, &timestamp),
+            SbomFormat::Spdx => self.spdx(serial, &timestamp),
+        }
+    }
+
+    /// A CycloneDX 1.5 JSON document.
+    fn cyclonedx(&self, serial: Uuid, timestamp: &str) -> Value {
+        let known = self.packages
+                        .iter()
+                        .map(|p| p.ident.to_string())
+                        .collect::<HashSet<_>>();
+        let top_level = self.top_level().iter().cloned().collect::<HashSet<_>>();
+        let depends_on = |deps: &[String]| {
+            deps.iter()
+                .filter(|d| known.contains(*d))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let metadata_component = match self.root() {
+            Some(root) => cyclonedx_component(root, "application"),
+            None => {
+                json!({ "type": "application",
+                        "bom-ref": self.name(),
+                        "name": self.name() })
+            }
+        };
+
+        // In a channel SBOM the releases of the channel are the applications
+        let in_channel = |p: &SbomPackage| {
+            self.root().is_none() && top_level.contains(&p.ident.to_string())
+        };
+        let components = self.packages
+                             .iter()
+                             .map(|p| {
+                                 let kind = if in_channel(p) { "application" } else { "library" };
+                                 cyclonedx_component(p, kind)
+                             })
+                             .collect::<Vec<_>>();
+
+        let mut dependencies = vec![json!({ "ref": self.name(),
+                                            "dependsOn": depends_on(self.top_level()) })];
+        for package in self.packages.iter() {
+            dependencies.push(json!({ "ref": package.ident.to_string(),
+                                      "dependsOn": depends_on(&package.deps) }));
+        }
+
+        json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "serialNumber": format!("urn:uuid:{}", serial),
+            "version": 1,
+            "metadata": {
+                "timestamp": timestamp,
+                "tools": { "components": [{ "type": "application", "name": TOOL_NAME }] },
+                "component": metadata_component,
+            },
+            "components": components,
+            "dependencies": dependencies,
+        })
+    }
+
+    /// An SPDX 2.3 JSON document.
+    fn spdx(&self, namespace: Uuid, timestamp: &str) -> Value {
+        let all = self.root().into_iter().chain(self.packages.iter()).collect::<Vec<_>>();
+        let spdx_ids = all.iter()
+                          .enumerate()
+                          .map(|(i, p)| (p.ident.to_string(), format!("SPDXRef-Package-{}", i)))
+                          .collect::<HashMap<_, _>>();
+
+        let packages = all.iter()
+                          .map(|p| spdx_package(p, &spdx_ids[&p.ident.to_string()]))
+                          .collect::<Vec<_>>();
+
+        let described = match self.root() {
+            Some(root) => vec![root.ident.to_string()],
+            None => self.top_level().to_vec(),
+        };
+        let mut relationships = described.iter()
+                                         .filter_map(|ident| spdx_ids.get(ident))
+                                         .map(|id| {
+                                             json!({ "spdxElementId": "SPDXRef-DOCUMENT",
+                                                     "relationshipType": "DESCRIBES",
+                                                     "relatedSpdxElement": id })
+                                         })
+                                         .collect::<Vec<_>>();
+        for package in all.iter() {
+            let id = &spdx_ids[&package.ident.to_string()];
+            relationships.extend(package.deps
+                                        .iter()
+                                        .filter_map(|dep| spdx_ids.get(dep))
+                                        .map(|dep_id| {
+                                            json!({ "spdxElementId": id,
+                                                    "relationshipType": "DEPENDS_ON",
+                                                    "relatedSpdxElement": dep_id })
+                                        }));
+        }
+
+        let mut document = json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": self.name(),
+            "documentNamespace": format!("urn:uuid:{}", namespace),
+            "creationInfo": {
+                "created": timestamp,
+                "creators": [format!("Tool: {}", TOOL_NAME)],
+            },
+            "packages": packages,
+            "relationships": relationships,
+        });
+
+        // Every license reference used by a package has to be defined in the document
+        let license_refs = all.iter()
+                              .flat_map(|p| p.licenses.iter())
+                              .map(|l| (spdx_license(l), l))
+                              .filter(|(id, _)| id.starts_with(LICENSE_REF_PREFIX))
+                              .collect::<BTreeMap<_, _>>();
+        if !license_refs.is_empty() {
+            document["hasExtractedLicensingInfos"] =
+                license_refs.into_iter()
+                            .map(|(id, license)| {
+                                json!({ "licenseId": id,
+                                        "name": license,
+                                        "extractedText": license })
+                            })
+                            .collect();
+        }
+        document
+    }
+}
+
+fn cyclonedx_component(package: &SbomPackage, kind: &str) -> Value {
+    let mut component = json!({
+        "type": kind,
+        "bom-ref": package.ident.to_string(),
+        "group": package.ident.origin,
+        "name": package.ident.name,
+        "version": package.version(),
+        "purl": package.purl(),
+    });
+    if let Some(ref checksum) = package.checksum {
+        component["hashes"] = json!([{ "alg": CHECKSUM_ALGORITHM, "content": checksum }]);
+    }
+    if !package.licenses.is_empty() {
+        component["licenses"] = package.licenses
+                                       .iter()
+                                       .map(|l| json!({ "license": { "name": l } }))
+                                       .collect();
+    }
+    component
+}
+
+fn spdx_package(package: &SbomPackage, spdx_id: &str) -> Value {
+    let licenses = if package.licenses.is_empty() {
+        "NOASSERTION".to_string()
+    } else {
+        package.licenses
+               .iter()
+               .map(|l| spdx_license(l))
+               .collect::<Vec<_>>()
+               .join(" AND ")
+    };
+
+    let mut spdx_package = json!({
+        "SPDXID": spdx_id,
+        "name": format!("{}/{}", package.ident.origin, package.ident.name),
+        "versionInfo": package.version(),
+        "supplier": format!("Organization: {}", package.ident.origin),
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+        "licenseConcluded": "NOASSERTION",
+        "licenseDeclared": licenses,
+        "externalRefs": [{ "referenceCategory": "PACKAGE-MANAGER",
+                           "referenceType": "purl",
+                           "referenceLocator": package.purl() }],
+    });
+    if let Some(ref checksum) = package.checksum {
+        spdx_package["checksums"] = json!([{ "algorithm": CHECKSUM_ALGORITHM,
+                                             "checksumValue": checksum }]);
+    }
+    spdx_package
+}
+
+// Plans usually declare SPDX license ids, anything else is kept as a license reference
+fn spdx_license(license: &str) -> String {
+    if license.chars()
+              .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+')
+    {
+        license.to_string()
+    } else {
+        let id = license.chars()
+                        .map(|c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '-' })
+                        .collect::<String>();
+        format!("{}{}", LICENSE_REF_PREFIX, id)
+    }
+}
+
+/// Loads the given releases of `target` for an SBOM. Releases the requester can't see are
+/// listed by ident alone.
+pub(crate) fn load_packages(req: &HttpRequest,
+                            opt_session_id: Option<u64>,
+                            idents: Vec<BuilderPackageIdent>,
+                            target: PackageTarget,
+                            conn: &mut PgConnection)
+                            -> Result<Vec<SbomPackage>> {
+    let wanted = idents.iter()
+                       .map(|i| (i.to_string(), i.clone()))
+                       .collect::<BTreeMap<_, _>>();
+    let packages = helpers::visible_package_group(req, opt_session_id, idents, target, conn)?;
+    let mut found = packages.iter()
+                            .map(|pkg| (pkg.ident.to_string(), SbomPackage::from_package(pkg)))
+                            .collect::<HashMap<_, _>>();
+
+    let mut sbom_packages = Vec::new();
+    for (key, ident) in wanted {
+        let package = found.remove(&key)
+                           .unwrap_or_else(|| SbomPackage::unresolved(&ident, target));
+        sbom_packages.push(package);
+    }
+    Ok(sbom_packages)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn package(ident: &str, deps: &[&str]) -> SbomPackage {
+        SbomPackage { ident:    BuilderPackageIdent::from_str(ident).unwrap(),
+                      target:   "x86_64-linux".to_string(),
+                      checksum: Some("abc123".to_string()),
+                      licenses: vec!["MIT".to_string()],
+                      deps:     deps.iter().map(|d| d.to_string()).collect(), }
+    }
+
+    fn release_sbom() -> Sbom {
+        Sbom { subject:  SbomSubject::Release(package("core/redis/4.0.14/20190321163516",
+                                                      &["core/glibc/2.27/20190115002733"])),
+               packages: vec![package("core/glibc/2.27/20190115002733",
+                                      &["core/linux-headers/4.17.12/20190115002705"]),
+                              package("core/linux-headers/4.17.12/20190115002705", &[])], }
+    }
+
+    #[test]
+    fn cyclonedx_lists_components_and_dependencies() {
+        let bom = release_sbom().cyclonedx(Uuid::nil(), "2025-09-16T00:00:00Z");
+
+        assert_eq!(bom["bomFormat"], "CycloneDX");
+        assert_eq!(bom["metadata"]["component"]["bom-ref"],
+                   "core/redis/4.0.14/20190321163516");
+        assert_eq!(bom["components"].as_array().unwrap().len(), 2);
+        assert_eq!(bom["components"][0]["purl"],
+                   "pkg:biome/core/glibc@2.27?release=20190115002733&target=x86_64-linux");
+        assert_eq!(bom["components"][0]["hashes"][0]["alg"], "BLAKE2b-256");
+        assert_eq!(bom["dependencies"][0]["dependsOn"][0], "core/glibc/2.27/20190115002733");
+    }
+
+    #[test]
+    fn spdx_describes_the_release() {
+        let doc = release_sbom().spdx(Uuid::nil(), "2025-09-16T00:00:00Z");
+
+        assert_eq!(doc["spdxVersion"], "SPDX-2.3");
+        assert_eq!(doc["packages"].as_array().unwrap().len(), 3);
+        assert_eq!(doc["packages"][0]["licenseDeclared"], "MIT");
+        assert_eq!(doc["relationships"][0]["relationshipType"], "DESCRIBES");
+        assert_eq!(doc["relationships"][0]["relatedSpdxElement"], "SPDXRef-Package-0");
+        assert_eq!(doc["relationships"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn non_spdx_licenses_become_references() {
+        assert_eq!(spdx_license("Apache-2.0"), "Apache-2.0");
+        assert_eq!(spdx_license("Public Domain"), "LicenseRef-Public-Domain");
+    }
+
+    #[test]
+    fn spdx_defines_license_references() {
+        let mut sbom = release_sbom();
+        sbom.packages[0].licenses = vec!["MIT".to_string(), "Public Domain".to_string()];
+
+        let doc = sbom.spdx(Uuid::nil(), "2025-09-16T00:00:00Z");
+
+        assert_eq!(doc["packages"][1]["licenseDeclared"],
+                   "MIT AND LicenseRef-Public-Domain");
+        assert_eq!(doc["hasExtractedLicensingInfos"],
+                   json!([{ "licenseId": "LicenseRef-Public-Domain",
+                            "name": "Public Domain",
+                            "extractedText": "Public Domain" }]));
+        assert!(release_sbom().spdx(Uuid::nil(), "2025-09-16T00:00:00Z")
+                              .get("hasExtractedLicensingInfos")
+                              .is_none());
+    }
+}
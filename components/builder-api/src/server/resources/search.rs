@@ -0,0 +1,511 @@
+// Biome project based on Chef Habitat's code (c) 2016-2020 Chef Software, Inc
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeMap,
+          str::FromStr};
+
+use diesel::{debug_query,
+             pg::Pg,
+             sql_query,
+             sql_types::{Array,
+                         BigInt,
+                         Bool,
+                         Integer,
+                         Nullable,
+                         Text},
+             PgConnection,
+             QueryableByName,
+             RunQueryDsl};
+
+use crate::{bio_core::package::{PackageIdent,
+                                PackageTarget},
+            db::models::package::{BuilderPackageIdent,
+                                  SERVICE_MANIFEST_KEYS},
+            server::{error::{Error,
+                             Result},
+                     helpers}};
+
+// A search query split into its free text and its field qualifiers, e.g.
+// `redis origin:core target:x86_64-linux exposes:6379 export:port`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct SearchTerms {
+    // Prefix tsquery over the package idents and descriptions, None when the query only has
+    // qualifiers. Every word is a quoted lexeme, so tsquery operators in it match literally.
+    pub text:         Option<String>,
+    pub origin:       Option<String>,
+    pub target:       Option<String>,
+    pub channel:      Option<String>,
+    pub exposes:      Option<i32>,
+    // Direct runtime dependency, either `origin/name` or a more qualified ident
+    pub dep:          Option<String>,
+    pub package_type: Option<String>,
+    pub visibility:   Option<String>,
+    // Names of the binds the package has to export, all of them
+    pub exports:      Vec<String>,
+    // A bind the package consumes
+    pub bind:         Option<String>,
+    // Whether the package is a service, the same way `Package::is_a_service` tells
+    pub service:      Option<bool>,
+    // Key/value labels of the release or its package, all of them
+    pub labels:       Vec<(String, String)>,
+}
+
+impl FromStr for SearchTerms {
+    type Err = String;
+
+    fn from_str(query: &str) -> std::result::Result<Self, Self::Err> {
+        let mut terms = SearchTerms::default();
+        let mut words = Vec::new();
+
+        for token in query.split_whitespace() {
+            let (field, value) = match token.split_once(':') {
+                Some((field, value)) => (field, value),
+                None => {
+                    // `core/redis` searches for both parts of the ident
+                    words.extend(token.split('/')
+                                      .filter(|w| !w.is_empty())
+                                      .map(str::to_string));
+                    continue;
+                }
+            };
+
+            if value.is_empty() {
+                return Err(format!("Missing value for '{}'", field));
+            }
+
+            match field {
+                "origin" => terms.origin = Some(value.to_string()),
+                "target" => {
+                    PackageTarget::from_str(value).map_err(|_| {
+                                                      format!("Invalid package target '{}'",
+                                                              value)
+                                                  })?;
+                    terms.target = Some(value.to_string());
+                }
+                "channel" => terms.channel = Some(value.to_string()),
+                "exposes" => {
+                    let port = value.parse::<i32>()
+                                    .map_err(|_| format!("Invalid port '{}'", value))?;
+                    terms.exposes = Some(port);
+                }
+                "dep" => terms.dep = Some(value.trim_end_matches('/').to_string()),
+                "type" => {
+                    match value.to_lowercase().as_str() {
+                        "standard" | "native" => terms.package_type = Some(value.to_lowercase()),
+                        _ => return Err(format!("Invalid package type '{}'", value)),
+                    }
+                }
+                "visibility" => {
+                    match value.to_lowercase().as_str() {
+                        "public" | "private" | "hidden" => {
+                            terms.visibility = Some(value.to_lowercase())
+                        }
+                        _ => return Err(format!("Invalid visibility '{}'", value)),
+                    }
+                }
+                "export" => terms.exports.push(value.to_string()),
+                "label" => {
+                    let mut labels = helpers::label_filter(Some(value))?;
+                    terms.labels.append(&mut labels);
+                }
+                "bind" => terms.bind = Some(value.to_string()),
+                "service" => {
+                    let service = value.parse::<bool>()
+                                       .map_err(|_| format!("Invalid service flag '{}'", value))?;
+                    terms.service = Some(service);
+                }
+                _ => return Err(format!("Unknown search qualifier '{}'", field)),
+            }
+        }
+
+        if !words.is_empty() {
+            let lexemes = words.iter().map(|w| tsquery_lexeme(w)).collect::<Vec<_>>();
+            terms.text = Some(format!("{}:*", lexemes.join(" & ")));
+        }
+
+        Ok(terms)
+    }
+}
+
+// Quotes a word for `to_tsquery`, which otherwise fails on its operators and parentheses
+fn tsquery_lexeme(word: &str) -> String {
+    format!("'{}'", word.replace('\\', "\\\\").replace('\'', "''"))
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub(crate) struct SearchFacets {
+    pub origin:  BTreeMap<String, i64>,
+    pub target:  BTreeMap<String, i64>,
+    pub channel: BTreeMap<String, i64>,
+}
+
+// A package shipping files that match a path search
+#[derive(Clone, Debug, QueryableByName, Serialize)]
+pub(crate) struct FileMatch {
+    #[sql_type = "Text"]
+    pub ident:       String,
+    #[sql_type = "Text"]
+    pub target:      String,
+    // The matched paths, relative to the package install directory
+    #[sql_type = "Array<Text>"]
+    pub paths:       Vec<String>,
+    #[serde(skip)]
+    #[sql_type = "BigInt"]
+    pub total_count: i64,
+}
+
+pub(crate) struct SearchFilesByPath<'a> {
+    pub pattern:    &'a str,
+    pub target:     Option<&'a str>,
+    pub origin:     Option<&'a str>,
+    pub channel:    Option<&'a str>,
+    pub account_id: Option<i64>,
+    pub page:       i64,
+    pub limit:      i64,
+}
+
+/// Turns a file path query into a `like` pattern. `*` matches any run of characters, every
+/// other character matches itself.
+pub(crate) fn path_pattern(query: &str) -> std::result::Result<String, String> {
+    let query = query.trim().trim_start_matches('/');
+    if query.is_empty() || query.chars().all(|c| c == '*') {
+        return Err("The path to search for is empty".to_string());
+    }
+
+    let mut pattern = String::with_capacity(query.len());
+    for c in query.chars() {
+        match c {
+            '*' => pattern.push('%'),
+            '%' | '_' | '\\' => {
+                pattern.push('\\');
+                pattern.push(c);
+            }
+            _ => pattern.push(c),
+        }
+    }
+    Ok(pattern)
+}
+
+// Either a package of the requested page, with the total count and its position in the
+// results, or a facet value and its count
+#[derive(Clone, Debug, QueryableByName)]
+struct SearchRow {
+    #[sql_type = "Text"]
+    facet:    String,
+    #[sql_type = "Text"]
+    value:    String,
+    #[sql_type = "BigInt"]
+    count:    i64,
+    #[sql_type = "BigInt"]
+    position: i64,
+}
+
+pub(crate) struct SearchPackagesByTerms<'a> {
+    pub terms:      &'a SearchTerms,
+    pub account_id: Option<i64>,
+    pub page:       i64,
+    pub limit:      i64,
+}
+
+// Every qualifier is bound, a null one doesn't filter. Private and hidden packages are only
+// matched for members of their origin, like the plain ident search does. A label of the
+// release takes precedence over the same label of its package, like `PackageLabels::effective`.
+const MATCHED_PACKAGES: &str = r###"
+    with matched as (
+        select op.id, op.ident, op.origin, op.target
+          from origin_packages as op
+         where op.hidden = false
+           and ($1::text is null
+                or to_tsquery($1) @@ op.ident_vector
+                or op.id in (select opm.package_id from origin_package_metadata as opm
+                              where to_tsvector('simple', coalesce(opm.description, ''))
+                                    @@ to_tsquery('simple', $1)))
+           and ($2::text is null or op.origin = $2)
+           and ($3::text is null or op.target = $3)
+           and ($4::text is null
+                or exists (select 1 from origin_channel_packages as ocp
+                             join origin_channels as oc on oc.id = ocp.channel_id
+                            where ocp.package_id = op.id and oc.name = $4))
+           and ($5::integer is null or $5 = any(op.exposes))
+           and ($6::text is null
+                or exists (select 1 from unnest(op.deps) as d(ident)
+                            where d.ident = $6
+                               or d.ident like replace(replace(replace($6, '\', '\\'),
+                                                               '%', '\%'),
+                                                       '_', '\_') || '/%'))
+           and ($7::text is null or lower(op.package_type) = $7)
+           and ($8::text is null or op.visibility::text = $8)
+           and ($10::text[] is null
+                or (select coalesce(array_agg(ope.name), '{}') from origin_package_exports as ope
+                     where ope.package_id = op.id) @> $10)
+           and ($11::text is null
+                or exists (select 1 from origin_package_binds as opb
+                            where opb.package_id = op.id and opb.name = $11))
+           and ($12::boolean is null or $12 = (op.manifest like any($17)))
+           and ($13::text[] is null
+                or not exists (select 1 from unnest($13::text[], $14::text[]) as l(key, value)
+                                where l.value is distinct from
+                                      coalesce((select opl.value
+                                                  from origin_package_labels as opl
+                                                 where opl.package_id = op.id
+                                                   and opl.key = l.key),
+                                               (select opsl.value
+                                                  from origin_package_settings_labels as opsl
+                                                 where opsl.origin = op.origin
+                                                   and opsl.name = op.name
+                                                   and opsl.key = l.key))))
+           and (op.visibility = 'public'
+                or op.origin in (select origin from origin_members where account_id = $9))
+    )"###;
+
+/// Returns a page of the packages matching the search terms, their total count and the
+/// facet counts over all of them.
+pub(crate) fn search_packages(conn: &mut PgConnection,
+                              req: &SearchPackagesByTerms)
+                              -> Result<(Vec<BuilderPackageIdent>, i64, SearchFacets)> {
+    let terms = req.terms;
+    let text = terms.text.as_deref();
+    let origin = terms.origin.as_deref();
+    let target = terms.target.as_deref();
+    let channel = terms.channel.as_deref();
+    let dep = terms.dep.as_deref();
+    let package_type = terms.package_type.as_deref();
+    let visibility = terms.visibility.as_deref();
+    let exports = if terms.exports.is_empty() {
+        None
+    } else {
+        Some(&terms.exports)
+    };
+    let bind = terms.bind.as_deref();
+    let (label_keys, label_values): (Vec<String>, Vec<String>) =
+        terms.labels.iter().cloned().unzip();
+    let (label_keys, label_values) = if terms.labels.is_empty() {
+        (None, None)
+    } else {
+        (Some(label_keys), Some(label_values))
+    };
+
+    // `service:` looks for the same manifest settings `Package::is_a_service` does
+    let service_patterns =
+        SERVICE_MANIFEST_KEYS.iter()
+                             .map(|key| format!("%{}%", key.replace('_', "\\_")))
+                             .collect::<Vec<_>>();
+
+    // The matched packages are only computed once, the page and the facets are read from them
+    // in the same statement
+    let sql_stmt = format!("{}
+        select * from (select 'package' as facet, ident as value, count(*) over () as count,
+                              row_number() over (order by ident asc) as position
+                         from matched
+                        order by ident asc
+                        limit $15 offset $16) as page
+        union all
+        select 'origin' as facet, origin as value, count(*) as count, 0::bigint as position
+          from matched group by origin
+        union all
+        select 'target' as facet, target as value, count(*) as count, 0::bigint as position
+          from matched group by target
+        union all
+        select 'channel' as facet, oc.name as value, count(*) as count, 0::bigint as position
+          from matched
+          join origin_channel_packages as ocp on ocp.package_id = matched.id
+          join origin_channels as oc on oc.id = ocp.channel_id
+         group by oc.name",
+                           MATCHED_PACKAGES);
+
+    let query = sql_query(sql_stmt).bind::<Nullable<Text>, _>(text)
+                                   .bind::<Nullable<Text>, _>(origin)
+                                   .bind::<Nullable<Text>, _>(target)
+                                   .bind::<Nullable<Text>, _>(channel)
+                                   .bind::<Nullable<Integer>, _>(terms.exposes)
+                                   .bind::<Nullable<Text>, _>(dep)
+                                   .bind::<Nullable<Text>, _>(package_type)
+                                   .bind::<Nullable<Text>, _>(visibility)
+                                   .bind::<Nullable<BigInt>, _>(req.account_id)
+                                   .bind::<Nullable<Array<Text>>, _>(exports)
+                                   .bind::<Nullable<Text>, _>(bind)
+                                   .bind::<Nullable<Bool>, _>(terms.service)
+                                   .bind::<Nullable<Array<Text>>, _>(&label_keys)
+                                   .bind::<Nullable<Array<Text>>, _>(&label_values)
+                                   .bind::<BigInt, _>(req.limit)
+                                   .bind::<BigInt, _>((req.page - 1) * req.limit)
+                                   .bind::<Array<Text>, _>(&service_patterns);
+
+    debug!("debug_query {}", debug_query::<Pg, _>(&query));
+
+    let rows = query.load::<SearchRow>(conn).map_err(Error::DieselError)?;
+
+    let mut page = Vec::new();
+    let mut total_count = None;
+    let mut facets = SearchFacets::default();
+    for row in rows {
+        match row.facet.as_str() {
+            "package" => {
+                total_count = Some(row.count);
+                page.push((row.position, PackageIdent::from_str(&row.value)?));
+            }
+            "origin" => {
+                facets.origin.insert(row.value, row.count);
+            }
+            "target" => {
+                facets.target.insert(row.value, row.count);
+            }
+            _ => {
+                facets.channel.insert(row.value, row.count);
+            }
+        }
+    }
+    page.sort_by_key(|(position, _)| *position);
+    let packages = page.into_iter()
+                       .map(|(_, ident)| BuilderPackageIdent(ident))
+                       .collect();
+
+    // A page past the end has no rows to carry the total, every matched package has exactly
+    // one origin though
+    let total_count = total_count.unwrap_or_else(|| facets.origin.values().sum());
+
+    Ok((packages, total_count, facets))
+}
+
+/// Lists the packages shipping a file whose path matches the pattern, either whole or from
+/// one of its directories on, e.g. `bin/psql` or `libssl.so.*`.
+pub(crate) fn search_files(conn: &mut PgConnection,
+                           req: &SearchFilesByPath)
+                           -> Result<(Vec<FileMatch>, i64)> {
+    // Only packages uploaded since file listings are captured can be found
+    let sql_stmt = r###"
+        select op.ident, op.target, array_agg(f.path order by f.path) as paths,
+               count(*) over () as total_count
+          from origin_package_file_paths as f
+          join origin_packages as op on op.id = f.package_id
+         where (f.path like $1 or f.path like '%/' || $1)
+           and op.hidden = false
+           and ($2::text is null or op.target = $2)
+           and ($3::text is null or op.origin = $3)
+           and ($4::text is null
+                or exists (select 1 from origin_channel_packages as ocp
+                             join origin_channels as oc on oc.id = ocp.channel_id
+                            where ocp.package_id = op.id and oc.name = $4))
+           and (op.visibility = 'public'
+                or op.origin in (select origin from origin_members where account_id = $5))
+         group by op.ident, op.target
+         order by op.ident, op.target
+         limit $6 offset $7"###;
+
+    let query = sql_query(sql_stmt).bind::<Text, _>(req.pattern)
+                                   .bind::<Nullable<Text>, _>(req.target)
+                                   .bind::<Nullable<Text>, _>(req.origin)
+                                   .bind::<Nullable<Text>, _>(req.channel)
+                                   .bind::<Nullable<BigInt>, _>(req.account_id)
+                                   .bind::<BigInt, _>(req.limit)
+                                   .bind::<BigInt, _>((req.page - 1) * req.limit);
+
+    debug!("debug_query {}", debug_query::<Pg, _>(&query));
+
+    let matches = query.load::<FileMatch>(conn).map_err(Error::DieselError)?;
+    let total_count = matches.first().map_or(0, |m| m.total_count);
+    Ok((matches, total_count))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn search_terms_plain_ident() {
+        let terms = SearchTerms::from_str("core/redis").unwrap();
+
+        assert_eq!(terms.text, Some("'core' & 'redis':*".to_string()));
+        assert_eq!(terms.origin, None);
+    }
+
+    #[test]
+    fn search_terms_qualifiers() {
+        let terms = SearchTerms::from_str("redis origin:core target:x86_64-linux channel:stable \
+                                           exposes:6379 dep:core/glibc/ type:Standard \
+                                           visibility:public").unwrap();
+
+        assert_eq!(terms,
+                   SearchTerms { text:         Some("'redis':*".to_string()),
+                                 origin:       Some("core".to_string()),
+                                 target:       Some("x86_64-linux".to_string()),
+                                 channel:      Some("stable".to_string()),
+                                 exposes:      Some(6379),
+                                 dep:          Some("core/glibc".to_string()),
+                                 package_type: Some("standard".to_string()),
+                                 visibility:   Some("public".to_string()),
+                                 exports:      vec![],
+                                 bind:         None,
+                                 service:      None,
+                                 labels:       vec![], });
+    }
+
+    #[test]
+    fn search_terms_quote_tsquery_operators() {
+        let terms = SearchTerms::from_str("c++ (redis) o'reilly!").unwrap();
+
+        assert_eq!(terms.text,
+                   Some("'c++' & '(redis)' & 'o''reilly!':*".to_string()));
+    }
+
+    #[test]
+    fn search_terms_only_qualifiers() {
+        let terms = SearchTerms::from_str("exposes:5432").unwrap();
+
+        assert_eq!(terms.text, None);
+        assert_eq!(terms.exposes, Some(5432));
+    }
+
+    #[test]
+    fn search_terms_bind_qualifiers() {
+        let terms =
+            SearchTerms::from_str("export:port export:superuser_name bind:database service:true")
+                .unwrap();
+
+        assert_eq!(terms.exports,
+                   vec!["port".to_string(), "superuser_name".to_string()]);
+        assert_eq!(terms.bind, Some("database".to_string()));
+        assert_eq!(terms.service, Some(true));
+    }
+
+    #[test]
+    fn search_terms_labels() {
+        let terms = SearchTerms::from_str("label:team=payments label:tier=critical").unwrap();
+
+        assert_eq!(terms.labels,
+                   vec![("team".to_string(), "payments".to_string()),
+                        ("tier".to_string(), "critical".to_string())]);
+        assert!(SearchTerms::from_str("label:team").is_err());
+        assert!(SearchTerms::from_str("label:Team=payments").is_err());
+    }
+
+    #[test]
+    fn search_terms_rejects_invalid_qualifiers() {
+        assert!(SearchTerms::from_str("owner:me").is_err());
+        assert!(SearchTerms::from_str("exposes:http").is_err());
+        assert!(SearchTerms::from_str("type:weird").is_err());
+        assert!(SearchTerms::from_str("target:nope").is_err());
+        assert!(SearchTerms::from_str("origin:").is_err());
+        assert!(SearchTerms::from_str("service:maybe").is_err());
+    }
+
+    #[test]
+    fn path_pattern_escapes_like_wildcards() {
+        assert_eq!(path_pattern("/bin/psql"), Ok("bin/psql".to_string()));
+        assert_eq!(path_pattern("libssl.so.*"), Ok("libssl.so.%".to_string()));
+        assert_eq!(path_pattern("lib/my_lib%"), Ok("lib/my\\_lib\\%".to_string()));
+        assert!(path_pattern("*").is_err());
+        assert!(path_pattern(" ").is_err());
+    }
+}
@@ -12,12 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+
 use actix_web::{body::BoxBody,
                 http::StatusCode,
                 web::{self,
                       Data,
                       Json,
                       Path,
+                      Query,
                       ServiceConfig},
                 HttpRequest,
                 HttpResponse};
@@ -27,10 +30,13 @@ use builder_core::Error::PackageSettingDeleteError;
 use crate::{db::models::{origin::*,
                          package::*,
                          settings::*},
+            bio_core::package::PackageIdent,
             server::{authorize::authorize_session,
                      error::{Error,
                              Result},
-                     helpers::req_state,
+                     helpers::{self,
+                               req_state,
+                               Target},
                      AppState}};
 
 use bytes::Bytes;
@@ -42,6 +48,12 @@ pub struct UpdateOriginPackageSettingsReq {
     pub visibility: String,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PackageLabelsReq {
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
 pub struct Settings;
 
 impl Settings {
@@ -55,7 +67,15 @@ impl Settings {
            .route("/settings/{origin}/{name}",
                   web::put().to(update_origin_package_settings))
            .route("/settings/{origin}/{name}",
-                  web::delete().to(delete_origin_package_settings));
+                  web::delete().to(delete_origin_package_settings))
+           .route("/settings/{origin}/{name}/labels",
+                  web::get().to(get_origin_package_labels))
+           .route("/settings/{origin}/{name}/labels",
+                  web::put().to(update_origin_package_labels))
+           .route("/settings/{origin}/{name}/{version}/{release}/labels",
+                  web::get().to(get_release_labels))
+           .route("/settings/{origin}/{name}/{version}/{release}/labels",
+                  web::put().to(update_release_labels));
     }
 }
 
@@ -227,6 +247,195 @@ async fn delete_origin_package_settings(req: HttpRequest,
     }
 }
 
+#[allow(clippy::needless_pass_by_value)]
+async fn get_origin_package_labels(req: HttpRequest,
+                                   path: Path<(String, String)>,
+                                   state: Data<AppState>)
+                                   -> HttpResponse {
+    let (origin, pkg) = path.into_inner();
+
+    let opt_session_id = match authorize_session(&req, None, None) {
+        Ok(session) => Some(session.get_id()),
+        Err(_) => None,
+    };
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    // Labels are as public as the package JSON that carries them
+    let get_ops = &GetOriginPackageSettings { origin: &origin,
+                                              name:   &pkg, };
+    let settings = match OriginPackageSettings::get(get_ops, &mut conn) {
+        Ok(settings) => settings,
+        Err(err) => {
+            debug!("{}", err);
+            return Error::DieselError(err).into();
+        }
+    };
+    let visibility = helpers::visibility_for_optional_session(&req, opt_session_id, &origin);
+    if !visibility.contains(&settings.visibility) {
+        return HttpResponse::new(StatusCode::NOT_FOUND);
+    }
+
+    match PackageLabels::package(&origin, &pkg, &mut conn).map_err(Error::DieselError) {
+        Ok(labels) => HttpResponse::Ok().json(PackageLabelsReq { labels }),
+        Err(err) => {
+            debug!("{}", err);
+            err.into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn update_origin_package_labels(req: HttpRequest,
+                                      path: Path<(String, String)>,
+                                      body: Json<PackageLabelsReq>,
+                                      state: Data<AppState>)
+                                      -> HttpResponse {
+    let (origin, pkg) = path.into_inner();
+
+    if let Err(err) = authorize_session(&req, Some(&origin), Some(OriginMemberRole::Maintainer)) {
+        return err.into();
+    }
+
+    if let Err(msg) = helpers::validate_labels(&body.labels) {
+        return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY,
+                                       BoxBody::new(Bytes::from(msg.into_bytes())));
+    }
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    // Package labels live next to the package settings, so those have to exist
+    let get_ops = &GetOriginPackageSettings { origin: &origin,
+                                              name:   &pkg, };
+    if let Err(err) = OriginPackageSettings::get(get_ops, &mut conn) {
+        debug!("{}", err);
+        return Error::DieselError(err).into();
+    }
+
+    match PackageLabels::set_package(&origin, &pkg, &body.labels, &mut conn) {
+        Ok(_) => {
+            let ident = PackageIdent::new(origin, pkg, None, None);
+            state.memcache.borrow_mut().clear_cache_for_package(&ident);
+            HttpResponse::Ok().json(body.0)
+        }
+        Err(err) => {
+            debug!("{}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn get_release_labels(req: HttpRequest,
+                            path: Path<(String, String, String, String)>,
+                            qtarget: Query<Target>,
+                            state: Data<AppState>)
+                            -> HttpResponse {
+    let (origin, pkg, version, release) = path.into_inner();
+
+    let opt_session_id = match authorize_session(&req, None, None) {
+        Ok(session) => Some(session.get_id()),
+        Err(_) => None,
+    };
+    let visibility = helpers::visibility_for_optional_session(&req, opt_session_id, &origin);
+
+    let ident = PackageIdent::new(origin, pkg, Some(version), Some(release));
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    let package = match release_for_labels(&req, &ident, &qtarget, visibility, &mut conn) {
+        Ok(package) => package,
+        Err(resp) => return resp,
+    };
+
+    match PackageLabels::release(package.id, &mut conn).map_err(Error::DieselError) {
+        Ok(labels) => HttpResponse::Ok().json(PackageLabelsReq { labels }),
+        Err(err) => {
+            debug!("{}", err);
+            err.into()
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn update_release_labels(req: HttpRequest,
+                               path: Path<(String, String, String, String)>,
+                               qtarget: Query<Target>,
+                               body: Json<PackageLabelsReq>,
+                               state: Data<AppState>)
+                               -> HttpResponse {
+    let (origin, pkg, version, release) = path.into_inner();
+
+    if let Err(err) = authorize_session(&req, Some(&origin), Some(OriginMemberRole::Maintainer)) {
+        return err.into();
+    }
+
+    if let Err(msg) = helpers::validate_labels(&body.labels) {
+        return HttpResponse::with_body(StatusCode::UNPROCESSABLE_ENTITY,
+                                       BoxBody::new(Bytes::from(msg.into_bytes())));
+    }
+
+    let ident = PackageIdent::new(origin, pkg, Some(version), Some(release));
+
+    let mut conn = match state.db.get_conn().map_err(Error::DbError) {
+        Ok(conn_ref) => conn_ref,
+        Err(err) => return err.into(),
+    };
+
+    // Members of the origin label releases of any visibility
+    let package = match release_for_labels(&req,
+                                           &ident,
+                                           &qtarget,
+                                           PackageVisibility::all(),
+                                           &mut conn)
+    {
+        Ok(package) => package,
+        Err(resp) => return resp,
+    };
+
+    match PackageLabels::set_release(package.id, &body.labels, &mut conn) {
+        Ok(_) => {
+            state.memcache.borrow_mut().clear_cache_for_package(&ident);
+            HttpResponse::Ok().json(body.0)
+        }
+        Err(err) => {
+            debug!("{}", err);
+            Error::DieselError(err).into()
+        }
+    }
+}
+
+// The release being labeled or read, among the ones of the given visibility
+fn release_for_labels(req: &HttpRequest,
+                      ident: &PackageIdent,
+                      qtarget: &Query<Target>,
+                      visibility: Vec<PackageVisibility>,
+                      conn: &mut PgConnection)
+                      -> std::result::Result<Package, HttpResponse> {
+    if !ident.valid() {
+        return Err(HttpResponse::new(StatusCode::BAD_REQUEST));
+    }
+
+    let target = helpers::requested_target(req, qtarget.target.as_deref())?;
+
+    Package::get(GetPackage { ident:      BuilderPackageIdent(ident.clone()),
+                              visibility,
+                              target:     BuilderPackageTarget(target), },
+                 conn).map_err(|err| {
+                          debug!("{}", err);
+                          Error::DieselError(err).into()
+                      })
+}
+
 fn package_settings_delete_preflight(origin: &str,
                                      pkg: &str,
                                      conn: &mut PgConnection)
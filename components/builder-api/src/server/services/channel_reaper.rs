@@ -0,0 +1,131 @@
+// Biome project based on Chef Habitat's code (c) 2016-2020 Chef Software, Inc
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Background worker that removes channels whose expiry has passed.
+
+use std::{thread,
+          time::Duration};
+
+use diesel::result::Error::NotFound;
+
+use crate::{bio_core::ChannelIdent,
+            config::Config,
+            db::{models::{channel::{Channel,
+                                    PackageChannelOperation,
+                                    PackageChannelTrigger,
+                                    PackageGroupChannelAudit},
+                          origin::{origin_audit,
+                                   OriginOperation}},
+                 DbPool}};
+
+use super::memcache::MemcacheClient;
+
+// Recorded as the requester name on the audit entries written by the reaper
+const REAPER_NAME: &str = "channel-reaper";
+
+pub fn start(config: &Config, db: DbPool) {
+    let interval = config.api.channel_reaper_interval;
+    if interval == 0 {
+        info!("Channel reaper disabled");
+        return;
+    }
+
+    let config = config.clone();
+    let spawned = thread::Builder::new().name("channel-reaper".to_string())
+                                        .spawn(move || {
+                                            let mut memcache =
+                                                MemcacheClient::new(&config.memcache);
+                                            loop {
+                                                reap_expired_channels(&db, &mut memcache);
+                                                thread::sleep(Duration::from_secs(interval));
+                                            }
+                                        });
+
+    if let Err(err) = spawned {
+        error!("Unable to start channel reaper, err = {}", err);
+    }
+}
+
+fn reap_expired_channels(db: &DbPool, memcache: &mut MemcacheClient) {
+    let mut conn = match db.get_conn() {
+        Ok(conn) => conn,
+        Err(err) => {
+            warn!("Channel reaper unable to get a db connection, err = {}", err);
+            return;
+        }
+    };
+
+    let channels = match Channel::list_expired(&mut conn) {
+        Ok(channels) => channels,
+        Err(err) => {
+            warn!("Channel reaper unable to list expired channels, err = {}", err);
+            return;
+        }
+    };
+
+    for channel in channels {
+        let ident = ChannelIdent::from(channel.name.as_str());
+
+        // The expiry column is only ever set through the API, but never reap the
+        // built-in channels even if someone set one by hand.
+        if ident == ChannelIdent::stable() || ident == ChannelIdent::unstable() {
+            continue;
+        }
+
+        let pkg_ids = match Channel::reap(&channel, &mut conn) {
+            Ok(pkg_ids) => pkg_ids,
+            Err(NotFound) => {
+                debug!("Channel {}/{} was already reaped", channel.origin, channel.name);
+                continue;
+            }
+            Err(err) => {
+                warn!("Failed to reap channel {}/{}, err = {}",
+                      channel.origin, channel.name, err);
+                continue;
+            }
+        };
+
+        info!("Reaped expired channel {}/{} ({} packages demoted)",
+              channel.origin,
+              channel.name,
+              pkg_ids.len());
+
+        memcache.clear_cache_for_channel(&channel.origin, &ident);
+
+        if !pkg_ids.is_empty() {
+            if let Err(err) = PackageGroupChannelAudit::audit(
+                PackageGroupChannelAudit {
+                    origin: &channel.origin,
+                    channel: &channel.name,
+                    package_ids: pkg_ids,
+                    operation: PackageChannelOperation::Demote,
+                    trigger: PackageChannelTrigger::Unknown,
+                    requester_id: channel.owner_id,
+                    requester_name: REAPER_NAME,
+                    group_id: 0_i64,
+                },
+                &mut conn,
+            ) {
+                debug!("Failed to save rank change to audit log: {}", err);
+            }
+        }
+
+        origin_audit(&channel.origin,
+                     OriginOperation::ChannelDelete,
+                     &channel.name,
+                     channel.owner_id,
+                     REAPER_NAME,
+                     &mut conn);
+    }
+}
@@ -24,6 +24,7 @@ pub enum Counter {
     GitHubEvent,
     RouteMessage,
     SearchPackages,
+    SearchFiles,
     UploadRequests,
     SingleUploadRequests,
     MultipartUploadRequests,
@@ -51,6 +52,7 @@ impl metrics::Metric for Counter {
             Counter::GitHubEvent => "github.event".into(),
             Counter::RouteMessage => "route-message".into(),
             Counter::SearchPackages => "search-packages".into(),
+            Counter::SearchFiles => "search-files".into(),
             Counter::UploadRequests => "upload-packages".into(),
             Counter::SingleUploadRequests => "upload-single".into(),
             Counter::MultipartUploadRequests => "upload-multi".into(),
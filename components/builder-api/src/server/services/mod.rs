@@ -1,3 +1,8 @@
+pub mod channel_reaper;
 pub mod memcache;
 pub mod metrics;
+pub mod package_delete;
+pub mod retention;
 pub mod s3;
+pub mod storage_check;
+pub mod store_worker;
@@ -0,0 +1,77 @@
+// Biome project based on Chef Habitat's code (c) 2016-2020 Chef Software, Inc
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deleting single releases, shared by the API and the retention worker.
+
+use diesel::PgConnection;
+
+use crate::{bio_core::{package::{PackageIdent,
+                                 PackageTarget},
+                       ChannelIdent},
+            db::models::{channel::Channel,
+                         package::{BuilderPackageIdent,
+                                   BuilderPackageTarget,
+                                   DeletePackage,
+                                   GetPackage,
+                                   Package,
+                                   PackageVisibility}},
+            server::{error::Result,
+                     resources::reverse_dependencies::{self,
+                                                       ReleaseDependent}}};
+
+/// Returns the reason the release can't be deleted, if any. Packages in the stable
+/// channel are never deleted, nor are releases other packages depend on at runtime.
+pub fn delete_blocker(ident: &PackageIdent,
+                      target: PackageTarget,
+                      conn: &mut PgConnection)
+                      -> Result<Option<String>> {
+    let channels = Package::list_package_channels(&BuilderPackageIdent(ident.clone()),
+                                                  target,
+                                                  PackageVisibility::all(),
+                                                  conn)?;
+    if channels.iter()
+               .any(|c| c.name == ChannelIdent::stable().to_string())
+    {
+        return Ok(Some(format!("Deleting package in stable channel not allowed '{}'",
+                               ident)));
+    }
+
+    let rdeps = reverse_dependencies::get_release_rdeps(conn,
+                                                        &ident.to_string(),
+                                                        &target.to_string(),
+                                                        None,
+                                                        true)?;
+    if rdeps.iter().any(ReleaseDependent::is_runtime) {
+        return Ok(Some(format!("Deleting package with rdeps not allowed '{}'", ident)));
+    }
+
+    Ok(None)
+}
+
+// TODO (SA): Eliminate need to do channel package deletion
+pub fn do_delete_package(ident: &PackageIdent,
+                         target: PackageTarget,
+                         conn: &mut PgConnection)
+                         -> Result<()> {
+    let pkg = Package::get(GetPackage { ident:      BuilderPackageIdent(ident.clone()),
+                                        visibility: PackageVisibility::all(),
+                                        target:     BuilderPackageTarget(target), },
+                           conn)?;
+
+    Channel::delete_channel_package(pkg.id, conn)?;
+    Package::delete(DeletePackage { ident:  BuilderPackageIdent(ident.clone()),
+                                    target: BuilderPackageTarget(target), },
+                    conn)?;
+    Ok(())
+}
@@ -0,0 +1,305 @@
+// Biome project based on Chef Habitat's code (c) 2016-2020 Chef Software, Inc
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Background worker that applies the origin retention policies.
+
+use std::{str::FromStr,
+          time::Duration};
+
+use chrono::{NaiveDateTime,
+             Utc};
+use diesel::{sql_query,
+             sql_types::{BigInt,
+                         Nullable,
+                         Text,
+                         Timestamptz},
+             PgConnection,
+             QueryableByName,
+             RunQueryDsl};
+
+use crate::{bio_core::package::{PackageIdent,
+                                PackageTarget},
+            config::Config,
+            db::{models::retention::{NewRetentionReport,
+                                     RetentionPolicy,
+                                     RetentionReport},
+                 DbPool},
+            server::error::{Error,
+                            Result}};
+
+use super::{memcache::MemcacheClient,
+            package_delete::{delete_blocker,
+                             do_delete_package},
+            s3::S3Handler,
+            store_worker};
+
+// Makes sure only one node applies the policies at a time
+const RETENTION_LOCK_KEY: i64 = 0x6269_6f6d_6572_6574;
+
+// A release never promoted out of unstable, along with its position among the releases of
+// the same package and target, newest first
+#[derive(Debug, QueryableByName)]
+struct UnpromotedRelease {
+    #[sql_type = "Text"]
+    ident:      String,
+    #[sql_type = "Text"]
+    target:     String,
+    #[sql_type = "BigInt"]
+    position:   i64,
+    #[sql_type = "Nullable<Timestamptz>"]
+    created_at: Option<NaiveDateTime>,
+}
+
+pub fn start(config: &Config, db: DbPool) {
+    let interval = config.api.retention_interval;
+    let config = config.clone();
+    store_worker::start("retention", interval, move || {
+        async move {
+            let packages = S3Handler::new(config.s3.clone());
+            let mut memcache = MemcacheClient::new(&config.memcache);
+            loop {
+                apply_retention_policies(&db, &packages, &mut memcache).await;
+                actix_rt::time::sleep(Duration::from_secs(interval)).await;
+            }
+        }
+    });
+}
+
+async fn apply_retention_policies(db: &DbPool,
+                                  packages: &S3Handler,
+                                  memcache: &mut MemcacheClient) {
+    let mut conn = match db.get_conn() {
+        Ok(conn) => conn,
+        Err(err) => {
+            warn!("Retention worker unable to get a db connection, err = {}", err);
+            return;
+        }
+    };
+
+    match store_worker::try_lock(&mut conn, RETENTION_LOCK_KEY) {
+        Ok(true) => (),
+        Ok(false) => {
+            debug!("Retention policies are being applied by another node");
+            return;
+        }
+        Err(err) => {
+            warn!("Retention worker unable to take the lock, err = {}", err);
+            return;
+        }
+    }
+
+    apply_policies(&mut conn, packages, memcache).await;
+
+    if let Err(err) = store_worker::unlock(&mut conn, RETENTION_LOCK_KEY) {
+        warn!("Retention worker unable to release the lock, err = {}", err);
+    }
+}
+
+async fn apply_policies(conn: &mut PgConnection,
+                        packages: &S3Handler,
+                        memcache: &mut MemcacheClient) {
+    let policies = match RetentionPolicy::list(conn) {
+        Ok(policies) => policies,
+        Err(err) => {
+            warn!("Retention worker unable to list policies, err = {}", err);
+            return;
+        }
+    };
+
+    for policy in policies {
+        let candidates = match retention_candidates(&policy, conn) {
+            Ok(candidates) => candidates,
+            Err(err) => {
+                warn!("Unable to evaluate retention policy for {}, err = {}",
+                      policy.origin, err);
+                continue;
+            }
+        };
+
+        if candidates.is_empty() {
+            continue;
+        }
+
+        let mut deleted = Vec::new();
+        let mut skipped = Vec::new();
+
+        for candidate in candidates {
+            let entry = format!("{} ({})", candidate.ident, candidate.target);
+            let (ident, target) = match (PackageIdent::from_str(&candidate.ident),
+                                         PackageTarget::from_str(&candidate.target))
+            {
+                (Ok(ident), Ok(target)) => (ident, target),
+                _ => {
+                    skipped.push(format!("{}: invalid ident or target", entry));
+                    continue;
+                }
+            };
+
+            match remove_release(&ident, target, packages, conn).await {
+                Ok(None) => {
+                    memcache.clear_cache_for_package(&ident);
+                    deleted.push(entry);
+                }
+                Ok(Some(reason)) => skipped.push(format!("{}: {}", entry, reason)),
+                Err(err) => {
+                    warn!("Retention worker failed to delete {}, err = {}", entry, err);
+                    skipped.push(format!("{}: {}", entry, err));
+                }
+            }
+        }
+
+        info!("Applied retention policy for {} ({} deleted, {} skipped)",
+              policy.origin,
+              deleted.len(),
+              skipped.len());
+
+        if let Err(err) = RetentionReport::create(&NewRetentionReport { origin:  &policy.origin,
+                                                                        deleted: &deleted,
+                                                                        skipped: &skipped, },
+                                                  conn)
+        {
+            warn!("Failed to save retention report for {}, err = {}",
+                  policy.origin, err);
+        }
+    }
+}
+
+// Releases only ever promoted to unstable that the policy selects for removal. The newest
+// releases of each package and target are positioned in the same order the latest release
+// is resolved in.
+fn retention_candidates(policy: &RetentionPolicy,
+                        conn: &mut PgConnection)
+                        -> Result<Vec<UnpromotedRelease>> {
+    let sql_stmt = r###"
+        select op.ident, op.target, op.created_at,
+               row_number() over (partition by op.name, op.target
+                                  order by string_to_array(op.version_array[1],'.')::numeric[] desc,
+                                           op.version_array[2] desc,
+                                           op.ident_array[4] desc) as position
+          from origin_packages_with_version_array as op
+         where op.origin = $1
+           and not exists (select 1 from origin_channel_packages as ocp
+                             join origin_channels as oc on oc.id = ocp.channel_id
+                            where ocp.package_id = op.id and oc.name <> 'unstable')
+         order by op.ident, op.target"###;
+
+    let releases = sql_query(sql_stmt).bind::<Text, _>(&policy.origin)
+                                      .load::<UnpromotedRelease>(conn)
+                                      .map_err(Error::DieselError)?;
+
+    let now = Utc::now().naive_utc();
+    Ok(releases.into_iter()
+               .filter(|release| is_candidate(policy, release, now))
+               .collect())
+}
+
+// The newest `keep_unstable_releases` releases, or the newest one when that rule isn't set,
+// are always kept. Older ones go once they are past the max age, or right away when only
+// the keep rule is set.
+fn is_candidate(policy: &RetentionPolicy, release: &UnpromotedRelease, now: NaiveDateTime) -> bool {
+    let keep = policy.keep_unstable_releases.unwrap_or(1).max(1);
+    if release.position <= i64::from(keep) {
+        return false;
+    }
+
+    match policy.unpromoted_max_age_days {
+        Some(days) => {
+            let cutoff = now - chrono::Duration::days(days.into());
+            release.created_at.map_or(false, |created_at| created_at < cutoff)
+        }
+        None => policy.keep_unstable_releases.is_some(),
+    }
+}
+
+// Returns the reason the release was kept, if it was
+async fn remove_release(ident: &PackageIdent,
+                        target: PackageTarget,
+                        packages: &S3Handler,
+                        conn: &mut PgConnection)
+                        -> Result<Option<String>> {
+    // Same safety checks as deleting a package through the API
+    if let Some(reason) = delete_blocker(ident, target, conn)? {
+        return Ok(Some(reason));
+    }
+
+    do_delete_package(ident, target, conn)?;
+
+    // The release is gone once its row is, a leftover object is found by the storage check
+    if let Err(err) = packages.delete(ident, target).await {
+        warn!("Unable to remove {} ({}) from the package store, err = {}",
+              ident, target, err);
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn policy(keep: Option<i32>, max_age_days: Option<i32>) -> RetentionPolicy {
+        RetentionPolicy { origin:                  "core".to_string(),
+                          keep_unstable_releases:  keep,
+                          unpromoted_max_age_days: max_age_days,
+                          created_at:              None,
+                          updated_at:              None, }
+    }
+
+    fn release(position: i64, age_days: i64, now: NaiveDateTime) -> UnpromotedRelease {
+        UnpromotedRelease { ident: format!("core/redis/5.0.{}/20200101000000", position),
+                            target: "x86_64-linux".to_string(),
+                            position,
+                            created_at: Some(now - chrono::Duration::days(age_days)) }
+    }
+
+    #[test]
+    fn keep_rule_removes_releases_past_the_newest() {
+        let now = Utc::now().naive_utc();
+        let policy = policy(Some(2), None);
+
+        assert!(!is_candidate(&policy, &release(1, 0, now), now));
+        assert!(!is_candidate(&policy, &release(2, 0, now), now));
+        assert!(is_candidate(&policy, &release(3, 0, now), now));
+    }
+
+    #[test]
+    fn newest_releases_are_kept_whatever_their_age() {
+        let now = Utc::now().naive_utc();
+        let policy = policy(Some(2), Some(30));
+
+        assert!(!is_candidate(&policy, &release(1, 90, now), now));
+        assert!(!is_candidate(&policy, &release(2, 90, now), now));
+        assert!(!is_candidate(&policy, &release(3, 10, now), now));
+        assert!(is_candidate(&policy, &release(3, 90, now), now));
+    }
+
+    #[test]
+    fn age_rule_never_removes_the_newest_release() {
+        let now = Utc::now().naive_utc();
+        let policy = policy(None, Some(30));
+
+        assert!(!is_candidate(&policy, &release(1, 90, now), now));
+        assert!(is_candidate(&policy, &release(2, 90, now), now));
+        assert!(!is_candidate(&policy, &release(2, 10, now), now));
+    }
+
+    #[test]
+    fn releases_without_a_creation_time_are_not_aged_out() {
+        let now = Utc::now().naive_utc();
+        let mut unknown_age = release(2, 0, now);
+        unknown_age.created_at = None;
+
+        assert!(!is_candidate(&policy(None, Some(30)), &unknown_age, now));
+    }
+}
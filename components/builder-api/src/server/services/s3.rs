@@ -35,6 +35,8 @@ use std::{fmt::Display,
           str::FromStr,
           time::Instant};
 
+use chrono::{DateTime,
+             NaiveDateTime};
 use futures::StreamExt;
 
 use rusoto_s3::{CompleteMultipartUploadRequest,
@@ -42,8 +44,10 @@ use rusoto_s3::{CompleteMultipartUploadRequest,
                 CompletedPart,
                 CreateBucketRequest,
                 CreateMultipartUploadRequest,
+                DeleteObjectRequest,
                 GetObjectRequest,
                 HeadObjectRequest,
+                ListObjectsV2Request,
                 PutObjectRequest,
                 S3Client,
                 UploadPartRequest,
@@ -74,6 +78,12 @@ pub struct S3Handler {
     bucket: String,
 }
 
+#[derive(Debug)]
+pub struct StoredObject {
+    pub key:           String,
+    pub last_modified: Option<NaiveDateTime>,
+}
+
 impl S3Handler {
     // The S3 Handler struct contains all of the credential
     // and target information that we should need to perfom
@@ -236,6 +246,67 @@ impl S3Handler {
         }
     }
 
+    pub async fn delete(&self, ident: &PackageIdent, target: PackageTarget) -> Result<()> {
+        let key = s3_key(ident, target)?;
+        self.delete_key(&key).await
+    }
+
+    pub async fn delete_key(&self, key: &str) -> Result<()> {
+        let request = DeleteObjectRequest { bucket: self.bucket.clone(),
+                                            key: key.to_string(),
+                                            ..Default::default() };
+
+        match self.client.delete_object(request).await {
+            Ok(_) => {
+                info!("Deleted {} from the package store", key);
+                Ok(())
+            }
+            Err(e) => {
+                warn!("Failed to delete object from S3, key={}: {:?}", key, e);
+                Err(Error::DeleteObject(e))
+            }
+        }
+    }
+
+    // Lists one page of the objects in the bucket, along with the token of the next page
+    // when there is one. Pass no token to start from the beginning.
+    pub async fn list_page(&self,
+                           continuation_token: Option<String>)
+                           -> Result<(Vec<StoredObject>, Option<String>)> {
+        let request = ListObjectsV2Request { bucket: self.bucket.clone(),
+                                             continuation_token,
+                                             ..Default::default() };
+
+        let response = match self.client.list_objects_v2(request).await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Failed to list objects in S3, bucket={}: {:?}",
+                      self.bucket, e);
+                return Err(Error::ListObjects(e));
+            }
+        };
+
+        let objects = response.contents
+                              .unwrap_or_default()
+                              .into_iter()
+                              .filter_map(|object| {
+                                  let last_modified =
+                                      object.last_modified
+                                            .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+                                            .map(|t| t.naive_utc());
+                                  object.key
+                                        .map(|key| StoredObject { key, last_modified })
+                              })
+                              .collect();
+
+        let next = match (response.is_truncated, response.next_continuation_token) {
+            (Some(true), Some(token)) => Some(token),
+            _ => None,
+        };
+
+        Ok((objects, next))
+    }
+
     async fn single_upload<P: Into<PathBuf> + Display>(&self,
                                                        key: &str,
                                                        hart: File,
@@ -354,7 +425,7 @@ impl S3Handler {
 
 // Helper function for programmatic creation of
 // the s3 object key
-fn s3_key(ident: &PackageIdent, target: PackageTarget) -> Result<String> {
+pub fn s3_key(ident: &PackageIdent, target: PackageTarget) -> Result<String> {
     // Calling this method first ensures that the ident is fully qualified and the correct errors
     // are returned in case of failure
     let hart_name = ident.archive_name_with_target(target)
@@ -0,0 +1,307 @@
+// Biome project based on Chef Habitat's code (c) 2016-2020 Chef Software, Inc
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Consistency check between the package store and the database.
+//!
+//! Objects in the bucket without a matching `origin_packages` row are orphans, rows
+//! without an object are missing, and leftovers of failed uploads in the data path are
+//! stale temp files. Orphans and temp files are only removed when asked to, missing
+//! objects and checksum mismatches are always just reported.
+
+use std::{collections::HashMap,
+          fs,
+          path::Path,
+          str::FromStr,
+          time::{Duration,
+                 SystemTime}};
+
+use chrono::{NaiveDateTime,
+             Utc};
+use diesel::{sql_query,
+             sql_types::Text,
+             PgConnection,
+             QueryableByName,
+             RunQueryDsl};
+use tempfile::tempdir_in;
+
+use crate::{bio_core::package::{PackageIdent,
+                                PackageTarget},
+            config::Config,
+            db::DbPool,
+            server::error::{Error,
+                            Result}};
+
+use super::{s3::{s3_key,
+                 S3Handler},
+            store_worker};
+
+// Uploads write the object before the row, and temp files live for the length of an
+// upload, so anything younger than this may still be in flight.
+const GRACE_PERIOD_SECS: u64 = 24 * 60 * 60;
+
+// Makes sure only one node goes through the bucket at a time
+const STORAGE_CHECK_LOCK_KEY: i64 = 0x6269_6f6d_6573_746f;
+
+#[derive(Debug, QueryableByName)]
+struct StoredPackage {
+    #[sql_type = "Text"]
+    ident:    String,
+    #[sql_type = "Text"]
+    target:   String,
+    #[sql_type = "Text"]
+    checksum: String,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct StorageCheck {
+    pub remove:           bool,
+    pub verify_checksums: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct StorageReport {
+    // Object keys without a package row
+    pub orphaned:            Vec<String>,
+    // Packages whose object is not in the store
+    pub missing:             Vec<String>,
+    // Packages whose object checksum differs from the recorded one
+    pub checksum_mismatches: Vec<String>,
+    // Leftover upload files in the data path
+    pub temp_files:          Vec<String>,
+    // Orphans and temp files that were removed
+    pub removed:             Vec<String>,
+}
+
+impl StorageReport {
+    pub fn is_clean(&self) -> bool {
+        self.orphaned.is_empty()
+        && self.missing.is_empty()
+        && self.checksum_mismatches.is_empty()
+        && self.temp_files.is_empty()
+    }
+}
+
+pub fn start(config: &Config, db: DbPool) {
+    let interval = config.api.storage_check_interval;
+    let opts = StorageCheck { remove:           config.api.storage_check_remove,
+                              verify_checksums: config.api.storage_check_verify_checksums, };
+    let config = config.clone();
+    store_worker::start("storage-check", interval, move || {
+        async move {
+            let packages = S3Handler::new(config.s3.clone());
+            loop {
+                check_once(&config, &db, &packages, opts).await;
+                actix_rt::time::sleep(Duration::from_secs(interval)).await;
+            }
+        }
+    });
+}
+
+async fn check_once(config: &Config, db: &DbPool, packages: &S3Handler, opts: StorageCheck) {
+    // The check gets connections of its own, this one only holds the lock
+    let mut conn = match db.get_conn() {
+        Ok(conn) => conn,
+        Err(err) => {
+            warn!("Storage check unable to get a db connection, err = {}", err);
+            return;
+        }
+    };
+
+    match store_worker::try_lock(&mut conn, STORAGE_CHECK_LOCK_KEY) {
+        Ok(true) => (),
+        Ok(false) => {
+            debug!("Storage check is being run by another node");
+            return;
+        }
+        Err(err) => {
+            warn!("Storage check unable to take the lock, err = {}", err);
+            return;
+        }
+    }
+
+    match check(config, db, packages, opts).await {
+        Ok(report) => log_report(&report),
+        Err(err) => warn!("Storage check failed, err = {}", err),
+    }
+
+    if let Err(err) = store_worker::unlock(&mut conn, STORAGE_CHECK_LOCK_KEY) {
+        warn!("Storage check unable to release the lock, err = {}", err);
+    }
+}
+
+fn log_report(report: &StorageReport) {
+    if report.is_clean() {
+        info!("Storage check found no problems");
+        return;
+    }
+
+    for key in report.orphaned.iter() {
+        warn!("Storage check: orphaned object {}", key);
+    }
+    for pkg in report.missing.iter() {
+        warn!("Storage check: missing object for {}", pkg);
+    }
+    for pkg in report.checksum_mismatches.iter() {
+        warn!("Storage check: checksum mismatch for {}", pkg);
+    }
+    for file in report.temp_files.iter() {
+        warn!("Storage check: stale temp file {}", file);
+    }
+    info!("Storage check removed {} orphans and temp files",
+          report.removed.len());
+}
+
+/// Compares the package store and the data path against the database.
+pub async fn check(config: &Config,
+                   db: &DbPool,
+                   packages: &S3Handler,
+                   opts: StorageCheck)
+                   -> Result<StorageReport> {
+    let mut report = StorageReport::default();
+    let cutoff = Utc::now().naive_utc() - chrono::Duration::seconds(GRACE_PERIOD_SECS as i64);
+
+    let mut conn = db.get_conn().map_err(Error::DbError)?;
+    let mut expected = HashMap::new();
+    for row in stored_packages(&mut conn)? {
+        let parsed = (PackageIdent::from_str(&row.ident), PackageTarget::from_str(&row.target));
+        match parsed {
+            (Ok(ident), Ok(target)) => {
+                let key = s3_key(&ident, target)?;
+                expected.insert(key, (ident, target, row.checksum));
+            }
+            _ => warn!("Storage check skipping unparsable package {} ({})",
+                       row.ident, row.target),
+        }
+    }
+    // Release the connection, the rest of the check can take a while
+    drop(conn);
+
+    // The bucket is compared a page at a time, packages left in `expected` at the end
+    // have no object
+    let mut present = Vec::new();
+    let mut continuation_token = None;
+    loop {
+        let (objects, next) = packages.list_page(continuation_token).await?;
+
+        for object in objects {
+            if let Some(package) = expected.remove(&object.key) {
+                present.push(package);
+                continue;
+            }
+            if !older_than(object.last_modified, cutoff) {
+                continue;
+            }
+            if opts.remove {
+                match packages.delete_key(&object.key).await {
+                    Ok(_) => report.removed.push(object.key.clone()),
+                    Err(err) => warn!("Unable to remove orphan {}, err = {}", object.key, err),
+                }
+            }
+            report.orphaned.push(object.key);
+        }
+
+        match next {
+            Some(token) => continuation_token = Some(token),
+            None => break,
+        }
+    }
+
+    for (ident, target, _) in expected.values() {
+        report.missing.push(format!("{} ({})", ident, target));
+    }
+
+    if opts.verify_checksums {
+        for (ident, target, checksum) in present {
+            match object_checksum(&config.api.data_path, packages, &ident, target).await {
+                Ok(actual) if actual == checksum => (),
+                Ok(actual) => {
+                    report.checksum_mismatches
+                          .push(format!("{} ({}): expected {}, found {}",
+                                        ident, target, checksum, actual))
+                }
+                Err(err) => {
+                    warn!("Unable to verify checksum of {} ({}), err = {}",
+                          ident, target, err)
+                }
+            }
+        }
+    }
+
+    sweep_temp_files(&config.api.data_path, opts.remove, &mut report)?;
+
+    report.orphaned.sort();
+    report.missing.sort();
+    report.checksum_mismatches.sort();
+    report.temp_files.sort();
+    report.removed.sort();
+    Ok(report)
+}
+
+fn stored_packages(conn: &mut PgConnection) -> Result<Vec<StoredPackage>> {
+    sql_query("select ident, target, checksum from origin_packages")
+        .load::<StoredPackage>(conn)
+        .map_err(Error::DieselError)
+}
+
+fn older_than(last_modified: Option<NaiveDateTime>, cutoff: NaiveDateTime) -> bool {
+    // An object without a modification time can't be in flight
+    last_modified.map_or(true, |t| t < cutoff)
+}
+
+async fn object_checksum(data_path: &Path,
+                         packages: &S3Handler,
+                         ident: &PackageIdent,
+                         target: PackageTarget)
+                         -> Result<String> {
+    let dir = tempdir_in(data_path)?;
+    let path = dir.path().join(ident.archive_name_with_target(target)?);
+    let mut archive = packages.download(&path, ident, target).await?;
+    archive.checksum().map_err(Error::BiomeCore)
+}
+
+// Failed uploads leave `<uuid>.tmp` files, renamed `.hart` files and `.tmp*` download
+// directories behind in the data path.
+fn sweep_temp_files(data_path: &Path, remove: bool, report: &mut StorageReport) -> Result<()> {
+    let cutoff = SystemTime::now() - Duration::from_secs(GRACE_PERIOD_SECS);
+
+    for entry in fs::read_dir(data_path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        let is_temp = if metadata.is_dir() {
+            name.starts_with(".tmp")
+        } else {
+            name.ends_with(".tmp") || name.ends_with(".hart")
+        };
+        if !is_temp || metadata.modified()? > cutoff {
+            continue;
+        }
+
+        let path = entry.path();
+        report.temp_files.push(path.display().to_string());
+        if remove {
+            let removed = if metadata.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            match removed {
+                Ok(_) => report.removed.push(path.display().to_string()),
+                Err(err) => warn!("Unable to remove temp file {:?}, err = {}", path, err),
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+// Biome project based on Chef Habitat's code (c) 2016-2020 Chef Software, Inc
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Background workers that go through the package store, shared by retention and the
+//! storage check.
+//!
+//! Every API node starts them, so a worker takes an advisory lock before doing anything that
+//! should only happen once per interval.
+
+use std::{future::Future,
+          thread};
+
+use diesel::{result::QueryResult,
+             sql_query,
+             sql_types::{BigInt,
+                         Bool},
+             PgConnection,
+             QueryableByName,
+             RunQueryDsl};
+
+use crate::server::feat;
+
+#[derive(QueryableByName)]
+struct AdvisoryLock {
+    #[sql_type = "Bool"]
+    locked: bool,
+}
+
+/// Starts a worker on a thread of its own, unless its interval is 0.
+pub fn start<F, Fut>(name: &str, interval: u64, work: F)
+    where F: FnOnce() -> Fut + Send + 'static,
+          Fut: Future<Output = ()> + 'static
+{
+    if interval == 0 {
+        info!("Worker {} disabled", name);
+        return;
+    }
+
+    // Packages aren't kept in the S3 store when Artifactory is enabled
+    if feat::is_enabled(feat::Artifactory) {
+        warn!("Worker {} is not supported with Artifactory and won't be run", name);
+        return;
+    }
+
+    // The package store client is async, so the worker drives its own runtime
+    let spawned = thread::Builder::new().name(name.to_string())
+                                        .spawn(move || actix_rt::System::new().block_on(work()));
+
+    if let Err(err) = spawned {
+        error!("Unable to start worker {}, err = {}", name, err);
+    }
+}
+
+/// Takes the advisory lock with the given key without waiting for it. The key only has to
+/// differ from other advisory locks on the database.
+pub fn try_lock(conn: &mut PgConnection, key: i64) -> QueryResult<bool> {
+    sql_query("select pg_try_advisory_lock($1) as locked").bind::<BigInt, _>(key)
+                                                          .get_result::<AdvisoryLock>(conn)
+                                                          .map(|lock| lock.locked)
+}
+
+/// Releases an advisory lock. It belongs to the session, so it has to be released on the
+/// connection that took it.
+pub fn unlock(conn: &mut PgConnection, key: i64) -> QueryResult<()> {
+    sql_query("select pg_advisory_unlock($1)").bind::<BigInt, _>(key)
+                                              .execute(conn)
+                                              .map(|_| ())
+}
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS origin_channels_expires_at;
+ALTER TABLE origin_channels DROP COLUMN IF EXISTS expires_at;
+-- Postgres cannot drop a value from an enum, 'channel_delete' is left on origin_operation
@@ -0,0 +1,4 @@
+ALTER TABLE origin_channels ADD COLUMN expires_at timestamptz;
+CREATE INDEX IF NOT EXISTS origin_channels_expires_at ON origin_channels(expires_at) WHERE expires_at IS NOT NULL;
+
+ALTER TYPE origin_operation ADD VALUE IF NOT EXISTS 'channel_delete';
@@ -0,0 +1 @@
+-- Postgres cannot drop a value from an enum, 'channel_rename' is left on origin_operation
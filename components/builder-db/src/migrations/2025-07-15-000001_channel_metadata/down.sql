@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS origin_channels_labels;
+ALTER TABLE origin_channels DROP COLUMN IF EXISTS labels;
+ALTER TABLE origin_channels DROP COLUMN IF EXISTS description;
@@ -0,0 +1,3 @@
+ALTER TABLE origin_channels ADD COLUMN description text;
+ALTER TABLE origin_channels ADD COLUMN labels text[] NOT NULL DEFAULT '{}';
+CREATE INDEX IF NOT EXISTS origin_channels_labels ON origin_channels USING GIN(labels);
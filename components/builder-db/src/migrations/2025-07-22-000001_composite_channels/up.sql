@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS origin_channel_members (
+    channel_id bigint NOT NULL REFERENCES origin_channels(id) ON DELETE CASCADE,
+    member_channel_id bigint NOT NULL REFERENCES origin_channels(id) ON DELETE CASCADE,
+    precedence integer NOT NULL,
+    created_at timestamptz DEFAULT now(),
+    PRIMARY KEY (channel_id, member_channel_id),
+    UNIQUE (channel_id, precedence),
+    CHECK (channel_id <> member_channel_id)
+);
@@ -0,0 +1,72 @@
+DROP VIEW origin_package_versions;
+CREATE OR REPLACE VIEW origin_package_versions AS
+    SELECT origin, name, visibility,
+    ident_array[3] as version,
+    COUNT(ident_array[4]) as release_count,
+    MAX(ident_array[4]) as latest,
+    ARRAY_AGG(DISTINCT target) as platforms,
+    regexp_matches(ident_array[3], '([\d\.]*\d+)(.+)?') as version_array
+    FROM origin_packages
+    WHERE hidden = false
+    GROUP BY ident_array[3], origin, name, visibility;
+
+DROP VIEW packages_with_channel_platform;
+CREATE OR REPLACE VIEW packages_with_channel_platform AS
+ SELECT op.id,
+    op.owner_id,
+    op.name,
+    op.ident,
+    op.ident_array,
+    op.checksum,
+    op.manifest,
+    op.config,
+    op.target,
+    op.deps,
+    op.tdeps,
+    op.build_deps,
+    op.build_tdeps,
+    op.exposes,
+    op.visibility,
+    op.created_at,
+    op.updated_at,
+    op.origin,
+    array_agg(oc.name) OVER w AS channels,
+    array_agg(op.target) OVER w AS platforms
+   FROM origin_packages op
+     JOIN origin_channel_packages ocp ON op.id = ocp.package_id
+     JOIN origin_channels oc ON oc.id = ocp.channel_id
+   WHERE op.hidden = false
+  WINDOW w AS (PARTITION BY op.origin, op.name, op.ident);
+
+DROP VIEW origin_packages_with_version_array;
+CREATE OR REPLACE VIEW origin_packages_with_version_array AS
+    SELECT
+        id,
+        owner_id,
+        name,
+        ident,
+        ident_array,
+        checksum,
+        manifest,
+        config,
+        target,
+        deps,
+        tdeps,
+        exposes,
+        created_at,
+        updated_at,
+        visibility,
+        origin,
+        build_deps,
+        build_tdeps,
+        regexp_matches(ident_array[3], '([\d\.]*\d+)(.+)?') as version_array,
+        package_type
+FROM origin_packages
+WHERE hidden = false;
+
+-- Postgres cannot drop a value from an enum, 'yank', 'deprecate' and 'restore' are left on
+-- package_channel_operation
+ALTER TABLE origin_packages DROP COLUMN status_reason;
+ALTER TABLE origin_packages DROP COLUMN status;
+
+DROP TYPE origin_package_status;
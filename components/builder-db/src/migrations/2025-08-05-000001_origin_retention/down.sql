@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS origin_retention_reports;
+DROP SEQUENCE IF EXISTS origin_retention_reports_id_seq;
+DROP TABLE IF EXISTS origin_retention_policies;
@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS origin_retention_policies (
+    origin text PRIMARY KEY REFERENCES origins(name) ON DELETE CASCADE,
+    keep_unstable_releases integer CHECK (keep_unstable_releases > 0),
+    unpromoted_max_age_days integer CHECK (unpromoted_max_age_days > 0),
+    created_at timestamp with time zone DEFAULT now(),
+    updated_at timestamp with time zone DEFAULT now()
+);
+
+CREATE SEQUENCE IF NOT EXISTS origin_retention_reports_id_seq;
+
+CREATE TABLE IF NOT EXISTS origin_retention_reports (
+    id bigint DEFAULT next_id_v1('origin_retention_reports_id_seq') PRIMARY KEY NOT NULL,
+    origin text NOT NULL REFERENCES origins(name) ON DELETE CASCADE,
+    deleted text[] NOT NULL DEFAULT '{}',
+    skipped text[] NOT NULL DEFAULT '{}',
+    created_at timestamp with time zone DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS origin_retention_reports_origin_created_at
+    ON origin_retention_reports (origin, created_at DESC);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS origin_package_downloads (
+    package_id bigint NOT NULL REFERENCES origin_packages(id) ON DELETE CASCADE,
+    day date NOT NULL DEFAULT current_date,
+    count integer NOT NULL DEFAULT 0,
+    PRIMARY KEY (package_id, day)
+);
+
+CREATE INDEX IF NOT EXISTS origin_package_downloads_day
+    ON origin_package_downloads (day);
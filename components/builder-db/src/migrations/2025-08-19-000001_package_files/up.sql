@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS origin_package_files (
+    package_id bigint PRIMARY KEY REFERENCES origin_packages(id) ON DELETE CASCADE,
+    files text[] NOT NULL DEFAULT '{}',
+    created_at timestamp with time zone DEFAULT now()
+);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS origin_package_binds;
+DROP TABLE IF EXISTS origin_package_exports;
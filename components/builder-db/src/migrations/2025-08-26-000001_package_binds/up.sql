@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS origin_package_exports (
+    package_id bigint NOT NULL REFERENCES origin_packages(id) ON DELETE CASCADE,
+    name text NOT NULL,
+    config_key text NOT NULL,
+    PRIMARY KEY (package_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS origin_package_exports_name_idx ON origin_package_exports (name);
+
+CREATE TABLE IF NOT EXISTS origin_package_binds (
+    package_id bigint NOT NULL REFERENCES origin_packages(id) ON DELETE CASCADE,
+    name text NOT NULL,
+    exports text[] NOT NULL DEFAULT '{}',
+    optional boolean NOT NULL DEFAULT false,
+    PRIMARY KEY (package_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS origin_package_binds_name_idx ON origin_package_binds (name);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS origin_package_labels;
+DROP TABLE IF EXISTS origin_package_settings_labels;
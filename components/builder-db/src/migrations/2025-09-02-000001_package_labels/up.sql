@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS origin_package_settings_labels (
+    origin text NOT NULL,
+    name text NOT NULL,
+    key text NOT NULL,
+    value text NOT NULL,
+    PRIMARY KEY (origin, name, key),
+    FOREIGN KEY (origin, name) REFERENCES origin_package_settings(origin, name) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS origin_package_settings_labels_key_value_idx
+    ON origin_package_settings_labels (key, value);
+
+CREATE TABLE IF NOT EXISTS origin_package_labels (
+    package_id bigint NOT NULL REFERENCES origin_packages(id) ON DELETE CASCADE,
+    key text NOT NULL,
+    value text NOT NULL,
+    PRIMARY KEY (package_id, key)
+);
+
+CREATE INDEX IF NOT EXISTS origin_package_labels_key_value_idx
+    ON origin_package_labels (key, value);
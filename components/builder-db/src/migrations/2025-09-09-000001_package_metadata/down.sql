@@ -0,0 +1 @@
+DROP TABLE IF EXISTS origin_package_metadata;
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS origin_package_metadata (
+    package_id bigint PRIMARY KEY REFERENCES origin_packages(id) ON DELETE CASCADE,
+    description text,
+    upstream_url text,
+    licenses text[] NOT NULL DEFAULT '{}',
+    maintainer text
+);
+
+CREATE INDEX IF NOT EXISTS origin_package_metadata_description_idx
+    ON origin_package_metadata USING gin (to_tsvector('simple', coalesce(description, '')));
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS advisory_ranges;
+DROP TABLE IF EXISTS advisories;
@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS advisories (
+    id text PRIMARY KEY,
+    summary text,
+    details text,
+    aliases text[] NOT NULL DEFAULT '{}',
+    severity text,
+    published timestamptz,
+    modified timestamptz,
+    created_at timestamptz DEFAULT now(),
+    updated_at timestamptz DEFAULT now()
+);
+
+-- One row per affected version range of a package, or per list of affected versions
+CREATE TABLE IF NOT EXISTS advisory_ranges (
+    advisory_id text NOT NULL REFERENCES advisories(id) ON DELETE CASCADE,
+    package text NOT NULL,
+    introduced text,
+    fixed text,
+    last_affected text,
+    versions text[] NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX IF NOT EXISTS advisory_ranges_advisory_id ON advisory_ranges(advisory_id);
+CREATE INDEX IF NOT EXISTS advisory_ranges_package ON advisory_ranges(package);
@@ -0,0 +1 @@
+ALTER TYPE origin_operation ADD VALUE IF NOT EXISTS 'package_delete';
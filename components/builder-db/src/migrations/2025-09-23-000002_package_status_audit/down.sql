@@ -0,0 +1 @@
+-- Postgres cannot drop a value from an enum, 'package_status_change' is left on origin_operation
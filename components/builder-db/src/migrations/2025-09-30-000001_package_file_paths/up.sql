@@ -0,0 +1,17 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE TABLE IF NOT EXISTS origin_package_file_paths (
+    package_id bigint NOT NULL REFERENCES origin_packages(id) ON DELETE CASCADE,
+    path text NOT NULL,
+    PRIMARY KEY (package_id, path)
+);
+
+-- File searches match anywhere in the path, a trigram index serves those
+CREATE INDEX IF NOT EXISTS origin_package_file_paths_path_trgm_idx
+    ON origin_package_file_paths USING gin (path gin_trgm_ops);
+
+INSERT INTO origin_package_file_paths (package_id, path)
+SELECT DISTINCT opf.package_id, f.path
+  FROM origin_package_files AS opf
+ CROSS JOIN LATERAL unnest(opf.files) AS f(path)
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,128 @@
+use chrono::NaiveDateTime;
+use diesel::{self,
+             pg::{upsert::excluded,
+                  PgConnection},
+             result::QueryResult,
+             Connection,
+             ExpressionMethods,
+             QueryDsl,
+             RunQueryDsl};
+
+use crate::{bldr_core::metrics::CounterMetric,
+            metrics::Counter,
+            schema::advisories::{advisories,
+                                 advisory_ranges}};
+
+// A vulnerability advisory imported from an OSV document
+#[derive(Clone, Debug, Serialize, Queryable)]
+pub struct Advisory {
+    pub id:         String,
+    pub summary:    Option<String>,
+    pub details:    Option<String>,
+    // Other ids of the same vulnerability, e.g. its CVE
+    pub aliases:    Vec<String>,
+    pub severity:   Option<String>,
+    pub published:  Option<NaiveDateTime>,
+    pub modified:   Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+// The versions of an `origin/name` package an advisory applies to. Versions from
+// `introduced` on are affected, up to `fixed` or up to and including `last_affected`.
+// Advisories listing affected versions one by one have them in `versions` instead.
+#[derive(Clone, Debug, Default, PartialEq, Queryable)]
+pub struct AdvisoryRange {
+    pub advisory_id:   String,
+    pub package:       String,
+    pub introduced:    Option<String>,
+    pub fixed:         Option<String>,
+    pub last_affected: Option<String>,
+    pub versions:      Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NewAdvisory {
+    pub id:        String,
+    pub summary:   Option<String>,
+    pub details:   Option<String>,
+    pub aliases:   Vec<String>,
+    pub severity:  Option<String>,
+    pub published: Option<NaiveDateTime>,
+    pub modified:  Option<NaiveDateTime>,
+    pub ranges:    Vec<AdvisoryRange>,
+}
+
+impl Advisory {
+    pub fn get_all(ids: &[String], conn: &mut PgConnection) -> QueryResult<Vec<Advisory>> {
+        Counter::DBCall.increment();
+
+        advisories::table.filter(advisories::id.eq_any(ids))
+                         .order(advisories::id.asc())
+                         .get_results(conn)
+    }
+
+    /// Creates or replaces an advisory along with its ranges.
+    pub fn upsert(advisory: &NewAdvisory, conn: &mut PgConnection) -> QueryResult<()> {
+        Counter::DBCall.increment();
+
+        let ranges = advisory.ranges
+                             .iter()
+                             .map(|r| {
+                                 (advisory_ranges::advisory_id.eq(&advisory.id),
+                                  advisory_ranges::package.eq(&r.package),
+                                  advisory_ranges::introduced.eq(&r.introduced),
+                                  advisory_ranges::fixed.eq(&r.fixed),
+                                  advisory_ranges::last_affected.eq(&r.last_affected),
+                                  advisory_ranges::versions.eq(&r.versions))
+                             })
+                             .collect::<Vec<_>>();
+
+        conn.transaction::<_, diesel::result::Error, _>(|txn_conn| {
+                diesel::insert_into(advisories::table)
+                    .values((advisories::id.eq(&advisory.id),
+                             advisories::summary.eq(&advisory.summary),
+                             advisories::details.eq(&advisory.details),
+                             advisories::aliases.eq(&advisory.aliases),
+                             advisories::severity.eq(&advisory.severity),
+                             advisories::published.eq(advisory.published),
+                             advisories::modified.eq(advisory.modified)))
+                    .on_conflict(advisories::id)
+                    .do_update()
+                    .set((advisories::summary.eq(excluded(advisories::summary)),
+                          advisories::details.eq(excluded(advisories::details)),
+                          advisories::aliases.eq(excluded(advisories::aliases)),
+                          advisories::severity.eq(excluded(advisories::severity)),
+                          advisories::published.eq(excluded(advisories::published)),
+                          advisories::modified.eq(excluded(advisories::modified)),
+                          advisories::updated_at.eq(diesel::dsl::now)))
+                    .execute(txn_conn)?;
+                diesel::delete(advisory_ranges::table
+                    .filter(advisory_ranges::advisory_id.eq(&advisory.id)))
+                    .execute(txn_conn)?;
+                if !ranges.is_empty() {
+                    diesel::insert_into(advisory_ranges::table).values(&ranges)
+                                                               .execute(txn_conn)?;
+                }
+                Ok(())
+            })
+    }
+
+    pub fn delete(id: &str, conn: &mut PgConnection) -> QueryResult<usize> {
+        Counter::DBCall.increment();
+
+        diesel::delete(advisories::table.find(id)).execute(conn)
+    }
+}
+
+impl AdvisoryRange {
+    /// Lists the ranges of every advisory about any of the `origin/name` packages.
+    pub fn for_packages(packages: &[String],
+                        conn: &mut PgConnection)
+                        -> QueryResult<Vec<AdvisoryRange>> {
+        Counter::DBCall.increment();
+
+        advisory_ranges::table.filter(advisory_ranges::package.eq_any(packages))
+                              .get_results(conn)
+    }
+}
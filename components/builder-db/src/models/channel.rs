@@ -7,11 +7,14 @@ use crate::{bldr_core::metrics::{CounterMetric,
             metrics::{Counter,
                       Histogram},
             models::package::{BuilderPackageIdent,
+                              BuilderPackageTarget,
+                              PackageStatus,
                               PackageVisibility,
                               PackageWithVersionArray},
             schema::{audit::{audit_package,
                              audit_package_group},
-                     channel::{origin_channel_packages,
+                     channel::{origin_channel_members,
+                               origin_channel_packages,
                                origin_channels},
                      member::origin_members,
                      origin::origins,
@@ -19,7 +22,8 @@ use crate::{bldr_core::metrics::{CounterMetric,
                                origin_packages_with_version_array}}};
 use chrono::NaiveDateTime;
 use diesel_derive_enum::DbEnum;
-use std::time::Instant;
+use std::{collections::HashSet,
+          time::Instant};
 
 use diesel::{self,
              dsl::{count,
@@ -29,7 +33,8 @@ use diesel::{self,
              pg::PgConnection,
              prelude::*,
              result::QueryResult,
-             sql_types::{Text,
+             sql_types::{BigInt,
+                         Text,
                          Timestamptz},
              ExpressionMethods,
              NullableExpressionMethods,
@@ -43,13 +48,16 @@ use diesel_full_text_search::{to_tsquery,
 #[derive(Debug, Serialize, Deserialize, Queryable)]
 pub struct Channel {
     #[serde(with = "db_id_format")]
-    pub id:         i64,
+    pub id:          i64,
     #[serde(with = "db_id_format")]
-    pub owner_id:   i64,
-    pub name:       String,
-    pub created_at: Option<NaiveDateTime>,
-    pub updated_at: Option<NaiveDateTime>,
-    pub origin:     String,
+    pub owner_id:    i64,
+    pub name:        String,
+    pub created_at:  Option<NaiveDateTime>,
+    pub updated_at:  Option<NaiveDateTime>,
+    pub origin:      String,
+    pub expires_at:  Option<NaiveDateTime>,
+    pub description: Option<String>,
+    pub labels:      Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -63,9 +71,19 @@ pub struct ChannelWithPromotion {
 #[table_name = "origin_channels"]
 pub struct CreateChannel<'a> {
     // This would be ChannelIdent, but Insertable requires implementing diesel::Expression
-    pub name:     &'a str,
-    pub owner_id: i64,
-    pub origin:   &'a str,
+    pub name:       &'a str,
+    pub owner_id:   i64,
+    pub origin:     &'a str,
+    // Channels with an expiry are removed by the channel reaper once it has passed
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+// Fields left as None are not changed, a description of Some(None) is cleared
+#[derive(AsChangeset, Debug)]
+#[table_name = "origin_channels"]
+pub struct UpdateChannel<'a> {
+    pub description: Option<Option<&'a str>>,
+    pub labels:      Option<Vec<String>>,
 }
 
 #[derive(Clone, Debug)]
@@ -91,6 +109,13 @@ pub struct ListAllChannelPackages<'a> {
     pub origin:     &'a str,
 }
 
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct ChannelPackageChecksum {
+    pub ident:    BuilderPackageIdent,
+    pub target:   BuilderPackageTarget,
+    pub checksum: String,
+}
+
 pub struct ListAllChannelPackagesForTarget<'a> {
     pub visibility: &'a Vec<PackageVisibility>,
     pub channel:    &'a ChannelIdent,
@@ -104,6 +129,7 @@ impl Channel {
 
     pub fn list(origin: &str,
                 include_sandbox_channels: bool,
+                label: Option<&str>,
                 conn: &mut PgConnection)
                 -> QueryResult<Vec<Channel>> {
         Counter::DBCall.increment();
@@ -113,6 +139,9 @@ impl Channel {
         if !include_sandbox_channels {
             query = query.filter(origin_channels::name.not_like("bldr-%"));
         }
+        if let Some(label) = label {
+            query = query.filter(origin_channels::labels.contains(vec![label.to_string()]));
+        }
         query.order(origin_channels::name.asc()).get_results(conn)
     }
 
@@ -145,6 +174,124 @@ impl Channel {
         .execute(conn)
     }
 
+    pub fn update(origin: &str,
+                  channel: &ChannelIdent,
+                  update: &UpdateChannel,
+                  conn: &mut PgConnection)
+                  -> QueryResult<Channel> {
+        Counter::DBCall.increment();
+        diesel::update(origin_channels::table.filter(origin_channels::origin.eq(origin))
+                                             .filter(origin_channels::name.eq(channel.as_str())))
+            .set((update, origin_channels::updated_at.eq(diesel::dsl::now)))
+            .get_result(conn)
+    }
+
+    pub fn rename(origin: &str,
+                  channel: &ChannelIdent,
+                  new_name: &ChannelIdent,
+                  conn: &mut PgConnection)
+                  -> QueryResult<Channel> {
+        Counter::DBCall.increment();
+        // The channel keeps its id, so the promotion timestamps in origin_channel_packages
+        // carry over to the new name untouched.
+        diesel::update(origin_channels::table.filter(origin_channels::origin.eq(origin))
+                                             .filter(origin_channels::name.eq(channel.as_str())))
+            .set(origin_channels::name.eq(new_name.as_str()))
+            .get_result(conn)
+    }
+
+    /// Creates a new channel holding the same packages as `source`, returning the new
+    /// channel and the ids of the packages copied into it.
+    pub fn copy(source: &Channel,
+                target: &CreateChannel,
+                conn: &mut PgConnection)
+                -> QueryResult<(Channel, Vec<i64>)> {
+        Counter::DBCall.increment();
+        conn.transaction::<_, diesel::result::Error, _>(|txn_conn| {
+                let channel = Channel::create(target, txn_conn)?;
+                let pkg_ids = diesel::insert_into(origin_channel_packages::table)
+                    .values(
+                        origin_channel_packages::table
+                            .filter(origin_channel_packages::channel_id.eq(source.id))
+                            .select((
+                                channel.id.into_sql::<BigInt>(),
+                                origin_channel_packages::package_id,
+                            )),
+                    )
+                    .into_columns((
+                        origin_channel_packages::channel_id,
+                        origin_channel_packages::package_id,
+                    ))
+                    .returning(origin_channel_packages::package_id)
+                    .get_results(txn_conn)?;
+                Ok((channel, pkg_ids))
+            })
+    }
+
+    /// Lists the channels a composite channel is made of, in the order they are searched.
+    /// Member channels are not expanded any further.
+    pub fn list_members(channel_id: i64, conn: &mut PgConnection) -> QueryResult<Vec<Channel>> {
+        Counter::DBCall.increment();
+        origin_channel_members::table.inner_join(origin_channels::table)
+                                     .filter(origin_channel_members::channel_id.eq(channel_id))
+                                     .order(origin_channel_members::precedence.asc())
+                                     .select(origin_channels::all_columns)
+                                     .get_results(conn)
+    }
+
+    /// Replaces the members of a composite channel; precedence follows the order of
+    /// `member_ids`. An empty list turns the channel back into a regular one.
+    pub fn set_members(channel_id: i64,
+                       member_ids: &[i64],
+                       conn: &mut PgConnection)
+                       -> QueryResult<usize> {
+        Counter::DBCall.increment();
+        conn.transaction::<_, diesel::result::Error, _>(|txn_conn| {
+                diesel::delete(
+                    origin_channel_members::table
+                        .filter(origin_channel_members::channel_id.eq(channel_id)),
+                )
+                .execute(txn_conn)?;
+
+                let insert: Vec<(_, _, _)> =
+                    member_ids.iter()
+                              .enumerate()
+                              .map(|(precedence, id)| {
+                                  (origin_channel_members::channel_id.eq(channel_id),
+                                   origin_channel_members::member_channel_id.eq(*id),
+                                   origin_channel_members::precedence.eq(precedence as i32))
+                              })
+                              .collect();
+                diesel::insert_into(origin_channel_members::table).values(insert)
+                                                                  .execute(txn_conn)
+            })
+    }
+
+    pub fn list_expired(conn: &mut PgConnection) -> QueryResult<Vec<Channel>> {
+        Counter::DBCall.increment();
+        origin_channels::table.filter(origin_channels::expires_at.lt(diesel::dsl::now))
+                              .order(origin_channels::expires_at.asc())
+                              .get_results(conn)
+    }
+
+    /// Demotes every package from an expired channel and deletes it, returning the ids
+    /// of the demoted packages. Both happen in one transaction so a channel is never
+    /// left half reaped. Fails with `NotFound` when the channel was already deleted,
+    /// e.g. by the reaper of another node.
+    pub fn reap(channel: &Channel, conn: &mut PgConnection) -> QueryResult<Vec<i64>> {
+        Counter::DBCall.increment();
+        conn.transaction::<_, diesel::result::Error, _>(|txn_conn| {
+                let pkg_ids = Channel::list_all_packages_by_channel_id(channel.id,
+                                                                       &PackageVisibility::all(),
+                                                                       txn_conn)?;
+                Channel::demote_packages(channel.id, &pkg_ids, txn_conn)?;
+                match diesel::delete(origin_channels::table.find(channel.id)).execute(txn_conn)? {
+                    0 => Err(diesel::result::Error::NotFound),
+                    _ => Ok(pkg_ids),
+                }
+            })
+    }
+
     pub fn delete_channel_package(package_id: i64, conn: &mut PgConnection) -> QueryResult<usize> {
         Counter::DBCall.increment();
         diesel::delete(
@@ -169,6 +316,9 @@ impl Channel {
             .filter(origin_channels::name.eq(req.channel.as_str()))
             .filter(origin_packages_with_version_array::target.eq(req.target))
             .filter(origin_packages_with_version_array::visibility.eq_any(req.visibility))
+            // Yanked releases only resolve when asked for by their fully qualified ident
+            .filter(origin_packages_with_version_array::status.ne(PackageStatus::Yanked)
+                .or(origin_packages_with_version_array::ident.eq(ident.to_string())))
             .order(sql::<Text>(
                 "string_to_array(version_array[1],'.')::\
                  numeric[] desc, version_array[2] desc, \
@@ -200,6 +350,7 @@ impl Channel {
             .filter(origin_channels::name.eq(&channel))
             .filter(origin_packages_with_version_array::target.eq(&target))
             .filter(origin_packages_with_version_array::visibility.eq_any(req.visibility))
+            .filter(origin_packages_with_version_array::status.ne(PackageStatus::Yanked))
             .distinct_on(origin_packages_with_version_array::name)
             .select((
                 origin_packages_with_version_array::name,
@@ -349,6 +500,32 @@ impl Channel {
         result
     }
 
+    pub fn list_all_packages_with_checksums(lacp: &ListAllChannelPackages,
+                                            conn: &mut PgConnection)
+                                            -> QueryResult<Vec<ChannelPackageChecksum>> {
+        Counter::DBCall.increment();
+        let start_time = Instant::now();
+
+        let result = origin_packages::table
+            .inner_join(
+                origin_channel_packages::table
+                    .inner_join(origin_channels::table.inner_join(origins::table)),
+            )
+            .filter(origin_packages::visibility.eq_any(lacp.visibility))
+            .filter(origins::name.eq(lacp.origin))
+            .filter(origin_channels::name.eq(lacp.channel.as_str()))
+            .select((origin_packages::ident, origin_packages::target, origin_packages::checksum))
+            .order((origin_packages::ident.asc(), origin_packages::target.asc()))
+            .get_results(conn);
+
+        let duration_millis = start_time.elapsed().as_millis();
+        trace!("DBCall channel::list_all_packages_with_checksums time: {} ms",
+               duration_millis);
+        Histogram::DbCallTime.set(duration_millis as f64);
+        Histogram::ChannelListAllPackagesCallTime.set(duration_millis as f64);
+        result
+    }
+
     pub fn list_all_packages_by_channel_id(channel_id: i64,
                                            visibility: &[PackageVisibility],
                                            conn: &mut PgConnection)
@@ -409,6 +586,29 @@ impl Channel {
         .execute(conn)
     }
 
+    /// Makes the channel contain exactly `package_ids`, returning the ids that were
+    /// promoted and the ids that were demoted to get there.
+    pub fn replace_packages(channel_id: i64,
+                            package_ids: &[i64],
+                            conn: &mut PgConnection)
+                            -> QueryResult<(Vec<i64>, Vec<i64>)> {
+        conn.transaction::<_, diesel::result::Error, _>(|txn_conn| {
+                let current: HashSet<i64> =
+                    Channel::list_all_packages_by_channel_id(channel_id,
+                                                             &PackageVisibility::all(),
+                                                             txn_conn)?.into_iter()
+                                                                       .collect();
+                let wanted: HashSet<i64> = package_ids.iter().cloned().collect();
+
+                let promoted: Vec<i64> = wanted.difference(&current).cloned().collect();
+                let demoted: Vec<i64> = current.difference(&wanted).cloned().collect();
+
+                Channel::promote_packages(channel_id, &promoted, txn_conn)?;
+                Channel::demote_packages(channel_id, &demoted, txn_conn)?;
+                Ok((promoted, demoted))
+            })
+    }
+
     //
     pub fn do_promote_or_demote_packages_cross_channels(ch_source: i64,
                                                         ch_target: i64,
@@ -445,6 +645,9 @@ pub enum PackageChannelTrigger {
 pub enum PackageChannelOperation {
     Promote,
     Demote,
+    Yank,
+    Deprecate,
+    Restore,
 }
 
 pub struct ListEvents {
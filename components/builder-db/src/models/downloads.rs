@@ -0,0 +1,119 @@
+use chrono::NaiveDate;
+use diesel::{self,
+             dsl::sum,
+             pg::PgConnection,
+             result::QueryResult,
+             ExpressionMethods,
+             QueryDsl,
+             RunQueryDsl};
+
+use crate::{bldr_core::metrics::CounterMetric,
+            metrics::Counter,
+            models::package::{BuilderPackageTarget,
+                              PackageVisibility},
+            schema::{downloads::origin_package_downloads,
+                     package::origin_packages}};
+
+// Downloads are counted per package (ident and target) and day rather than per request
+#[derive(Debug, Serialize, Queryable)]
+pub struct ReleaseDownloads {
+    pub ident:     String,
+    pub target:    String,
+    pub downloads: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct DailyDownloads {
+    pub day:       NaiveDate,
+    pub downloads: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct PackageDownloads {
+    pub name:      String,
+    pub downloads: Option<i64>,
+}
+
+pub struct ListPackageDownloads<'a> {
+    pub origin:     &'a str,
+    pub name:       &'a str,
+    pub target:     BuilderPackageTarget,
+    pub visibility: &'a [PackageVisibility],
+    pub since:      NaiveDate,
+}
+
+pub struct ListOriginDownloads<'a> {
+    pub origin:     &'a str,
+    pub visibility: &'a [PackageVisibility],
+    pub since:      NaiveDate,
+}
+
+pub struct Downloads;
+
+impl Downloads {
+    pub fn record(package_id: i64, conn: &mut PgConnection) -> QueryResult<usize> {
+        Counter::DBCall.increment();
+
+        diesel::insert_into(origin_package_downloads::table)
+            .values((origin_package_downloads::package_id.eq(package_id),
+                     origin_package_downloads::count.eq(1)))
+            .on_conflict((origin_package_downloads::package_id, origin_package_downloads::day))
+            .do_update()
+            .set(origin_package_downloads::count.eq(origin_package_downloads::count + 1))
+            .execute(conn)
+    }
+
+    pub fn by_release(req: &ListPackageDownloads,
+                      conn: &mut PgConnection)
+                      -> QueryResult<Vec<ReleaseDownloads>> {
+        Counter::DBCall.increment();
+
+        origin_package_downloads::table
+            .inner_join(origin_packages::table)
+            .filter(origin_packages::origin.eq(req.origin))
+            .filter(origin_packages::name.eq(req.name))
+            .filter(origin_packages::target.eq(req.target))
+            .filter(origin_packages::visibility.eq_any(req.visibility))
+            .filter(origin_package_downloads::day.ge(req.since))
+            .group_by((origin_packages::ident, origin_packages::target))
+            .select((origin_packages::ident,
+                     origin_packages::target,
+                     sum(origin_package_downloads::count)))
+            .order(sum(origin_package_downloads::count).desc())
+            .get_results(conn)
+    }
+
+    pub fn by_day(req: &ListPackageDownloads,
+                  conn: &mut PgConnection)
+                  -> QueryResult<Vec<DailyDownloads>> {
+        Counter::DBCall.increment();
+
+        origin_package_downloads::table
+            .inner_join(origin_packages::table)
+            .filter(origin_packages::origin.eq(req.origin))
+            .filter(origin_packages::name.eq(req.name))
+            .filter(origin_packages::target.eq(req.target))
+            .filter(origin_packages::visibility.eq_any(req.visibility))
+            .filter(origin_package_downloads::day.ge(req.since))
+            .group_by(origin_package_downloads::day)
+            .select((origin_package_downloads::day, sum(origin_package_downloads::count)))
+            .order(origin_package_downloads::day.asc())
+            .get_results(conn)
+    }
+
+    pub fn by_package(req: &ListOriginDownloads,
+                      conn: &mut PgConnection)
+                      -> QueryResult<Vec<PackageDownloads>> {
+        Counter::DBCall.increment();
+
+        origin_package_downloads::table
+            .inner_join(origin_packages::table)
+            .filter(origin_packages::origin.eq(req.origin))
+            .filter(origin_packages::visibility.eq_any(req.visibility))
+            .filter(origin_package_downloads::day.ge(req.since))
+            .group_by(origin_packages::name)
+            .select((origin_packages::name, sum(origin_package_downloads::count)))
+            .order(sum(origin_package_downloads::count).desc())
+            .get_results(conn)
+    }
+}
@@ -6,16 +6,22 @@
 mod migration_support;
 
 pub mod account;
+pub mod advisories;
 pub mod channel;
+pub mod downloads;
 pub mod integration;
 pub mod invitations;
 pub mod keys;
 pub mod license_keys;
 pub mod origin;
 pub mod package;
+pub mod package_binds;
+pub mod package_files;
+pub mod package_metadata;
 pub mod pagination;
 pub mod project_integration;
 pub mod projects;
+pub mod retention;
 pub mod secrets;
 pub mod settings;
 
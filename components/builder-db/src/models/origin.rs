@@ -161,6 +161,10 @@ pub enum OriginOperation {
     OriginCreate,
     OriginDelete,
     OwnerTransfer,
+    ChannelDelete,
+    ChannelRename,
+    PackageDelete,
+    PackageStatusChange,
 }
 
 #[derive(Debug, Serialize, Deserialize, Insertable)]
@@ -221,13 +225,15 @@ impl Origin {
                                                             .get_result(conn)?;
 
         OriginMember::add(req.name, req.owner_id, conn, OriginMemberRole::Owner)?;
-        Channel::create(&CreateChannel { name:     ChannelIdent::unstable().as_str(),
-                                         owner_id: req.owner_id,
-                                         origin:   req.name, },
+        Channel::create(&CreateChannel { name:       ChannelIdent::unstable().as_str(),
+                                         owner_id:   req.owner_id,
+                                         origin:     req.name,
+                                         expires_at: None, },
                         conn)?;
-        Channel::create(&CreateChannel { name:     ChannelIdent::stable().as_str(),
-                                         owner_id: req.owner_id,
-                                         origin:   req.name, },
+        Channel::create(&CreateChannel { name:       ChannelIdent::stable().as_str(),
+                                         owner_id:   req.owner_id,
+                                         origin:     req.name,
+                                         expires_at: None, },
                         conn)?;
 
         Ok(new_origin)
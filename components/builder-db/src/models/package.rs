@@ -20,6 +20,7 @@ use crate::{bio_core::{self,
             models::{channel::{Channel,
                                OriginChannelPackage,
                                OriginChannelPromote},
+                     package_metadata::PackageMetadata,
                      settings::OriginPackageSettings},
             schema::{channel::{origin_channel_packages,
                                origin_channels},
@@ -29,7 +30,9 @@ use crate::{bio_core::{self,
                                origin_packages,
                                origin_packages_with_version_array,
                                packages_with_channel_platform},
-                     settings::origin_package_settings}};
+                     settings::{origin_package_labels,
+                                origin_package_settings,
+                                origin_package_settings_labels}}};
 use chrono::NaiveDateTime;
 use diesel::{self,
              deserialize::{self,
@@ -75,26 +78,28 @@ use diesel_derive_enum::DbEnum;
 #[table_name = "origin_packages"]
 pub struct Package {
     #[serde(with = "db_id_format")]
-    pub id:           i64,
+    pub id:            i64,
     #[serde(with = "db_id_format")]
-    pub owner_id:     i64,
-    pub name:         String,
-    pub ident:        BuilderPackageIdent,
-    pub ident_array:  Vec<String>,
-    pub checksum:     String,
-    pub manifest:     String,
-    pub config:       String,
-    pub target:       BuilderPackageTarget,
-    pub deps:         Vec<BuilderPackageIdent>,
-    pub tdeps:        Vec<BuilderPackageIdent>,
-    pub build_deps:   Vec<BuilderPackageIdent>,
-    pub build_tdeps:  Vec<BuilderPackageIdent>,
-    pub exposes:      Vec<i32>,
-    pub visibility:   PackageVisibility,
-    pub created_at:   Option<NaiveDateTime>,
-    pub updated_at:   Option<NaiveDateTime>,
-    pub origin:       String,
-    pub package_type: BuilderPackageType,
+    pub owner_id:      i64,
+    pub name:          String,
+    pub ident:         BuilderPackageIdent,
+    pub ident_array:   Vec<String>,
+    pub checksum:      String,
+    pub manifest:      String,
+    pub config:        String,
+    pub target:        BuilderPackageTarget,
+    pub deps:          Vec<BuilderPackageIdent>,
+    pub tdeps:         Vec<BuilderPackageIdent>,
+    pub build_deps:    Vec<BuilderPackageIdent>,
+    pub build_tdeps:   Vec<BuilderPackageIdent>,
+    pub exposes:       Vec<i32>,
+    pub visibility:    PackageVisibility,
+    pub created_at:    Option<NaiveDateTime>,
+    pub updated_at:    Option<NaiveDateTime>,
+    pub origin:        String,
+    pub package_type:  BuilderPackageType,
+    pub status:        PackageStatus,
+    pub status_reason: Option<String>,
 }
 
 #[derive(Debug,
@@ -128,6 +133,8 @@ pub struct PackageWithVersionArray {
     pub build_tdeps:   Vec<BuilderPackageIdent>,
     pub version_array: Vec<Option<String>>,
     pub package_type:  BuilderPackageType,
+    pub status:        PackageStatus,
+    pub status_reason: Option<String>,
 }
 
 #[derive(Debug,
@@ -160,6 +167,7 @@ pub struct PackageWithChannelPlatform {
     pub created_at:  Option<NaiveDateTime>,
     pub updated_at:  Option<NaiveDateTime>,
     pub origin:      String,
+    pub status:      PackageStatus,
     pub channels:    Vec<String>,
     pub platforms:   Vec<String>,
 }
@@ -194,6 +202,12 @@ pub struct PackageIdentWithChannelPlatform {
     pub release:   Option<String>,
     pub channels:  Vec<String>,
     pub platforms: Vec<String>,
+    // Unknown for idents that weren't read from a release
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status:    Option<PackageStatus>,
+    // Only set on release listings
+    #[serde(flatten, default)]
+    pub metadata:  Option<PackageMetadata>,
 }
 
 /// We literally never want to select `ident_vector`
@@ -217,7 +231,9 @@ type AllColumns = (origin_packages::id,
                    origin_packages::created_at,
                    origin_packages::updated_at,
                    origin_packages::origin,
-                   origin_packages::package_type);
+                   origin_packages::package_type,
+                   origin_packages::status,
+                   origin_packages::status_reason);
 
 pub const ALL_COLUMNS: AllColumns = (origin_packages::id,
                                      origin_packages::owner_id,
@@ -237,7 +253,9 @@ pub const ALL_COLUMNS: AllColumns = (origin_packages::id,
                                      origin_packages::created_at,
                                      origin_packages::updated_at,
                                      origin_packages::origin,
-                                     origin_packages::package_type);
+                                     origin_packages::package_type,
+                                     origin_packages::status,
+                                     origin_packages::status_reason);
 
 type All = diesel::dsl::Select<origin_packages::table, AllColumns>;
 
@@ -260,7 +278,9 @@ type AllColumnsWithVersion = (origin_packages_with_version_array::id,
                               origin_packages_with_version_array::build_deps,
                               origin_packages_with_version_array::build_tdeps,
                               origin_packages_with_version_array::version_array,
-                              origin_packages_with_version_array::package_type);
+                              origin_packages_with_version_array::package_type,
+                              origin_packages_with_version_array::status,
+                              origin_packages_with_version_array::status_reason);
 
 pub const ALL_COLUMNS_WITH_VERSION: AllColumnsWithVersion =
     (origin_packages_with_version_array::id,
@@ -282,7 +302,9 @@ pub const ALL_COLUMNS_WITH_VERSION: AllColumnsWithVersion =
      origin_packages_with_version_array::build_deps,
      origin_packages_with_version_array::build_tdeps,
      origin_packages_with_version_array::version_array,
-     origin_packages_with_version_array::package_type);
+     origin_packages_with_version_array::package_type,
+     origin_packages_with_version_array::status,
+     origin_packages_with_version_array::status_reason);
 
 type AllWithVersion =
     diesel::dsl::Select<origin_packages_with_version_array::table, AllColumnsWithVersion>;
@@ -330,6 +352,14 @@ pub struct GetPackageGroup {
     pub visibility: Vec<PackageVisibility>,
 }
 
+#[derive(Debug)]
+pub struct UpdatePackageStatus<'a> {
+    pub ident:  BuilderPackageIdent,
+    pub target: BuilderPackageTarget,
+    pub status: PackageStatus,
+    pub reason: Option<&'a str>,
+}
+
 #[derive(Debug)]
 pub struct DeletePackage {
     pub ident:  BuilderPackageIdent,
@@ -347,6 +377,8 @@ pub struct ListPackages {
     pub visibility: Vec<PackageVisibility>,
     pub page:       i64,
     pub limit:      i64,
+    // Key/value labels the releases have to carry, all of them
+    pub labels:     Vec<(String, String)>,
 }
 
 pub struct SearchPackages {
@@ -362,7 +394,8 @@ pub struct OriginPackageVersions {
     pub version:       String,
     #[serde(with = "db_id_format")]
     pub release_count: i64,
-    pub latest:        String,
+    // None when every release of the version is yanked
+    pub latest:        Option<String>,
     pub platforms:     Vec<String>,
     pub visibility:    PackageVisibility,
 }
@@ -411,12 +444,73 @@ impl PackageVisibility {
     pub fn private() -> Vec<Self> { vec![PackageVisibility::Private, PackageVisibility::Hidden] }
 }
 
+// Yanked packages are never resolved as the latest release, but can still be fetched by
+// their fully qualified ident. Deprecated packages are only flagged.
+#[derive(DbEnum, Debug, Eq, Hash, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[ExistingTypePath = "crate::schema::sql_types::OriginPackageStatus"]
+#[DbValueStyle = "snake_case"]
+#[serde(rename_all = "snake_case")]
+pub enum PackageStatus {
+    Active,
+    Deprecated,
+    Yanked,
+}
+
+impl FromStr for PackageStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<PackageStatus, ()> {
+        match s {
+            "active" => Ok(PackageStatus::Active),
+            "deprecated" => Ok(PackageStatus::Deprecated),
+            "yanked" => Ok(PackageStatus::Yanked),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for PackageStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match *self {
+            PackageStatus::Active => "active",
+            PackageStatus::Deprecated => "deprecated",
+            PackageStatus::Yanked => "yanked",
+        };
+        write!(f, "{}", value)
+    }
+}
+
 impl PackageWithVersionArray {
     pub fn all() -> AllWithVersion {
         origin_packages_with_version_array::table.select(ALL_COLUMNS_WITH_VERSION)
     }
 }
 
+// Narrows a boxed package query down to the releases carrying a label, either their own or
+// one of their origin package the release doesn't override
+macro_rules! filter_by_label {
+    ($query:expr, $table:ident, $origin:expr, $key:expr, $value:expr) => {{
+        let release_labeled =
+            origin_package_labels::table.filter(origin_package_labels::key.eq($key.clone()))
+                                        .filter(origin_package_labels::value.eq($value.clone()))
+                                        .select(origin_package_labels::package_id);
+        let release_overridden =
+            origin_package_labels::table.filter(origin_package_labels::key.eq($key.clone()))
+                                        .select(origin_package_labels::package_id);
+        let package_labeled =
+            origin_package_settings_labels::table
+                .filter(origin_package_settings_labels::origin.eq($origin.clone()))
+                .filter(origin_package_settings_labels::key.eq($key.clone()))
+                .filter(origin_package_settings_labels::value.eq($value.clone()))
+                .select(origin_package_settings_labels::name);
+        let own_label = $table::id.eq_any(release_labeled);
+        let package_label =
+            $table::name.eq_any(package_labeled)
+                        .and(diesel::dsl::not($table::id.eq_any(release_overridden)));
+        $query.filter(own_label.or(package_label))
+    }};
+}
+
 impl Package {
     pub fn get_without_target(ident: BuilderPackageIdent,
                               visibility: Vec<PackageVisibility>,
@@ -513,6 +607,9 @@ impl Package {
             .filter(origin_packages_with_version_array::ident_array.contains(req.ident.parts()))
             .filter(origin_packages_with_version_array::target.eq(req.target))
             .filter(origin_packages_with_version_array::visibility.eq_any(req.visibility))
+            // Yanked releases only resolve when asked for by their fully qualified ident
+            .filter(origin_packages_with_version_array::status.ne(PackageStatus::Yanked)
+                .or(origin_packages_with_version_array::ident.eq(req.ident.to_string())))
             .order(sql::<Text>(
                 "string_to_array(version_array[1],'.')::\
                  numeric[] desc, version_array[2] desc, \
@@ -533,6 +630,7 @@ impl Package {
         Counter::DBCall.increment();
         let start_time = Instant::now();
         let result = origin_packages_with_version_array::table
+            .filter(origin_packages_with_version_array::status.ne(PackageStatus::Yanked))
             .distinct_on((
                 origin_packages_with_version_array::origin,
                 origin_packages_with_version_array::name,
@@ -590,6 +688,20 @@ impl Package {
         Ok(pkg)
     }
 
+    pub fn update_status(req: UpdatePackageStatus, conn: &mut PgConnection) -> QueryResult<Package> {
+        Counter::DBCall.increment();
+        diesel::update(
+            origin_packages::table
+                .filter(origin_packages::ident.eq(req.ident))
+                .filter(origin_packages::target.eq(req.target))
+                .filter(origin_packages::hidden.eq(false)),
+        )
+        .set((origin_packages::status.eq(req.status),
+              origin_packages::status_reason.eq(req.reason)))
+        .returning(ALL_COLUMNS)
+        .get_result(conn)
+    }
+
     pub fn update_visibility(vis: PackageVisibility,
                              idt: BuilderPackageIdent,
                              conn: &mut PgConnection)
@@ -631,6 +743,13 @@ impl Package {
         if !pl.ident.name.is_empty() {
             query = query.filter(packages_with_channel_platform::name.eq(name_str))
         };
+        for (key, value) in pl.labels.iter() {
+            query = filter_by_label!(query,
+                                     packages_with_channel_platform,
+                                     pl.ident.origin,
+                                     key,
+                                     value);
+        }
 
         let mut pkgs = if pl.limit < 0 {
             let query =
@@ -700,6 +819,9 @@ impl Package {
         if !name_str.is_empty() {
             count_query = count_query.filter(origin_packages::name.eq(name_str.clone()));
         }
+        for (key, value) in pl.labels.iter() {
+            count_query = filter_by_label!(count_query, origin_packages, origin_str, key, value);
+        }
         let total_count: i64 =
             count_query.filter(origin_packages::ident_array.contains(parts.clone()))
                        .filter(origin_packages::visibility.eq_any(visibility.clone()))
@@ -722,6 +844,9 @@ impl Package {
         if !pl.ident.name.is_empty() {
             page_query = page_query.filter(origin_packages::name.eq(pl.ident.name.clone()));
         }
+        for (key, value) in pl.labels.iter() {
+            page_query = filter_by_label!(page_query, origin_packages, origin_str, key, value);
+        }
 
         let limit_i64 = limit;
         let offset_i64 = (page.saturating_sub(1)) * limit;
@@ -817,6 +942,9 @@ impl Package {
                 origin_channel_packages::created_at,
                 origin_channel_packages::updated_at,
                 origin_channels::origin,
+                origin_channels::expires_at,
+                origin_channels::description,
+                origin_channels::labels,
             ))
             .filter(origin_packages::ident.eq(ident))
             .filter(origin_packages::target.eq(target.to_string()))
@@ -874,73 +1002,6 @@ impl Package {
         result
     }
 
-    pub fn search(sp: &SearchPackages,
-                  conn: &mut PgConnection)
-                  -> QueryResult<(Vec<BuilderPackageIdent>, i64)> {
-        Counter::DBCall.increment();
-        let start_time = Instant::now();
-
-        let mut count_query = origin_packages::table.into_boxed();
-        count_query = count_query
-        .filter(to_tsquery(format!("{}:*", sp.query)).matches(origin_packages::ident_vector))
-        .filter(origin_packages::hidden.eq(false));
-
-        if let Some(session_id) = sp.account_id {
-            let owned_origins =
-                origin_members::table.select(origin_members::origin)
-                                     .filter(origin_members::account_id.eq(session_id));
-
-            count_query = count_query.filter(
-            origin_packages::visibility
-                .eq_any(PackageVisibility::private())
-                .and(origin_packages::origin.eq_any(owned_origins))
-                .or(origin_packages::visibility.eq(PackageVisibility::Public)),
-        );
-        } else {
-            count_query =
-                count_query.filter(origin_packages::visibility.eq(PackageVisibility::Public));
-        }
-
-        let total_count: i64 = count_query.select(count_star()).first(conn)?;
-
-        let mut page_query = origin_packages::table.into_boxed();
-        page_query = page_query
-        .filter(to_tsquery(format!("{}:*", sp.query)).matches(origin_packages::ident_vector))
-        .filter(origin_packages::hidden.eq(false));
-
-        if let Some(session_id) = sp.account_id {
-            let owned_origins =
-                origin_members::table.select(origin_members::origin)
-                                     .filter(origin_members::account_id.eq(session_id));
-
-            page_query = page_query.filter(
-            origin_packages::visibility
-                .eq_any(PackageVisibility::private())
-                .and(origin_packages::origin.eq_any(owned_origins))
-                .or(origin_packages::visibility.eq(PackageVisibility::Public)),
-        );
-        } else {
-            page_query =
-                page_query.filter(origin_packages::visibility.eq(PackageVisibility::Public));
-        }
-
-        let limit = sp.limit;
-        let offset = (sp.page.saturating_sub(1)) * sp.limit;
-
-        let packages: Vec<BuilderPackageIdent> = page_query.select(origin_packages::ident)
-                                                           .order(origin_packages::ident.asc())
-                                                           .limit(limit)
-                                                           .offset(offset)
-                                                           .load(conn)?;
-
-        let duration_millis = start_time.elapsed().as_millis();
-        trace!("DBCall package::search time: {} ms", duration_millis);
-        Histogram::DbCallTime.set(duration_millis as f64);
-        Histogram::PackageSearchCallTime.set(duration_millis as f64);
-
-        Ok((packages, total_count))
-    }
-
     // This is me giving up on fighting the typechecker and just duplicating a bunch of code
     pub fn search_distinct(sp: &SearchPackages,
                            conn: &mut PgConnection)
@@ -1038,9 +1099,7 @@ impl Package {
         // TODO: This is a temporary workaround until we plumb in a better solution for
         // determining whether a package is a service from the DB instead of needing
         // to crack the archive file to look for a SVC_USER file
-        self.manifest.contains("pkg_exposes")
-        || self.manifest.contains("pkg_binds")
-        || self.manifest.contains("pkg_exports")
+        SERVICE_MANIFEST_KEYS.iter().any(|key| self.manifest.contains(key))
     }
 }
 
@@ -1049,12 +1108,13 @@ impl PackageWithChannelPlatform {
         // TODO: This is a temporary workaround until we plumb in a better solution for
         // determining whether a package is a service from the DB instead of needing
         // to crack the archive file to look for a SVC_USER file
-        self.manifest.contains("pkg_exposes")
-        || self.manifest.contains("pkg_binds")
-        || self.manifest.contains("pkg_exports")
+        SERVICE_MANIFEST_KEYS.iter().any(|key| self.manifest.contains(key))
     }
 }
 
+/// The plan settings whose presence in the manifest makes a package a service.
+pub const SERVICE_MANIFEST_KEYS: [&str; 3] = ["pkg_exposes", "pkg_binds", "pkg_exports"];
+
 fn searchable_ident(ident: &BuilderPackageIdent) -> Vec<String> {
     // https://github.com/rust-lang/rust-clippy/issues/3071U
     #[allow(clippy::redundant_closure)]
@@ -1351,25 +1411,27 @@ impl From<Package> for OriginPackage {
 
 impl From<PackageWithVersionArray> for Package {
     fn from(value: PackageWithVersionArray) -> Package {
-        Package { id:           value.id,
-                  owner_id:     value.owner_id,
-                  name:         value.name.clone(),
-                  ident:        value.ident.clone(),
-                  ident_array:  value.ident_array.clone(),
-                  checksum:     value.checksum.clone(),
-                  manifest:     value.manifest.clone(),
-                  config:       value.config.clone(),
-                  target:       value.target,
-                  deps:         value.deps.clone(),
-                  tdeps:        value.tdeps.clone(),
-                  build_deps:   value.build_deps.clone(),
-                  build_tdeps:  value.build_tdeps.clone(),
-                  exposes:      value.exposes.clone(),
-                  visibility:   value.visibility,
-                  created_at:   value.created_at,
-                  updated_at:   value.updated_at,
-                  origin:       value.origin,
-                  package_type: value.package_type, }
+        Package { id:            value.id,
+                  owner_id:      value.owner_id,
+                  name:          value.name.clone(),
+                  ident:         value.ident.clone(),
+                  ident_array:   value.ident_array.clone(),
+                  checksum:      value.checksum.clone(),
+                  manifest:      value.manifest.clone(),
+                  config:        value.config.clone(),
+                  target:        value.target,
+                  deps:          value.deps.clone(),
+                  tdeps:         value.tdeps.clone(),
+                  build_deps:    value.build_deps.clone(),
+                  build_tdeps:   value.build_tdeps.clone(),
+                  exposes:       value.exposes.clone(),
+                  visibility:    value.visibility,
+                  created_at:    value.created_at,
+                  updated_at:    value.updated_at,
+                  origin:        value.origin,
+                  package_type:  value.package_type,
+                  status:        value.status,
+                  status_reason: value.status_reason, }
     }
 }
 
@@ -1395,7 +1457,9 @@ impl From<PackageWithChannelPlatform> for PackageIdentWithChannelPlatform {
                                           version: value.ident.version.clone(),
                                           release: value.ident.release.clone(),
                                           channels: value.channels,
-                                          platforms }
+                                          platforms,
+                                          status: Some(value.status),
+                                          metadata: None }
     }
 }
 
@@ -1406,6 +1470,8 @@ impl From<BuilderPackageIdent> for PackageIdentWithChannelPlatform {
                                           version:   value.version.clone(),
                                           release:   value.release.clone(),
                                           channels:  Vec::new(),
-                                          platforms: Vec::new(), }
+                                          platforms: Vec::new(),
+                                          status:    None,
+                                          metadata:  None, }
     }
 }
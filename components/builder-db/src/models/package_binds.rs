@@ -0,0 +1,108 @@
+use diesel::{self,
+             pg::PgConnection,
+             result::QueryResult,
+             Connection,
+             ExpressionMethods,
+             QueryDsl,
+             RunQueryDsl};
+
+use crate::{bldr_core::metrics::CounterMetric,
+            metrics::Counter,
+            schema::package_binds::{origin_package_binds,
+                                    origin_package_exports}};
+
+// The binds a package exports and the ones it consumes, as declared in its plan
+#[derive(Debug, Serialize, Queryable)]
+pub struct PackageExport {
+    #[serde(skip)]
+    pub package_id: i64,
+    pub name:       String,
+    // The configuration key the export is read from
+    pub config_key: String,
+}
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct PackageBind {
+    #[serde(skip)]
+    pub package_id: i64,
+    pub name:       String,
+    // The exports the bound service has to provide
+    pub exports:    Vec<String>,
+    pub optional:   bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NewPackageExport {
+    pub name:       String,
+    pub config_key: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NewPackageBind {
+    pub name:     String,
+    pub exports:  Vec<String>,
+    pub optional: bool,
+}
+
+pub struct PackageBinds;
+
+impl PackageBinds {
+    pub fn exports(package_id: i64, conn: &mut PgConnection) -> QueryResult<Vec<PackageExport>> {
+        Counter::DBCall.increment();
+
+        origin_package_exports::table.filter(origin_package_exports::package_id.eq(package_id))
+                                     .order(origin_package_exports::name.asc())
+                                     .get_results(conn)
+    }
+
+    pub fn binds(package_id: i64, conn: &mut PgConnection) -> QueryResult<Vec<PackageBind>> {
+        Counter::DBCall.increment();
+
+        origin_package_binds::table.filter(origin_package_binds::package_id.eq(package_id))
+                                   .order(origin_package_binds::name.asc())
+                                   .get_results(conn)
+    }
+
+    /// Replaces the exports and binds recorded for a package.
+    pub fn set(package_id: i64,
+               exports: &[NewPackageExport],
+               binds: &[NewPackageBind],
+               conn: &mut PgConnection)
+               -> QueryResult<()> {
+        Counter::DBCall.increment();
+
+        let exports = exports.iter()
+                             .map(|e| {
+                                 (origin_package_exports::package_id.eq(package_id),
+                                  origin_package_exports::name.eq(&e.name),
+                                  origin_package_exports::config_key.eq(&e.config_key))
+                             })
+                             .collect::<Vec<_>>();
+        let binds = binds.iter()
+                         .map(|b| {
+                             (origin_package_binds::package_id.eq(package_id),
+                              origin_package_binds::name.eq(&b.name),
+                              origin_package_binds::exports.eq(&b.exports),
+                              origin_package_binds::optional.eq(b.optional))
+                         })
+                         .collect::<Vec<_>>();
+
+        conn.transaction::<_, diesel::result::Error, _>(|txn_conn| {
+                diesel::delete(origin_package_exports::table
+                    .filter(origin_package_exports::package_id.eq(package_id)))
+                    .execute(txn_conn)?;
+                diesel::delete(origin_package_binds::table
+                    .filter(origin_package_binds::package_id.eq(package_id)))
+                    .execute(txn_conn)?;
+                if !exports.is_empty() {
+                    diesel::insert_into(origin_package_exports::table).values(&exports)
+                                                                      .execute(txn_conn)?;
+                }
+                if !binds.is_empty() {
+                    diesel::insert_into(origin_package_binds::table).values(&binds)
+                                                                    .execute(txn_conn)?;
+                }
+                Ok(())
+            })
+    }
+}
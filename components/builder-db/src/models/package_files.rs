@@ -0,0 +1,63 @@
+use chrono::NaiveDateTime;
+use diesel::{self,
+             pg::{upsert::excluded,
+                  PgConnection},
+             result::QueryResult,
+             Connection,
+             ExpressionMethods,
+             QueryDsl,
+             RunQueryDsl};
+
+use crate::{bldr_core::metrics::CounterMetric,
+            metrics::Counter,
+            schema::package_files::{origin_package_file_paths,
+                                    origin_package_files}};
+
+// Paths of the files shipped in a package, relative to its install directory
+#[derive(Debug, Serialize, Queryable)]
+pub struct PackageFiles {
+    #[serde(skip)]
+    pub package_id: i64,
+    pub files:      Vec<String>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+impl PackageFiles {
+    pub fn get(package_id: i64, conn: &mut PgConnection) -> QueryResult<PackageFiles> {
+        Counter::DBCall.increment();
+
+        origin_package_files::table.find(package_id)
+                                   .get_result(conn)
+    }
+
+    /// Records the files of a package, both as a listing and one row per path for searching.
+    pub fn set(package_id: i64, files: &[String], conn: &mut PgConnection) -> QueryResult<usize> {
+        Counter::DBCall.increment();
+
+        let paths = files.iter()
+                         .map(|path| {
+                             (origin_package_file_paths::package_id.eq(package_id),
+                              origin_package_file_paths::path.eq(path))
+                         })
+                         .collect::<Vec<_>>();
+
+        conn.transaction::<_, diesel::result::Error, _>(|txn_conn| {
+                diesel::delete(origin_package_file_paths::table
+                    .filter(origin_package_file_paths::package_id.eq(package_id)))
+                    .execute(txn_conn)?;
+                if !paths.is_empty() {
+                    diesel::insert_into(origin_package_file_paths::table)
+                        .values(&paths)
+                        .on_conflict_do_nothing()
+                        .execute(txn_conn)?;
+                }
+                diesel::insert_into(origin_package_files::table)
+                    .values((origin_package_files::package_id.eq(package_id),
+                             origin_package_files::files.eq(files)))
+                    .on_conflict(origin_package_files::package_id)
+                    .do_update()
+                    .set(origin_package_files::files.eq(excluded(origin_package_files::files)))
+                    .execute(txn_conn)
+            })
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use diesel::{self,
+             pg::{upsert::excluded,
+                  PgConnection},
+             result::QueryResult,
+             ExpressionMethods,
+             QueryDsl,
+             RunQueryDsl};
+
+use crate::{bio_core::{self,
+                       package::{FromArchive,
+                                 PackageArchive}},
+            bldr_core::metrics::CounterMetric,
+            metrics::Counter,
+            schema::package_metadata::origin_package_metadata};
+
+// What a hart says about itself in its MANIFEST, besides its ident and dependencies
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Queryable)]
+pub struct PackageMetadata {
+    #[serde(skip)]
+    pub package_id:   i64,
+    pub description:  Option<String>,
+    pub upstream_url: Option<String>,
+    pub licenses:     Vec<String>,
+    pub maintainer:   Option<String>,
+}
+
+impl PackageMetadata {
+    /// Reads the plan metadata rendered at the top of a package MANIFEST, e.g.
+    ///
+    /// ```text
+    /// # core / redis
+    /// Persistent key-value database, with built-in net interface
+    ///
+    /// * __Maintainer__: The Biome Maintainers <humans@biome.sh>
+    /// * __Upstream URL__: [http://redis.io/](http://redis.io/)
+    /// * __License__: BSD-3-Clause
+    /// ```
+    pub fn from_manifest(package_id: i64, manifest: &str) -> PackageMetadata {
+        let mut metadata = PackageMetadata { package_id,
+                                             ..Default::default() };
+        let mut description = Vec::new();
+        let mut in_fields = false;
+
+        // The first line is the `# origin / name` title
+        for line in manifest.lines().skip(1) {
+            let line = line.trim();
+            if let Some(field) = line.strip_prefix("* __") {
+                in_fields = true;
+                let (name, value) = match field.split_once("__:") {
+                    Some((name, value)) => (name, value.trim()),
+                    None => continue,
+                };
+                match name {
+                    "Maintainer" if !value.is_empty() => {
+                        metadata.maintainer = Some(value.to_string())
+                    }
+                    "Upstream URL" => metadata.upstream_url = manifest_url(value),
+                    "License" => {
+                        metadata.licenses = value.split_whitespace().map(str::to_string).collect()
+                    }
+                    _ => (),
+                }
+            } else if in_fields && !line.is_empty() {
+                // The plan details that follow the fields aren't metadata
+                break;
+            } else if !in_fields {
+                description.push(line);
+            }
+        }
+
+        let description = description.join("\n").trim().to_string();
+        if !description.is_empty() {
+            metadata.description = Some(description);
+        }
+        metadata
+    }
+
+    pub fn get(package_id: i64, conn: &mut PgConnection) -> QueryResult<PackageMetadata> {
+        Counter::DBCall.increment();
+
+        origin_package_metadata::table.find(package_id)
+                                      .get_result(conn)
+    }
+
+    pub fn for_packages(package_ids: &[i64],
+                        conn: &mut PgConnection)
+                        -> QueryResult<HashMap<i64, PackageMetadata>> {
+        Counter::DBCall.increment();
+
+        let metadata: Vec<PackageMetadata> =
+            origin_package_metadata::table
+                .filter(origin_package_metadata::package_id.eq_any(package_ids))
+                .get_results(conn)?;
+        Ok(metadata.into_iter().map(|m| (m.package_id, m)).collect())
+    }
+
+    pub fn set(&self, conn: &mut PgConnection) -> QueryResult<usize> {
+        Counter::DBCall.increment();
+
+        diesel::insert_into(origin_package_metadata::table)
+            .values((origin_package_metadata::package_id.eq(self.package_id),
+                     origin_package_metadata::description.eq(&self.description),
+                     origin_package_metadata::upstream_url.eq(&self.upstream_url),
+                     origin_package_metadata::licenses.eq(&self.licenses),
+                     origin_package_metadata::maintainer.eq(&self.maintainer)))
+            .on_conflict(origin_package_metadata::package_id)
+            .do_update()
+            .set((origin_package_metadata::description
+                    .eq(excluded(origin_package_metadata::description)),
+                  origin_package_metadata::upstream_url
+                    .eq(excluded(origin_package_metadata::upstream_url)),
+                  origin_package_metadata::licenses.eq(excluded(origin_package_metadata::licenses)),
+                  origin_package_metadata::maintainer
+                    .eq(excluded(origin_package_metadata::maintainer))))
+            .execute(conn)
+    }
+}
+
+impl FromArchive for PackageMetadata {
+    type Error = bio_core::Error;
+
+    // The package id is only known once the release is stored
+    fn from_archive(archive: &mut PackageArchive) -> bio_core::Result<Self> {
+        Ok(PackageMetadata::from_manifest(0, archive.manifest()?))
+    }
+}
+
+// The upstream URL is rendered as a markdown link, or as a sentence when the plan has none
+fn manifest_url(value: &str) -> Option<String> {
+    let url = match value.strip_prefix('[').and_then(|v| v.split_once("](")) {
+        Some((url, _)) => url,
+        None => value,
+    };
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Some(url.to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_from_manifest() {
+        let manifest = "# core / redis\nPersistent key-value database, with built-in net \
+                        interface\n\n* __Maintainer__: The Biome Maintainers \
+                        <humans@biome.sh>\n* __Version__: 4.0.14\n* __Upstream URL__: \
+                        [http://redis.io/](http://redis.io/)\n* __License__: BSD-3-Clause \
+                        MIT \n* __Source__: [redis.tar.gz](redis.tar.gz)\n\n# Plan\n\nThe \
+                        plan source\n";
+
+        let metadata = PackageMetadata::from_manifest(42, manifest);
+
+        assert_eq!(metadata.package_id, 42);
+        assert_eq!(metadata.description.as_deref(),
+                   Some("Persistent key-value database, with built-in net interface"));
+        assert_eq!(metadata.upstream_url.as_deref(), Some("http://redis.io/"));
+        assert_eq!(metadata.licenses,
+                   vec!["BSD-3-Clause".to_string(), "MIT".to_string()]);
+        assert_eq!(metadata.maintainer.as_deref(),
+                   Some("The Biome Maintainers <humans@biome.sh>"));
+    }
+
+    #[test]
+    fn metadata_without_upstream_url() {
+        let manifest = "# core / thing\n\n* __Upstream URL__: upstream project's website or \
+                        home page is not defined\n";
+
+        let metadata = PackageMetadata::from_manifest(1, manifest);
+
+        assert_eq!(metadata.description, None);
+        assert_eq!(metadata.upstream_url, None);
+        assert!(metadata.licenses.is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+use super::db_id_format;
+use chrono::NaiveDateTime;
+use diesel::{self,
+             dsl::count_star,
+             pg::PgConnection,
+             result::QueryResult,
+             ExpressionMethods,
+             OptionalExtension,
+             QueryDsl,
+             RunQueryDsl};
+
+use crate::{bldr_core::metrics::CounterMetric,
+            metrics::Counter,
+            schema::retention::{origin_retention_policies,
+                                origin_retention_reports}};
+
+// Releases that were never promoted out of unstable are removed by the rules. The newest
+// `keep_unstable_releases` releases, or the newest one without that rule, are always kept,
+// and with a max age the older ones are only removed once they are past it.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct RetentionPolicy {
+    pub origin:                  String,
+    // Number of unstable only releases kept for each package and target
+    pub keep_unstable_releases:  Option<i32>,
+    // Age in days after which unstable only releases are removed
+    pub unpromoted_max_age_days: Option<i32>,
+    pub created_at:              Option<NaiveDateTime>,
+    pub updated_at:              Option<NaiveDateTime>,
+}
+
+pub struct NewRetentionPolicy<'a> {
+    pub origin:                  &'a str,
+    pub keep_unstable_releases:  Option<i32>,
+    pub unpromoted_max_age_days: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct RetentionReport {
+    #[serde(with = "db_id_format")]
+    pub id:         i64,
+    pub origin:     String,
+    pub deleted:    Vec<String>,
+    // Releases a rule selected that could not be deleted, with the reason
+    pub skipped:    Vec<String>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+pub struct NewRetentionReport<'a> {
+    pub origin:  &'a str,
+    pub deleted: &'a [String],
+    pub skipped: &'a [String],
+}
+
+pub struct ListRetentionReports<'a> {
+    pub origin: &'a str,
+    pub page:   i64,
+    pub limit:  i64,
+}
+
+impl RetentionPolicy {
+    pub fn get(origin: &str, conn: &mut PgConnection) -> QueryResult<Option<RetentionPolicy>> {
+        Counter::DBCall.increment();
+
+        origin_retention_policies::table.filter(origin_retention_policies::origin.eq(origin))
+                                        .first::<RetentionPolicy>(conn)
+                                        .optional()
+    }
+
+    pub fn list(conn: &mut PgConnection) -> QueryResult<Vec<RetentionPolicy>> {
+        Counter::DBCall.increment();
+
+        origin_retention_policies::table.order(origin_retention_policies::origin.asc())
+                                        .get_results(conn)
+    }
+
+    pub fn set(req: &NewRetentionPolicy, conn: &mut PgConnection) -> QueryResult<RetentionPolicy> {
+        Counter::DBCall.increment();
+
+        diesel::insert_into(origin_retention_policies::table).values((
+            origin_retention_policies::origin.eq(req.origin),
+            origin_retention_policies::keep_unstable_releases.eq(req.keep_unstable_releases),
+            origin_retention_policies::unpromoted_max_age_days.eq(req.unpromoted_max_age_days),
+        ))
+        .on_conflict(origin_retention_policies::origin)
+        .do_update()
+        .set((
+            origin_retention_policies::keep_unstable_releases.eq(req.keep_unstable_releases),
+            origin_retention_policies::unpromoted_max_age_days.eq(req.unpromoted_max_age_days),
+            origin_retention_policies::updated_at.eq(diesel::dsl::now),
+        ))
+        .get_result(conn)
+    }
+
+    pub fn delete(origin: &str, conn: &mut PgConnection) -> QueryResult<usize> {
+        Counter::DBCall.increment();
+
+        diesel::delete(
+            origin_retention_policies::table.filter(origin_retention_policies::origin.eq(origin)),
+        )
+        .execute(conn)
+    }
+}
+
+impl RetentionReport {
+    pub fn create(req: &NewRetentionReport,
+                  conn: &mut PgConnection)
+                  -> QueryResult<RetentionReport> {
+        Counter::DBCall.increment();
+
+        diesel::insert_into(origin_retention_reports::table).values((
+            origin_retention_reports::origin.eq(req.origin),
+            origin_retention_reports::deleted.eq(req.deleted),
+            origin_retention_reports::skipped.eq(req.skipped),
+        ))
+        .get_result(conn)
+    }
+
+    pub fn list(req: &ListRetentionReports,
+                conn: &mut PgConnection)
+                -> QueryResult<(Vec<RetentionReport>, i64)> {
+        Counter::DBCall.increment();
+
+        let total_count: i64 =
+            origin_retention_reports::table.filter(origin_retention_reports::origin.eq(req.origin))
+                                           .select(count_star())
+                                           .first(conn)?;
+
+        let reports =
+            origin_retention_reports::table.filter(origin_retention_reports::origin.eq(req.origin))
+                                           .order(origin_retention_reports::created_at.desc())
+                                           .limit(req.limit)
+                                           .offset((req.page - 1) * req.limit)
+                                           .get_results(conn)?;
+
+        Ok((reports, total_count))
+    }
+}
@@ -1,14 +1,19 @@
+use std::collections::BTreeMap;
+
 use super::db_id_format;
 use chrono::NaiveDateTime;
 use diesel::{self,
              dsl::count,
              pg::PgConnection,
              result::QueryResult,
+             Connection,
              ExpressionMethods,
              QueryDsl,
              RunQueryDsl};
 
-use crate::schema::settings::origin_package_settings;
+use crate::schema::settings::{origin_package_labels,
+                              origin_package_settings,
+                              origin_package_settings_labels};
 
 use crate::{models::package::PackageVisibility,
             schema::package::origin_packages};
@@ -132,3 +137,102 @@ impl OriginPackageSettings {
                               .first(conn)
     }
 }
+
+// Free form key/value labels. Package labels apply to every release of an origin package,
+// release labels to one release only and take precedence over the package ones.
+pub struct PackageLabels;
+
+impl PackageLabels {
+    pub fn package(origin: &str,
+                   name: &str,
+                   conn: &mut PgConnection)
+                   -> QueryResult<BTreeMap<String, String>> {
+        Counter::DBCall.increment();
+        let labels: Vec<(String, String)> =
+            origin_package_settings_labels::table
+                .filter(origin_package_settings_labels::origin.eq(origin))
+                .filter(origin_package_settings_labels::name.eq(name))
+                .select((origin_package_settings_labels::key,
+                         origin_package_settings_labels::value))
+                .get_results(conn)?;
+        Ok(labels.into_iter().collect())
+    }
+
+    pub fn release(package_id: i64,
+                   conn: &mut PgConnection)
+                   -> QueryResult<BTreeMap<String, String>> {
+        Counter::DBCall.increment();
+        let labels: Vec<(String, String)> =
+            origin_package_labels::table
+                .filter(origin_package_labels::package_id.eq(package_id))
+                .select((origin_package_labels::key, origin_package_labels::value))
+                .get_results(conn)?;
+        Ok(labels.into_iter().collect())
+    }
+
+    /// The labels of a release merged with the ones of its package.
+    pub fn effective(origin: &str,
+                     name: &str,
+                     package_id: i64,
+                     conn: &mut PgConnection)
+                     -> QueryResult<BTreeMap<String, String>> {
+        let mut labels = Self::package(origin, name, conn)?;
+        labels.extend(Self::release(package_id, conn)?);
+        Ok(labels)
+    }
+
+    /// Replaces the labels of an origin package, its settings have to exist.
+    pub fn set_package(origin: &str,
+                       name: &str,
+                       labels: &BTreeMap<String, String>,
+                       conn: &mut PgConnection)
+                       -> QueryResult<()> {
+        Counter::DBCall.increment();
+        let rows = labels.iter()
+                         .map(|(key, value)| {
+                             (origin_package_settings_labels::origin.eq(origin),
+                              origin_package_settings_labels::name.eq(name),
+                              origin_package_settings_labels::key.eq(key),
+                              origin_package_settings_labels::value.eq(value))
+                         })
+                         .collect::<Vec<_>>();
+
+        conn.transaction::<_, diesel::result::Error, _>(|txn_conn| {
+                diesel::delete(origin_package_settings_labels::table
+                    .filter(origin_package_settings_labels::origin.eq(origin))
+                    .filter(origin_package_settings_labels::name.eq(name)))
+                    .execute(txn_conn)?;
+                if !rows.is_empty() {
+                    diesel::insert_into(origin_package_settings_labels::table).values(&rows)
+                                                                              .execute(txn_conn)?;
+                }
+                Ok(())
+            })
+    }
+
+    /// Replaces the labels of a release.
+    pub fn set_release(package_id: i64,
+                       labels: &BTreeMap<String, String>,
+                       conn: &mut PgConnection)
+                       -> QueryResult<()> {
+        Counter::DBCall.increment();
+        let rows = labels.iter()
+                         .map(|(key, value)| {
+                             (origin_package_labels::package_id.eq(package_id),
+                              origin_package_labels::key.eq(key),
+                              origin_package_labels::value.eq(value))
+                         })
+                         .collect::<Vec<_>>();
+
+        conn.transaction::<_, diesel::result::Error, _>(|txn_conn| {
+                diesel::delete(origin_package_labels::table
+                    .filter(origin_package_labels::package_id.eq(package_id)))
+                    .execute(txn_conn)?;
+                if !rows.is_empty() {
+                    diesel::insert_into(origin_package_labels::table).values(&rows)
+                                                                     .execute(txn_conn)?;
+                }
+                Ok(())
+            })
+    }
+}
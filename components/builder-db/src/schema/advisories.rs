@@ -0,0 +1,28 @@
+table! {
+    advisories (id) {
+        id -> Text,
+        summary -> Nullable<Text>,
+        details -> Nullable<Text>,
+        aliases -> Array<Text>,
+        severity -> Nullable<Text>,
+        published -> Nullable<Timestamptz>,
+        modified -> Nullable<Timestamptz>,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    advisory_ranges (advisory_id) {
+        advisory_id -> Text,
+        package -> Text,
+        introduced -> Nullable<Text>,
+        fixed -> Nullable<Text>,
+        last_affected -> Nullable<Text>,
+        versions -> Array<Text>,
+    }
+}
+
+joinable!(advisory_ranges -> advisories (advisory_id));
+
+allow_tables_to_appear_in_same_query!(advisory_ranges, advisories);
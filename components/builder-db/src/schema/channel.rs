@@ -6,6 +6,9 @@ table! {
         created_at -> Nullable<Timestamptz>,
         updated_at -> Nullable<Timestamptz>,
         origin -> Text,
+        expires_at -> Nullable<Timestamptz>,
+        description -> Nullable<Text>,
+        labels -> Array<Text>,
     }
 }
 
@@ -18,6 +21,15 @@ table! {
     }
 }
 
+table! {
+    origin_channel_members (channel_id, member_channel_id) {
+        channel_id -> BigInt,
+        member_channel_id -> BigInt,
+        precedence -> Integer,
+        created_at -> Nullable<Timestamptz>,
+    }
+}
+
 use super::{origin::origins,
             package::{origin_packages,
                       origin_packages_with_version_array}};
@@ -26,8 +38,10 @@ joinable!(origin_channel_packages -> origin_packages (package_id));
 joinable!(origin_channel_packages -> origin_packages_with_version_array (package_id));
 joinable!(origin_channel_packages -> origin_channels (channel_id));
 joinable!(origin_channels -> origins (origin));
+joinable!(origin_channel_members -> origin_channels (member_channel_id));
 
 allow_tables_to_appear_in_same_query!(origin_channels,
+                                      origin_channel_members,
                                       origin_channel_packages,
                                       origin_packages,
                                       origin_packages_with_version_array,
@@ -0,0 +1,13 @@
+table! {
+    origin_package_downloads (package_id, day) {
+        package_id -> BigInt,
+        day -> Date,
+        count -> Integer,
+    }
+}
+
+use super::package::origin_packages;
+
+joinable!(origin_package_downloads -> origin_packages (package_id));
+
+allow_tables_to_appear_in_same_query!(origin_package_downloads, origin_packages);
@@ -3,8 +3,10 @@
 #![allow(proc_macro_derive_resolution_fallback)]
 
 pub mod account;
+pub mod advisories;
 pub mod audit;
 pub mod channel;
+pub mod downloads;
 pub mod integration;
 pub mod invitation;
 pub mod key;
@@ -12,8 +14,12 @@ pub mod license_keys;
 pub mod member;
 pub mod origin;
 pub mod package;
+pub mod package_binds;
+pub mod package_files;
+pub mod package_metadata;
 pub mod project;
 pub mod project_integration;
+pub mod retention;
 pub mod secrets;
 pub mod settings;
 pub mod sql_types;
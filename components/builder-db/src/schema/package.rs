@@ -1,5 +1,5 @@
 table! {
-    use crate::schema::sql_types::OriginPackageVisibility;
+    use crate::schema::sql_types::{OriginPackageStatus, OriginPackageVisibility};
     use diesel::sql_types::{Array, BigInt, Integer, Text, Nullable, Timestamptz};
     packages_with_channel_platform {
         id -> BigInt,
@@ -20,13 +20,14 @@ table! {
         created_at -> Nullable<Timestamptz>,
         updated_at -> Nullable<Timestamptz>,
         origin -> Text,
+        status -> OriginPackageStatus,
         channels -> Array<Text>,
         platforms -> Array<Text>,
     }
 }
 
 table! {
-    use crate::schema::sql_types::OriginPackageVisibility;
+    use crate::schema::sql_types::{OriginPackageStatus, OriginPackageVisibility};
     use diesel::sql_types::{Array, BigInt, Integer, Text,  Nullable, Timestamptz};
     origin_packages_with_version_array {
         id -> BigInt,
@@ -49,11 +50,13 @@ table! {
         build_tdeps -> Array<Text>,
         version_array -> Array<Nullable<Text>>,
         package_type -> Text,
+        status -> OriginPackageStatus,
+        status_reason -> Nullable<Text>,
     }
 }
 
 table! {
-    use crate::schema::sql_types::OriginPackageVisibility;
+    use crate::schema::sql_types::{OriginPackageStatus, OriginPackageVisibility};
     use diesel::sql_types::{Array, BigInt, Bool, Integer, Text, Nullable, Timestamptz};
     use diesel_full_text_search::TsVector;
     origin_packages {
@@ -78,18 +81,20 @@ table! {
         ident_vector -> TsVector,
         package_type -> Text,
         hidden -> Bool,
+        status -> OriginPackageStatus,
+        status_reason -> Nullable<Text>,
     }
 }
 
 table! {
     use crate::schema::sql_types::OriginPackageVisibility;
-    use diesel::sql_types::{Array, BigInt, Text};
+    use diesel::sql_types::{Array, BigInt, Nullable, Text};
     origin_package_versions (origin, name) {
         origin -> Text,
         name -> Text,
         version -> Text,
         release_count -> BigInt,
-        latest -> Text,
+        latest -> Nullable<Text>,
         platforms -> Array<Text>,
         visibility -> OriginPackageVisibility,
     }
@@ -0,0 +1,25 @@
+table! {
+    origin_package_exports (package_id, name) {
+        package_id -> BigInt,
+        name -> Text,
+        config_key -> Text,
+    }
+}
+
+table! {
+    origin_package_binds (package_id, name) {
+        package_id -> BigInt,
+        name -> Text,
+        exports -> Array<Text>,
+        optional -> Bool,
+    }
+}
+
+use super::package::origin_packages;
+
+joinable!(origin_package_exports -> origin_packages (package_id));
+joinable!(origin_package_binds -> origin_packages (package_id));
+
+allow_tables_to_appear_in_same_query!(origin_package_exports,
+                                      origin_package_binds,
+                                      origin_packages);
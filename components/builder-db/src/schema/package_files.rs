@@ -0,0 +1,22 @@
+table! {
+    origin_package_files (package_id) {
+        package_id -> BigInt,
+        files -> Array<Text>,
+        created_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    origin_package_file_paths (package_id, path) {
+        package_id -> BigInt,
+        path -> Text,
+    }
+}
+
+use super::package::origin_packages;
+
+joinable!(origin_package_files -> origin_packages (package_id));
+joinable!(origin_package_file_paths -> origin_packages (package_id));
+
+allow_tables_to_appear_in_same_query!(origin_package_files, origin_packages);
+allow_tables_to_appear_in_same_query!(origin_package_file_paths, origin_packages);
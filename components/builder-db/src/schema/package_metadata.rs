@@ -0,0 +1,15 @@
+table! {
+    origin_package_metadata (package_id) {
+        package_id -> BigInt,
+        description -> Nullable<Text>,
+        upstream_url -> Nullable<Text>,
+        licenses -> Array<Text>,
+        maintainer -> Nullable<Text>,
+    }
+}
+
+use super::package::origin_packages;
+
+joinable!(origin_package_metadata -> origin_packages (package_id));
+
+allow_tables_to_appear_in_same_query!(origin_package_metadata, origin_packages);
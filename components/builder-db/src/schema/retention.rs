@@ -0,0 +1,27 @@
+table! {
+    origin_retention_policies (origin) {
+        origin -> Text,
+        keep_unstable_releases -> Nullable<Integer>,
+        unpromoted_max_age_days -> Nullable<Integer>,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    origin_retention_reports (id) {
+        id -> BigInt,
+        origin -> Text,
+        deleted -> Array<Text>,
+        skipped -> Array<Text>,
+        created_at -> Nullable<Timestamptz>,
+    }
+}
+
+use super::origin::origins;
+
+joinable!(origin_retention_policies -> origins (origin));
+joinable!(origin_retention_reports -> origins (origin));
+
+allow_tables_to_appear_in_same_query!(origin_retention_policies, origins);
+allow_tables_to_appear_in_same_query!(origin_retention_reports, origins);
@@ -13,3 +13,31 @@ table! {
         updated_at -> Nullable<Timestamptz>,
     }
 }
+
+table! {
+    origin_package_settings_labels (origin, name, key) {
+        origin -> Text,
+        name -> Text,
+        key -> Text,
+        value -> Text,
+    }
+}
+
+table! {
+    origin_package_labels (package_id, key) {
+        package_id -> BigInt,
+        key -> Text,
+        value -> Text,
+    }
+}
+
+use super::package::{origin_packages,
+                     packages_with_channel_platform};
+
+joinable!(origin_package_labels -> origin_packages (package_id));
+
+allow_tables_to_appear_in_same_query!(origin_package_labels, origin_packages);
+allow_tables_to_appear_in_same_query!(origin_package_labels, packages_with_channel_platform);
+allow_tables_to_appear_in_same_query!(origin_package_settings_labels, origin_packages);
+allow_tables_to_appear_in_same_query!(origin_package_settings_labels,
+                                      packages_with_channel_platform);
@@ -6,6 +6,11 @@ use diesel::{query_builder::QueryId,
 #[diesel(postgres_type(name = "origin_package_visibility"))]
 pub struct OriginPackageVisibility;
 
+/// Backing Postgres enum for PackageStatus
+#[derive(SqlType, QueryId)]
+#[diesel(postgres_type(name = "origin_package_status"))]
+pub struct OriginPackageStatus;
+
 /// Backing Postgres enum for PackageChannelOperation
 #[derive(SqlType, QueryId)]
 #[diesel(postgres_type(name = "package_channel_operation"))]
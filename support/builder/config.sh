@@ -32,6 +32,8 @@ log_level = "debug,tokio_core=error,tokio_reactor=error,zmq=error,hyper=error"
 
 [api]
 allowed_users_for_origin_create = ['bobo', 'mystique', 'wesker', 'lkennedy']
+targets = ['x86_64-linux', 'aarch64-linux', 'x86_64-linux-kernel2', 'x86_64-windows']
+default_target = 'x86_64-linux'
 
 [http]
 handler_count = 15
@@ -459,6 +459,30 @@ describe('Working with packages', function () {
         });
     });
 
+    it('only finds the releases carrying the labels searched for', function (done) {
+      request.put(`/settings/neurosis/testapp2/v1.2.3-master/${release5}/labels`)
+        .set('Authorization', global.boboBearer)
+        .send({ labels: { tier: 'critical' } })
+        .expect(200)
+        .end(function (err) {
+          if (err) {
+            return done(err);
+          }
+          request.get('/depot/pkgs/search/testapp2%20label%3Atier%3Dcritical')
+            .type('application/json')
+            .accept('application/json')
+            .expect(200)
+            .end(function (err, res) {
+              expect(res.body.total_count).to.equal(1);
+              expect(res.body.data.length).to.equal(1);
+              expect(res.body.data[0].name).to.equal('testapp2');
+              expect(res.body.data[0].version).to.equal('v1.2.3-master');
+              expect(res.body.data[0].release).to.equal(release5);
+              done(err);
+            });
+        });
+    });
+
     it('allows me to search for distinct packages', function (done) {
       request.get('/depot/pkgs/search/testapp2?distinct=true')
         .type('application/json')